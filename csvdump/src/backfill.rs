@@ -0,0 +1,188 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Splitting of historical exports into date partitions
+//!
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use std::path::{Path, PathBuf};
+
+///
+/// Length of a single backfill partition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Granularity {
+    Day,
+    Month,
+    Year,
+}
+
+impl Granularity {
+    ///
+    /// Parses a granularity name as accepted on the command line
+    pub fn parse(name: &str) -> Option<Granularity> {
+        match name {
+            "day" => Some(Granularity::Day),
+            "month" => Some(Granularity::Month),
+            "year" => Some(Granularity::Year),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the first day of the partition following the one starting at `date`
+    fn next(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date.succ_opt().unwrap(),
+            Granularity::Month if date.month() == 12 => {
+                NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap()
+            }
+            Granularity::Month => {
+                NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1).unwrap()
+            }
+            Granularity::Year => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap(),
+        }
+    }
+
+    ///
+    /// Gets the first day of the partition containing `date`
+    fn truncate(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Month => date.with_day(1).unwrap(),
+            Granularity::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap(),
+        }
+    }
+
+    ///
+    /// Formats the label identifying the partition starting at `date`
+    fn label(self, date: NaiveDate) -> String {
+        match self {
+            Granularity::Day => date.format("%Y-%m-%d").to_string(),
+            Granularity::Month => date.format("%Y-%m").to_string(),
+            Granularity::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+///
+/// A half-open date range exported into its own file
+#[derive(Debug)]
+pub struct Partition {
+    /// label used in file names and messages
+    label: String,
+    /// first day of the partition
+    start: NaiveDate,
+    /// first day after the partition
+    end: NaiveDate,
+}
+
+impl Partition {
+    ///
+    /// Gets the label of the partition
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    ///
+    /// Gets the inclusive start of the partition
+    pub fn start(&self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&self.start.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    ///
+    /// Gets the exclusive end of the partition
+    pub fn end(&self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&self.end.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    ///
+    /// Inserts the partition label in front of the file extension,
+    /// e.g. `orders.csv.gz` becomes `orders_2015-01.csv.gz`
+    pub fn file_path(&self, path: &Path) -> PathBuf {
+        labelled_path(path, &self.label)
+    }
+}
+
+///
+/// Inserts a label in front of the file extension of a path
+pub fn labelled_path(path: &Path, label: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let labelled = match file_name.find('.') {
+        Some(idx) => format!("{}_{}{}", &file_name[..idx], label, &file_name[idx..]),
+        None => format!("{}_{}", file_name, label),
+    };
+
+    path.with_file_name(labelled)
+}
+
+///
+/// Parses a date given as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|p| p.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+
+    match numbers.as_slice() {
+        [year] if parts[0].len() == 4 => NaiveDate::from_ymd_opt(*year as i32, 1, 1),
+        [year, month] => NaiveDate::from_ymd_opt(*year as i32, *month, 1),
+        [year, month, day] => NaiveDate::from_ymd_opt(*year as i32, *month, *day),
+        _ => None,
+    }
+}
+
+///
+/// Splits the range from the partition containing `from` up to and
+/// including the partition containing `to` into consecutive partitions
+pub fn partitions(
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: Granularity,
+) -> Result<Vec<Partition>, String> {
+    let mut start = granularity.truncate(from);
+    let last = granularity.truncate(to);
+    if start > last {
+        return Err(format!("Backfill start {} is after end {}", from, to));
+    }
+
+    let mut result = Vec::new();
+    while start <= last {
+        let end = granularity.next(start);
+        result.push(Partition {
+            label: granularity.label(start),
+            start,
+            end,
+        });
+        start = end;
+    }
+
+    Ok(result)
+}
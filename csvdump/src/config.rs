@@ -28,39 +28,519 @@
 //! Configuration for accessing database
 //!
 
+use crate::masking::MaskStrategy;
+use crate::naming::NamingStrategy;
+use crate::validation::ValidationRule;
+use lib_oradb::definition::SessionOptions;
 use oracle::Connection;
+use std::collections::BTreeMap;
 use std::fs::read_to_string;
 use std::path::Path;
+use std::time::Duration;
 use toml::from_str;
+use toml::value::{Table, Value};
+
+/// default port of the Oracle listener
+const DEFAULT_PORT: u16 = 1521;
 
 ///
 /// Database configuration
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
-    dbhost: String,
-    dbname: String,
-    dbuser: String,
-    dbpass: String,
+    /// database host; not needed with `connect_string`
+    #[serde(default)]
+    dbhost: Option<String>,
+    /// service name of the database
+    #[serde(default)]
+    dbname: Option<String>,
+    /// database user; may also be given on the command line
+    #[serde(default)]
+    dbuser: Option<String>,
+    /// password of the database user
+    #[serde(default)]
+    dbpass: Option<String>,
+    /// listener port if not the default
+    #[serde(default)]
+    port: Option<u16>,
+    /// service name, taking precedence over `dbname`
+    #[serde(default)]
+    service_name: Option<String>,
+    /// system identifier to connect to instead of a service
+    #[serde(default)]
+    sid: Option<String>,
+    /// full connect string or TNS descriptor overriding host, port and service
+    #[serde(default)]
+    connect_string: Option<String>,
+    /// seconds to wait for the listener when connecting
+    #[serde(default)]
+    connect_timeout: Option<u32>,
+    /// statements run right after connecting, e.g. `ALTER SESSION`
+    #[serde(default)]
+    session_init: Vec<String>,
+    /// whether queries run in a read-only transaction
+    #[serde(default)]
+    read_only: bool,
+    /// whether query results are staged in a temporary table before fetching
+    #[serde(default)]
+    materialize: bool,
+    /// seconds after which the database cancels a single call
+    #[serde(default)]
+    call_timeout: Option<u64>,
+    /// owner to use when a table name exists for several owners
+    #[serde(default)]
+    default_owner: Option<String>,
+    /// masking strategies by column name or by `TABLE.COLUMN`
+    #[serde(default)]
+    masking: BTreeMap<String, MaskStrategy>,
+    /// validation rules by column name
+    #[serde(default)]
+    validation: BTreeMap<String, Vec<ValidationRule>>,
+    /// values written instead of NULL, by column name
+    #[serde(default)]
+    defaults: BTreeMap<String, String>,
+    /// SQL expressions exported as additional columns, by `COLNAME[:TYPE]`
+    #[serde(default)]
+    computed: BTreeMap<String, String>,
+    /// rules for deriving table names from input file names
+    #[serde(default)]
+    naming: NamingStrategy,
+    /// command run after a successful export
+    #[serde(default)]
+    post_success: Option<String>,
+    /// command run after a failed export
+    #[serde(default)]
+    post_failure: Option<String>,
+    /// private key for delivering output over SFTP
+    #[serde(default)]
+    sftp_identity_file: Option<String>,
+}
+
+///
+/// Connection settings from the command line, taking precedence
+/// over the configuration file and the environment
+#[derive(Default)]
+pub struct ConnectionOverrides {
+    pub dbhost: Option<String>,
+    pub port: Option<u16>,
+    pub service_name: Option<String>,
+    pub sid: Option<String>,
+    pub dbuser: Option<String>,
+    pub dbpass: Option<String>,
+    pub connect_string: Option<String>,
+}
+
+/// prefix of environment variables named after configuration keys
+const ENV_PREFIX: &str = "CSVDUMP_";
+
+/// configuration keys taking text verbatim from the environment
+const ENV_TEXT_KEYS: [&str; 11] = [
+    "dbhost",
+    "dbname",
+    "dbuser",
+    "dbpass",
+    "service_name",
+    "sid",
+    "connect_string",
+    "default_owner",
+    "post_success",
+    "post_failure",
+    "sftp_identity_file",
+];
+
+/// configuration keys taking a TOML value from the environment,
+/// e.g. `CSVDUMP_READ_ONLY=true` or `CSVDUMP_DEFAULTS='{ STATUS = "N/A" }'`
+const ENV_VALUE_KEYS: [&str; 11] = [
+    "port",
+    "connect_timeout",
+    "session_init",
+    "read_only",
+    "materialize",
+    "call_timeout",
+    "masking",
+    "validation",
+    "defaults",
+    "computed",
+    "naming",
+];
+
+///
+/// Problems found in a configuration, reported all at once
+#[derive(Debug)]
+pub struct ConfigProblems(Vec<String>);
+
+impl std::fmt::Display for ConfigProblems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} problem(s) found", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigProblems {}
+
+///
+/// Counts the single character edits turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+///
+/// Describes a key that is not a configuration key, suggesting
+/// the closest known key for typos
+fn unknown_key(key: &str) -> String {
+    let closest = ENV_TEXT_KEYS
+        .iter()
+        .chain(ENV_VALUE_KEYS.iter())
+        .map(|k| (edit_distance(key, k), k))
+        .min();
+
+    match closest {
+        Some((distance, k)) if distance <= 2 => {
+            format!("unknown key `{}`; did you mean `{}`?", key, k)
+        }
+        _ => format!("unknown key `{}`", key),
+    }
+}
+
+///
+/// Checks each key of a parsed configuration on its own, so that
+/// all problems are found rather than just the first
+fn check_table(table: &Table) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        let known = ENV_TEXT_KEYS
+            .iter()
+            .chain(ENV_VALUE_KEYS.iter())
+            .any(|k| k == key);
+        if !known {
+            problems.push(unknown_key(key));
+            continue;
+        }
+
+        let mut single = Table::new();
+        single.insert(key.clone(), value.clone());
+        match Value::Table(single).try_into::<Config>() {
+            Ok(config) => problems.extend(config.validate()),
+            Err(e) => problems.push(e.to_string()),
+        }
+    }
+
+    problems
+}
+
+///
+/// Deserializes a parsed configuration after checking it
+fn from_table(table: Table) -> Result<Config, Box<dyn std::error::Error>> {
+    let problems = check_table(&table);
+    if !problems.is_empty() {
+        return Err(Box::new(ConfigProblems(problems)));
+    }
+
+    Ok(Value::Table(table).try_into()?)
+}
+
+///
+/// Gets the name of the environment variable for a configuration
+/// key, e.g. `CSVDUMP_DBHOST` for `dbhost`
+fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.to_uppercase())
+}
+
+///
+/// Replaces keys of a parsed configuration with values of their
+/// environment variables; empty variables count as unset
+fn apply_env(table: &mut Table) -> Result<(), String> {
+    let value = |key: &str| std::env::var(env_name(key)).ok().filter(|v| !v.is_empty());
+
+    for key in ENV_TEXT_KEYS.iter() {
+        if let Some(v) = value(key) {
+            table.insert(String::from(*key), Value::String(v));
+        }
+    }
+    for key in ENV_VALUE_KEYS.iter() {
+        if let Some(v) = value(key) {
+            let parsed = from_str::<Table>(&format!("value = {}", v))
+                .ok()
+                .and_then(|mut t| t.remove("value"))
+                .ok_or_else(|| format!("{} is not a valid TOML value", env_name(key)))?;
+            table.insert(String::from(*key), parsed);
+        }
+    }
+
+    Ok(())
 }
 
 impl Config {
+    ///
+    /// Gets the connect string; a configured `connect_string` is used
+    /// verbatim, otherwise an easy connect string is built, or a TNS
+    /// descriptor if a SID or connect timeout is needed
+    pub fn connect_string(&self) -> Result<String, String> {
+        if let Some(connect_string) = &self.connect_string {
+            return Ok(connect_string.clone());
+        }
+
+        let host = self
+            .dbhost
+            .as_deref()
+            .ok_or("Either dbhost or connect_string must be configured")?;
+        let service = self.service_name.as_ref().or(self.dbname.as_ref());
+        let connect_data = match (&self.sid, service) {
+            (Some(_), Some(_)) => {
+                return Err(String::from(
+                    "Configure either sid or service_name/dbname, not both",
+                ))
+            }
+            (Some(sid), None) => format!("(SID={})", sid),
+            (None, Some(service)) => format!("(SERVICE_NAME={})", service),
+            (None, None) => {
+                return Err(String::from(
+                    "One of service_name, dbname or sid must be configured",
+                ))
+            }
+        };
+
+        match (&self.sid, self.connect_timeout, service) {
+            (None, None, Some(service)) => Ok(match self.port {
+                Some(port) => format!("//{}:{}/{}", host, port, service),
+                None => format!("//{}/{}", host, service),
+            }),
+            _ => Ok(format!(
+                "(DESCRIPTION={}(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA={}))",
+                self.connect_timeout
+                    .map(|t| format!("(CONNECT_TIMEOUT={})", t))
+                    .unwrap_or_default(),
+                host,
+                self.port.unwrap_or(DEFAULT_PORT),
+                connect_data
+            )),
+        }
+    }
+
     ///
     /// Connects to database via specified credentials
-    pub fn connect(self) -> Result<Connection, oracle::Error> {
-        Connection::connect(
-            &self.dbuser,
-            &self.dbpass,
-            format!("//{}/{}", self.dbhost, self.dbname),
-        )
+    pub fn connect(&self) -> Result<Connection, Box<dyn std::error::Error>> {
+        let user = self.dbuser.as_deref().ok_or("dbuser must be configured")?;
+        let password = self.dbpass.as_deref().ok_or("dbpass must be configured")?;
+
+        Ok(Connection::connect(user, password, self.connect_string()?)?)
+    }
+
+    ///
+    /// Replaces settings with those given as overrides; a host or
+    /// service given on its own also replaces a configured connect string
+    pub fn apply(&mut self, overrides: ConnectionOverrides) {
+        if overrides.connect_string.is_some() {
+            self.connect_string = overrides.connect_string;
+        } else if overrides.dbhost.is_some()
+            || overrides.service_name.is_some()
+            || overrides.sid.is_some()
+        {
+            self.connect_string = None;
+        }
+        if overrides.service_name.is_some() || overrides.sid.is_some() {
+            // a service and a SID exclude each other
+            self.service_name = overrides.service_name;
+            self.sid = overrides.sid;
+            self.dbname = None;
+        }
+        self.dbhost = overrides.dbhost.or(self.dbhost.take());
+        self.port = overrides.port.or(self.port);
+        self.dbuser = overrides.dbuser.or(self.dbuser.take());
+        self.dbpass = overrides.dbpass.or(self.dbpass.take());
     }
 
+    ///
+    /// Gets the session setup run right after connecting
+    pub fn session_options(&self) -> SessionOptions {
+        let mut options = self
+            .session_init
+            .iter()
+            .fold(SessionOptions::new(), |o, s| o.statement(s))
+            .read_only(self.read_only);
+        if let Some(seconds) = self.call_timeout {
+            options = options.call_timeout(Duration::from_secs(seconds));
+        }
+
+        options
+    }
+
+    ///
+    /// Gets configured owner for ambiguous table names
+    pub fn default_owner(&self) -> Option<&str> {
+        self.default_owner.as_deref()
+    }
+
+    ///
+    /// Gets configured rules for deriving table names
+    pub fn naming(&self) -> &NamingStrategy {
+        &self.naming
+    }
+
+    ///
+    /// Gets configured masking strategies by column name or `TABLE.COLUMN`
+    pub fn masking(&self) -> &BTreeMap<String, MaskStrategy> {
+        &self.masking
+    }
+
+    ///
+    /// Gets configured validation rules by column name
+    pub fn validation(&self) -> &BTreeMap<String, Vec<ValidationRule>> {
+        &self.validation
+    }
+
+    ///
+    /// Gets configured NULL replacements by column name
+    pub fn defaults(&self) -> &BTreeMap<String, String> {
+        &self.defaults
+    }
+
+    ///
+    /// Gets whether query results are staged before they are fetched
+    pub fn materialize(&self) -> bool {
+        self.materialize
+    }
+    ///
+    /// Gets configured computed column expressions by column name
+    pub fn computed(&self) -> &BTreeMap<String, String> {
+        &self.computed
+    }
+
+    ///
+    /// Gets the command run after a successful export, with placeholders
+    pub fn post_success(&self) -> Option<&str> {
+        self.post_success.as_deref()
+    }
+
+    ///
+    /// Gets the command run after a failed export, with placeholders
+    pub fn post_failure(&self) -> Option<&str> {
+        self.post_failure.as_deref()
+    }
+
+    ///
+    /// Gets the private key file for SFTP delivery, if not the default
+    pub fn sftp_identity_file(&self) -> Option<&str> {
+        self.sftp_identity_file.as_deref()
+    }
+
+    ///
+    /// Loads a configuration file with `CSVDUMP_` environment
+    /// variables overriding the keys they are named after
     pub fn load(filename: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let mut table = Config::read_table(filename)?;
+        apply_env(&mut table)?;
+
+        from_table(table)
+    }
+
+    ///
+    /// Loads a configuration file as is, e.g. for a second database
+    /// the environment does not describe
+    pub fn load_file(filename: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        from_table(Config::read_table(filename)?)
+    }
+
+    ///
+    /// Builds a configuration from `CSVDUMP_` environment variables alone
+    pub fn from_env() -> Result<Config, Box<dyn std::error::Error>> {
+        let mut table = Table::new();
+        apply_env(&mut table)?;
+
+        from_table(table)
+    }
+
+    ///
+    /// Builds a configuration from settings collected otherwise, e.g.
+    /// by the setup wizard, checking them like a configuration file
+    pub fn from_settings(table: Table) -> Result<Config, Box<dyn std::error::Error>> {
+        from_table(table)
+    }
+
+    ///
+    /// Checks the values that are set, e.g. for empty names
+    /// or timeouts of zero seconds
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let texts = [
+            ("dbhost", &self.dbhost),
+            ("dbname", &self.dbname),
+            ("dbuser", &self.dbuser),
+            ("service_name", &self.service_name),
+            ("sid", &self.sid),
+            ("connect_string", &self.connect_string),
+            ("default_owner", &self.default_owner),
+            ("post_success", &self.post_success),
+            ("post_failure", &self.post_failure),
+            ("sftp_identity_file", &self.sftp_identity_file),
+        ];
+        for (key, value) in texts.iter() {
+            if value.as_ref().is_some_and(|v| v.trim().is_empty()) {
+                problems.push(format!("{} must not be empty", key));
+            }
+        }
+        if self.port == Some(0) {
+            problems.push(String::from("port must be between 1 and 65535"));
+        }
+        if self.connect_timeout == Some(0) {
+            problems.push(String::from("connect_timeout must be at least 1 second"));
+        }
+        if self.call_timeout == Some(0) {
+            problems.push(String::from("call_timeout must be at least 1 second"));
+        }
+        for (column, expression) in self.computed.iter() {
+            if column.trim().is_empty() || expression.trim().is_empty() {
+                problems.push(String::from(
+                    "computed columns need a name and an expression",
+                ));
+            }
+        }
+        if self.session_init.iter().any(|s| s.trim().is_empty()) {
+            problems.push(String::from(
+                "session_init must not contain empty statements",
+            ));
+        }
+
+        problems
+    }
+
+    ///
+    /// Lists what is missing to connect, e.g. after command line
+    /// settings have been applied
+    pub fn check_connection(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.connect_string() {
+            problems.push(e);
+        }
+        if self.dbuser.is_none() {
+            problems.push(String::from("dbuser must be configured"));
+        }
+        if self.dbpass.is_none() {
+            problems.push(String::from("dbpass must be configured"));
+        }
+
+        problems
+    }
+
+    fn read_table(filename: &Path) -> Result<Table, Box<dyn std::error::Error>> {
         if !filename.exists() {
             eprintln!("File {} not found.", filename.to_string_lossy());
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "File not found",
-            )));
+            return Err(Box::new(std::io::Error::other("File not found")));
         }
 
         let contents = read_to_string(filename)?;
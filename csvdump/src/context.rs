@@ -0,0 +1,125 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! State of a run shared by all exports of it
+//!
+
+use crate::hooks::PostRunHooks;
+use crate::metrics::RunMetrics;
+use std::sync::{Mutex, MutexGuard};
+
+///
+/// Settings and totals of a run; passed to every export of the run,
+/// which may run on several threads at once
+#[derive(Default)]
+pub struct RunContext {
+    /// set if progress messages are suppressed
+    quiet: bool,
+    /// set if errors are reported as JSON objects
+    json_errors: bool,
+    /// totals of the run if `--metrics-file` is given
+    metrics: Mutex<Option<RunMetrics>>,
+    /// commands run after the export if configured
+    hooks: Mutex<Option<PostRunHooks>>,
+}
+
+///
+/// Locks a mutex, also after another thread panicked while holding it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl RunContext {
+    ///
+    /// Creates a `RunContext` without metrics and hooks
+    pub fn new(quiet: bool, json_errors: bool) -> RunContext {
+        RunContext {
+            quiet,
+            json_errors,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Gets whether progress messages are suppressed
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    ///
+    /// Gets whether errors are reported as JSON objects
+    pub fn json_errors(&self) -> bool {
+        self.json_errors
+    }
+
+    ///
+    /// Collects the totals of the run into `metrics`
+    pub fn set_metrics(&self, metrics: RunMetrics) {
+        *lock(&self.metrics) = Some(metrics);
+    }
+
+    ///
+    /// Sets the commands run once the run ends
+    pub fn set_hooks(&self, hooks: PostRunHooks) {
+        *lock(&self.hooks) = Some(hooks);
+    }
+
+    ///
+    /// Sets the table passed to the commands run once the run ends
+    pub fn set_table(&self, table: &str) {
+        if let Some(hooks) = lock(&self.hooks).as_mut() {
+            hooks.set_table(table);
+        }
+    }
+
+    ///
+    /// Adds a complete export of `rows` rows and `bytes` bytes to the totals
+    pub fn add_export(&self, rows: u64, bytes: u64) {
+        if let Some(metrics) = lock(&self.metrics).as_mut() {
+            metrics.add_export(rows, bytes);
+        }
+        if let Some(hooks) = lock(&self.hooks).as_mut() {
+            hooks.add_rows(rows);
+        }
+    }
+
+    ///
+    /// Writes the metrics file, if requested, for a run ending with an
+    /// exit code
+    pub fn write_metrics(&self, exit_code: i32) -> std::io::Result<()> {
+        match lock(&self.metrics).as_ref() {
+            Some(metrics) => metrics.write(exit_code),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Takes the commands run once the run ends, so that they run once
+    pub fn take_hooks(&self) -> Option<PostRunHooks> {
+        lock(&self.hooks).take()
+    }
+}
@@ -0,0 +1,186 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Control file describing a completed export
+//!
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::read::MultiGzDecoder;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+///
+/// Summary of an export written next to the output file
+#[derive(Serialize, Deserialize)]
+pub struct ControlFile {
+    /// exported table
+    table: String,
+    /// output file name
+    output_file: String,
+    /// number of rows written
+    rows: u64,
+    /// whether the output file starts with a header row
+    #[serde(default = "default_header")]
+    header: bool,
+    /// size of the output file in bytes
+    bytes: u64,
+    /// hex encoded SHA-256 of the output file
+    sha256: String,
+    /// start of export
+    started: String,
+    /// end of export
+    finished: String,
+    /// system change number the exported data is consistent with
+    scn: Option<u64>,
+    /// query used to read the data
+    query: String,
+}
+
+///
+/// Calculates SHA-256 and size of a file
+fn checksum(filename: &Path) -> std::io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let bytes = std::io::copy(&mut File::open(filename)?, &mut hasher)?;
+    let digest = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    Ok((digest, bytes))
+}
+
+fn default_header() -> bool {
+    true
+}
+
+///
+/// Counts data records of a plain or gzip compressed csv file
+pub fn count_records(
+    filename: &Path,
+    delimiter: u8,
+    header: bool,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut magic = [0u8; 2];
+    let is_gzip = File::open(filename)?.read(&mut magic)? == 2 && magic == [0x1f, 0x8b];
+
+    let file = BufReader::new(File::open(filename)?);
+    let input: Box<dyn Read> = if is_gzip {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(header)
+        .from_reader(input);
+    let mut record = csv::ByteRecord::new();
+    let mut rows: u64 = 0;
+    while reader.read_byte_record(&mut record)? {
+        rows += 1;
+    }
+
+    Ok(rows)
+}
+
+impl ControlFile {
+    ///
+    /// Creates control file data for a completed export
+    pub fn new(
+        table: &str,
+        output_file: &Path,
+        rows: u64,
+        header: bool,
+        started: DateTime<Utc>,
+        scn: Option<u64>,
+        query: String,
+    ) -> std::io::Result<ControlFile> {
+        let (sha256, bytes) = checksum(output_file)?;
+
+        Ok(ControlFile {
+            table: String::from(table),
+            output_file: output_file.to_string_lossy().to_string(),
+            rows,
+            header,
+            bytes,
+            sha256,
+            started: started.to_rfc3339_opts(SecondsFormat::Secs, true),
+            finished: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            scn,
+            query,
+        })
+    }
+
+    ///
+    /// Loads a control file written by a previous export
+    pub fn load(filename: &Path) -> Result<ControlFile, Box<dyn std::error::Error>> {
+        Ok(toml::from_str(&std::fs::read_to_string(filename)?)?)
+    }
+
+    ///
+    /// Compares an output file with the recorded export and
+    /// returns a description of each mismatch
+    pub fn verify(
+        &self,
+        output_file: &Path,
+        delimiter: u8,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut mismatches: Vec<String> = Vec::new();
+
+        let (sha256, bytes) = checksum(output_file)?;
+        if bytes != self.bytes {
+            mismatches.push(format!("size is {} bytes, expected {}", bytes, self.bytes));
+        }
+        if sha256 != self.sha256 {
+            mismatches.push(format!("SHA-256 is {}, expected {}", sha256, self.sha256));
+        }
+
+        let rows = count_records(output_file, delimiter, self.header)?;
+        if rows != self.rows {
+            mismatches.push(format!("contains {} rows, expected {}", rows, self.rows));
+        }
+
+        Ok(mismatches)
+    }
+
+    ///
+    /// Gets output file name recorded at export
+    pub fn output_file(&self) -> &str {
+        &self.output_file
+    }
+
+    ///
+    /// Writes control file as toml
+    pub fn write(&self, filename: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(filename, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Client-side removal of duplicate rows
+//!
+
+use crate::rowhash::RowHasher;
+use lib_oradb::definition::ColumnValue;
+use std::collections::HashSet;
+
+///
+/// Drops rows whose key columns repeat values of an earlier row.
+/// Only digests of the keys are kept, so memory grows with the number
+/// of distinct keys but not with the size of the key values.
+pub struct Deduplicator {
+    /// positions of the key columns
+    key_columns: Vec<usize>,
+    /// digests of the keys seen so far
+    seen: HashSet<String>,
+    hasher: RowHasher,
+}
+
+impl Deduplicator {
+    ///
+    /// Creates a `Deduplicator` keyed on the given exported columns
+    pub fn new<S: AsRef<str>>(key_names: &[S], header: &[String]) -> Result<Deduplicator, String> {
+        let mut key_columns = Vec::new();
+        for name in key_names {
+            // unquoted names are case-insensitive like on the database side
+            let name = name.as_ref();
+            match header
+                .iter()
+                .position(|cn| cn == name || *cn == name.to_uppercase())
+            {
+                Some(idx) => key_columns.push(idx),
+                None => return Err(format!("Key column {} is not exported", name)),
+            }
+        }
+
+        Ok(Deduplicator {
+            key_columns,
+            seen: HashSet::new(),
+            hasher: RowHasher::default(),
+        })
+    }
+
+    ///
+    /// Records the key of a row; `true` if the key was seen before
+    pub fn is_duplicate(&mut self, row: &[Option<ColumnValue>]) -> bool {
+        let key: Vec<Option<ColumnValue>> = self
+            .key_columns
+            .iter()
+            .map(|&idx| row[idx].clone())
+            .collect();
+
+        !self.seen.insert(self.hasher.hash(&key))
+    }
+}
@@ -0,0 +1,80 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Substitution of NULL values by configured defaults
+//!
+
+use lib_oradb::definition::{ColumnDefinition, ColumnValue, ValueFormat};
+use std::collections::BTreeMap;
+
+///
+/// Replaces NULL values of exported rows with per-column defaults,
+/// e.g. for target columns with NOT NULL constraints
+pub struct NullDefaults {
+    /// replacement for NULL values of each exported column, by position
+    values: Vec<Option<ColumnValue>>,
+}
+
+impl NullDefaults {
+    ///
+    /// Creates `NullDefaults` from configured values, which are given in
+    /// the output format of the respective column's data type
+    pub fn new(
+        defaults: &BTreeMap<String, String>,
+        columns: &[ColumnDefinition],
+        value_format: &ValueFormat,
+    ) -> Result<NullDefaults, String> {
+        if let Some(unknown) = defaults
+            .keys()
+            .find(|name| !columns.iter().any(|col| col.column_name() == *name))
+        {
+            return Err(format!("Defaulted column {} is not exported", unknown));
+        }
+
+        let mut values = Vec::new();
+        for col in columns {
+            values.push(match defaults.get(col.column_name()) {
+                Some(text) => value_format
+                    .parse(text, col.data_type())
+                    .map_err(|e| format!("Invalid default for {}: {}", col.column_name(), e))?,
+                None => None,
+            });
+        }
+
+        Ok(NullDefaults { values })
+    }
+
+    ///
+    /// Replaces the NULL values of a row
+    pub fn apply(&self, row: &mut [Option<ColumnValue>]) {
+        for (value, default) in row.iter_mut().zip(&self.values) {
+            if value.is_none() {
+                *value = default.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Delivery of finished output files to remote targets
+//!
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// scheme of output names delivered over SFTP
+const SFTP_SCHEME: &str = "sftp://";
+
+///
+/// Remote file written over SFTP, given as
+/// `sftp://[user@]host[:port]/path/file.csv`
+#[derive(Debug)]
+pub struct SftpTarget {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+    /// remote path; relative to the login directory if given as `/~/file.csv`
+    path: String,
+}
+
+///
+/// Quotes a path for an sftp batch command; quotes, backslashes and
+/// line breaks are refused since sftp would read them as syntax
+fn batch_quote(path: &str) -> Result<String, String> {
+    if path.contains(['"', '\\', '\n', '\r']) {
+        return Err(format!(
+            "path {} must not contain quotes, backslashes or line breaks",
+            path
+        ));
+    }
+
+    Ok(format!("\"{}\"", path))
+}
+
+impl SftpTarget {
+    ///
+    /// Parses an output name; names without the `sftp://` scheme are
+    /// local files and give `None`
+    pub fn parse(url: &str) -> Option<Result<SftpTarget, String>> {
+        let rest = url.strip_prefix(SFTP_SCHEME)?;
+
+        Some(SftpTarget::parse_location(rest))
+    }
+
+    fn parse_location(location: &str) -> Result<SftpTarget, String> {
+        let (authority, path) = match location.find('/') {
+            Some(pos) => location.split_at(pos),
+            None => return Err(String::from("a remote file path is missing")),
+        };
+        let (user, address) = match authority.rsplit_once('@') {
+            Some((user, address)) if !user.is_empty() => (Some(String::from(user)), address),
+            Some(_) => return Err(String::from("the user name is empty")),
+            None => (None, authority),
+        };
+        // the colons of a bracketed IPv6 address do not start a port
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) if !port.ends_with(']') => match port.parse::<u16>() {
+                Ok(p) if p > 0 => (host, Some(p)),
+                _ => return Err(format!("{} is not a valid port", port)),
+            },
+            _ => (address, None),
+        };
+        if host.is_empty() {
+            return Err(String::from("the host name is missing"));
+        }
+        let path = match path.strip_prefix("/~/") {
+            Some(relative) => relative,
+            None => path,
+        };
+        if path.is_empty() || path.ends_with('/') {
+            return Err(String::from("a remote file name is missing"));
+        }
+        batch_quote(path)?;
+
+        Ok(SftpTarget {
+            user,
+            host: String::from(host),
+            port,
+            path: String::from(path),
+        })
+    }
+
+    ///
+    /// Gets the name of the remote file without its directory
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    ///
+    /// Uploads a local file under a temporary name and renames it into
+    /// place, replacing an earlier file, so that the partner never picks
+    /// up a partial file; authenticates with keys only
+    pub fn upload(&self, local: &Path, identity_file: Option<&str>) -> Result<(), String> {
+        let local = batch_quote(&local.display().to_string())?;
+        let part = batch_quote(&format!("{}.part", self.path))?;
+        let remote = batch_quote(&self.path)?;
+        let batch = format!("put {} {}\nrename {} {}\n", local, part, part, remote);
+
+        let mut command = Command::new("sftp");
+        // never prompt for passwords or host keys
+        command.args(["-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(port) = self.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        if let Some(identity_file) = identity_file {
+            command.arg("-i").arg(identity_file);
+        }
+        command.arg(match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        });
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to start sftp: {}", e))?;
+        // stdin is piped above
+        let written = child.stdin.take().unwrap().write_all(batch.as_bytes());
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("failed to run sftp: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "sftp {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        written.map_err(|e| format!("failed to pass commands to sftp: {}", e))
+    }
+}
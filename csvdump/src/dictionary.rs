@@ -0,0 +1,119 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Dictionary encoding of low-cardinality columns
+//!
+
+use lib_oradb::definition::ColumnValue;
+use std::collections::HashMap;
+use std::path::Path;
+
+///
+/// A single line in the dictionary sidecar file
+#[derive(Serialize)]
+struct DictionaryRecord<'a> {
+    column_name: &'a str,
+    code: u64,
+    value: &'a str,
+}
+
+///
+/// Codes assigned to the values of one column
+struct ColumnDictionary {
+    /// position of the column in exported rows
+    index: usize,
+    column_name: String,
+    codes: HashMap<String, u64>,
+}
+
+///
+/// Replaces values of selected columns with integer codes and
+/// collects the lookup table written as sidecar file
+pub struct DictionaryEncoder {
+    columns: Vec<ColumnDictionary>,
+}
+
+impl DictionaryEncoder {
+    ///
+    /// Creates an encoder for the given header; fails if an
+    /// encoded column is not part of the export
+    pub fn new(column_names: &[String], header: &[String]) -> Result<DictionaryEncoder, String> {
+        let columns = column_names
+            .iter()
+            .map(|name| match header.iter().position(|cn| cn == name) {
+                Some(index) => Ok(ColumnDictionary {
+                    index,
+                    column_name: name.clone(),
+                    codes: HashMap::new(),
+                }),
+                None => Err(format!("Dictionary column {} is not exported", name)),
+            })
+            .collect::<Result<Vec<ColumnDictionary>, String>>()?;
+
+        Ok(DictionaryEncoder { columns })
+    }
+
+    ///
+    /// Replaces formatted values in `record` by their codes;
+    /// NULL values of `row` are left as they are
+    pub fn encode(&mut self, row: &[Option<ColumnValue>], record: &mut [String]) {
+        for dict in self.columns.iter_mut() {
+            if row[dict.index].is_none() {
+                continue;
+            }
+
+            let next_code = dict.codes.len() as u64 + 1;
+            let code = *dict
+                .codes
+                .entry(std::mem::take(&mut record[dict.index]))
+                .or_insert(next_code);
+            record[dict.index] = code.to_string();
+        }
+    }
+
+    ///
+    /// Writes the lookup table as csv with column name, code and value
+    pub fn write(&self, filename: &Path) -> Result<(), csv::Error> {
+        let mut out = csv::Writer::from_path(filename)?;
+
+        for dict in &self.columns {
+            let mut entries: Vec<(&String, &u64)> = dict.codes.iter().collect();
+            entries.sort_by_key(|(_, code)| **code);
+            for (value, code) in entries {
+                out.serialize(DictionaryRecord {
+                    column_name: &dict.column_name,
+                    code: *code,
+                    value,
+                })?;
+            }
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Comparison of two sets of exported rows by key
+//!
+
+use std::collections::BTreeMap;
+
+///
+/// Describes how a row differs between the old and the new side
+pub enum Change {
+    /// row only exists on the new side
+    Inserted(String),
+    /// row only exists on the old side
+    Deleted(String),
+    /// row exists on both sides with the listed column changes
+    Changed(String, Vec<String>),
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Inserted(key) => write!(f, "+ {}", key),
+            Change::Deleted(key) => write!(f, "- {}", key),
+            Change::Changed(key, columns) => write!(f, "~ {}: {}", key, columns.join("; ")),
+        }
+    }
+}
+
+///
+/// Compares rows of the same columns, matching them by key columns.
+/// Both sides are held in memory.
+pub struct RowDiff {
+    header: Vec<String>,
+    /// positions of the key columns
+    key_columns: Vec<usize>,
+}
+
+impl RowDiff {
+    ///
+    /// Creates a `RowDiff` keyed on the given columns of the header
+    pub fn new<S: AsRef<str>>(key_names: &[S], header: &[String]) -> Result<RowDiff, String> {
+        let mut key_columns = Vec::new();
+        for name in key_names {
+            // unquoted names are case-insensitive like on the database side
+            let name = name.as_ref();
+            match header
+                .iter()
+                .position(|cn| cn == name || *cn == name.to_uppercase())
+            {
+                Some(idx) => key_columns.push(idx),
+                None => return Err(format!("Key column {} is not compared", name)),
+            }
+        }
+
+        Ok(RowDiff {
+            header: header.to_vec(),
+            key_columns,
+        })
+    }
+
+    ///
+    /// Renders the key of a row as `COLUMN=value` pairs
+    fn key<T, F: Fn(&T) -> String>(&self, row: &[T], render: &F) -> String {
+        self.key_columns
+            .iter()
+            .map(|&idx| format!("{}={}", self.header[idx], render(&row[idx])))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    ///
+    /// Maps rows by their rendered key; fails on rows of the wrong
+    /// length or on a key occurring twice
+    fn index<T, F: Fn(&T) -> String>(
+        &self,
+        rows: Vec<Vec<T>>,
+        render: &F,
+    ) -> Result<BTreeMap<String, Vec<T>>, String> {
+        let mut indexed = BTreeMap::new();
+        for (idx, row) in rows.into_iter().enumerate() {
+            if row.len() != self.header.len() {
+                return Err(format!(
+                    "Row {} has {} fields instead of {}",
+                    idx + 1,
+                    row.len(),
+                    self.header.len()
+                ));
+            }
+            let key = self.key(&row, render);
+            if indexed.contains_key(&key) {
+                return Err(format!("Key {} occurs more than once", key));
+            }
+            indexed.insert(key, row);
+        }
+
+        Ok(indexed)
+    }
+
+    ///
+    /// Lists inserted, deleted and changed rows ordered by key
+    pub fn compare<T: PartialEq, F: Fn(&T) -> String>(
+        &self,
+        old: Vec<Vec<T>>,
+        new: Vec<Vec<T>>,
+        render: F,
+    ) -> Result<Vec<Change>, String> {
+        let mut old = self.index(old, &render)?;
+        let new = self.index(new, &render)?;
+
+        let mut changes = Vec::new();
+        for (key, new_row) in new {
+            match old.remove(&key) {
+                None => changes.push(Change::Inserted(key)),
+                Some(old_row) => {
+                    let columns: Vec<String> = self
+                        .header
+                        .iter()
+                        .zip(old_row.iter().zip(new_row.iter()))
+                        .filter(|(_, (old_value, new_value))| old_value != new_value)
+                        .map(|(cn, (old_value, new_value))| {
+                            format!("{}: {} -> {}", cn, render(old_value), render(new_value))
+                        })
+                        .collect();
+                    if !columns.is_empty() {
+                        changes.push(Change::Changed(key, columns));
+                    }
+                }
+            }
+        }
+        changes.extend(old.into_keys().map(Change::Deleted));
+        changes.sort_by(|a, b| change_key(a).cmp(change_key(b)));
+
+        Ok(changes)
+    }
+}
+
+///
+/// Gets the rendered key of a change
+fn change_key(change: &Change) -> &str {
+    match change {
+        Change::Inserted(key) | Change::Deleted(key) | Change::Changed(key, _) => key,
+    }
+}
@@ -0,0 +1,1693 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Export of a table, or chunks of it, into output files
+//!
+
+use crate::backfill::{labelled_path, parse_date, partitions, Granularity, Partition};
+use crate::config::Config;
+use crate::context::RunContext;
+use crate::control::{count_records, ControlFile};
+use crate::dedupe::Deduplicator;
+use crate::defaults::NullDefaults;
+use crate::dictionary::DictionaryEncoder;
+use crate::hints::HintCollector;
+use crate::interrupt;
+use crate::lobfiles::LobWriter;
+use crate::masking::Masker;
+use crate::output::*;
+use crate::pipeline::{RowOutput, RowPipeline};
+use crate::pool::WriterPool;
+use crate::postgres;
+use crate::profile::OutputProfile;
+use crate::rowhash::{HashAlgorithm, RowHasher};
+use crate::sqlite::SqliteWriter;
+use crate::state::IncrementalState;
+use crate::truncate::Truncator;
+use crate::validation::Validator;
+use crate::{
+    fail_run, parse_bind, parse_column_entry, parse_sample_percent, print_table, read_keys_file,
+    try_connect, ExitCode, Failure,
+};
+use clap::ArgMatches;
+use colored::*;
+use lib_oradb::definition::*;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+///
+/// Table and columns selected for export
+#[derive(Clone, Copy)]
+pub struct Selection<'a> {
+    /// name of the table, optionally prefixed by its owner
+    pub table_name: &'a str,
+    /// column entries of the input file
+    pub column_names: &'a [String],
+}
+
+///
+/// Part of a table exported into a file of its own
+pub enum Chunk<'a> {
+    /// rows within a backfill date range
+    Dates(&'a Partition),
+    /// rows of a table partition
+    Table(&'a str),
+}
+
+impl Chunk<'_> {
+    ///
+    /// Gets the label added to the names of files written for the chunk
+    fn label(&self) -> &str {
+        match self {
+            Chunk::Dates(p) => p.label(),
+            Chunk::Table(name) => name,
+        }
+    }
+}
+
+///
+/// Sets up the transforms of the rows of a table: deduplication, NULL
+/// defaults, validation, masking, type hints, LOB files, truncation,
+/// dictionary encoding and row hashing
+fn row_pipeline(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    config: &Config,
+    table_name: &str,
+    table_def: &TableDefinition,
+    output_file_path: &Path,
+    value_format: &ValueFormat,
+) -> Result<RowPipeline, Failure> {
+    let mut pipeline = RowPipeline::new(value_format.clone());
+
+    // set up NULL replacements; defaults are applied before validation
+    if !config.defaults().is_empty() {
+        match NullDefaults::new(config.defaults(), table_def.columns(), value_format) {
+            Ok(n) => {
+                status!(
+                    ctx;
+                    "Replacing NULL values in {} columns.",
+                    config.defaults().len().to_string().blue()
+                );
+                pipeline = pipeline.null_defaults(n);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Config,
+                    format!("{} to set up default values: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // set up validation; values are checked before they are masked
+    if !config.validation().is_empty() {
+        match Validator::new(config.validation(), &table_def.header()) {
+            Ok(v) => {
+                status!(
+                    ctx;
+                    "Validating {} columns.",
+                    config.validation().len().to_string().blue()
+                );
+                pipeline = pipeline.validate(v);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Validation,
+                    format!("{} to set up validation: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // set up client-side removal of duplicate rows
+    if let Some(keys) = matches.values_of("distincton") {
+        let keys: Vec<&str> = keys.collect();
+        match Deduplicator::new(&keys, &table_def.header()) {
+            Ok(d) => {
+                status!(ctx; "Skipping rows with duplicate {}.", keys.join(",").blue());
+                pipeline = pipeline.deduplicate(d);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Definition,
+                    format!("{} to set up deduplication: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // set up truncation of oversized text values; lengths are validated by clap
+    if let Some(length) = matches.value_of("maxfieldlength") {
+        pipeline = pipeline.truncate(Truncator::new(
+            length.parse().unwrap(),
+            matches.is_present("ellipsis"),
+            table_def.columns(),
+        ));
+    }
+
+    // set up export of LOB values into side files
+    if let Some(lob_dir) = matches.value_of("lobdir") {
+        // files are named after the primary key unless other columns are given
+        let keys: Vec<&str> = match (matches.values_of("lobkey"), table_def.primary_key()) {
+            (Some(keys), _) => keys.collect(),
+            (None, Some(primary_key)) => primary_key.iter().map(|k| k.as_str()).collect(),
+            (None, None) => {
+                return Err((
+                    ExitCode::Definition,
+                    format!(
+                        "{} has no primary key to name LOB files; use {}.",
+                        table_name.yellow(),
+                        "--lob-key".blue()
+                    ),
+                ));
+            }
+        };
+        match LobWriter::new(output_file_path, lob_dir, &keys, table_def.columns()) {
+            Ok(w) => {
+                status!(ctx; "Writing LOB values into {}.", lob_dir.yellow());
+                pipeline = pipeline.lob_files(w);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Output,
+                    format!("{} to set up LOB files: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // set up masking of sensitive columns; rules may name other tables
+    if !config.masking().is_empty() {
+        let table = match matches.value_of("owner") {
+            Some(owner) => TableName::parse(table_name).with_owner(owner),
+            None => Ok(TableName::parse(table_name)),
+        }
+        .unwrap_or_else(|_| TableName::parse(table_name));
+        match Masker::new(config.masking(), &table, &table_def.header()) {
+            Ok(m) if m.masked_columns() == 0 => (),
+            Ok(m) => {
+                status!(ctx; "Masking {} columns.", m.masked_columns().to_string().blue());
+                pipeline = pipeline.mask(m);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Masking,
+                    format!("{} to set up masking: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // collect statistics for type hints if requested
+    if matches.is_present("hints") {
+        pipeline = pipeline.collect_hints(HintCollector::new(table_def.header()));
+    }
+
+    // set up dictionary encoding of low-cardinality columns
+    if let Some(columns) = matches.values_of("dictionary") {
+        let columns: Vec<String> = columns.map(normalize_identifier).collect();
+        match DictionaryEncoder::new(&columns, &table_def.header()) {
+            Ok(d) => {
+                status!(
+                    ctx;
+                    "Dictionary encoding {} columns.",
+                    columns.len().to_string().blue()
+                );
+                pipeline = pipeline.dictionary(d);
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Masking,
+                    format!("{} to set up dictionary encoding: {}", "Failed".red(), e),
+                ));
+            }
+        }
+    }
+
+    // set up row hashing; the hash column must not clash with exported columns
+    if let Some(hash_column) = matches.value_of("rowhash") {
+        if table_def.header().iter().any(|cn| cn == hash_column) {
+            return Err((
+                ExitCode::RowHash,
+                format!(
+                    "Row hash column {} {} with an exported column.",
+                    hash_column.yellow(),
+                    "clashes".red()
+                ),
+            ));
+        }
+        // algorithm names are validated by clap
+        pipeline = pipeline.hash_rows(RowHasher::new(
+            HashAlgorithm::parse(matches.value_of("rowhashalgorithm").unwrap()).unwrap(),
+        ));
+    }
+
+    Ok(pipeline)
+}
+
+///
+/// Sets up the query of the selected columns of a table, restricted by
+/// the filters of the command line and to a chunk if given
+fn query_builder(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    config: &Config,
+    selection: Selection,
+    chunk: Option<&Chunk>,
+) -> Result<TableSelectionBuilder, Failure> {
+    let Selection {
+        table_name,
+        column_names,
+    } = selection;
+    // set up table selection builder to construct
+    // meta data query about table column information
+    let mut builder = TableSelectionBuilder::new(table_name)
+        .float_numbers(matches.is_present("floatnumbers"))
+        .distinct(matches.is_present("distinct"))
+        .materialize(matches.is_present("materialize") || config.materialize())
+        .unknown_types(match matches.value_of("unknowntypes") {
+            Some("skip-column") => UnknownTypePolicy::SkipColumn,
+            Some("as-string") => UnknownTypePolicy::AsString,
+            _ => UnknownTypePolicy::Fail,
+        })
+        .column_order(match matches.value_of("columnorder") {
+            Some("table") => ColumnOrder::Table,
+            _ => ColumnOrder::Input,
+        });
+    // exclusions alone select all other columns, unless aggregating
+    if !matches.is_present("aggregate") && !column_names.iter().any(|cn| !cn.starts_with('!')) {
+        builder = builder.all_columns();
+    }
+    for cn in column_names {
+        // add specified column names, entries were validated on input
+        builder = match cn.strip_prefix('!') {
+            Some(excluded) => builder.without(excluded),
+            None if cn == "*" => builder.all_columns(),
+            None => match parse_column_entry(cn).unwrap() {
+                (name, Some(data_type)) => builder.with(name).with_type(name, data_type),
+                (name, None) => builder.with(name),
+            },
+        };
+    }
+    // numeric values are validated by clap
+    if let Some(rows) = matches.value_of("fetchsize") {
+        builder = builder.fetch_size(rows.parse().unwrap());
+    }
+    if let Some(rows) = matches.value_of("batchsize") {
+        builder = builder.batch_size(rows.parse().unwrap());
+    }
+    if let Some(mb) = matches.value_of("memorylimit") {
+        let mb: u64 = mb.parse().unwrap();
+        let bytes = match mb.checked_mul(1024 * 1024) {
+            Some(bytes) => bytes,
+            None => {
+                return Err((
+                    ExitCode::Input,
+                    format!("Memory limit of {} MB {}.", mb, "is too large".red()),
+                ));
+            }
+        };
+        // without a readable memory use the limit would silently do nothing
+        if resident_bytes().is_none() {
+            return Err((
+                ExitCode::Input,
+                format!(
+                    "Memory limit {} on this platform; the memory use of the process is unknown.",
+                    "is not supported".red()
+                ),
+            ));
+        }
+        status!(ctx; "Limiting reader memory to {} MB.", mb.to_string().blue());
+        builder = builder.memory_limit(bytes);
+    }
+    if let Some(scn) = matches.value_of("asofscn") {
+        // validated by clap
+        let scn: u64 = scn.parse().unwrap();
+        status!(ctx; "Reading data as of SCN {}.", scn.to_string().blue());
+        builder = builder.as_of_scn(scn);
+    }
+    if let Some(percent) = matches.value_of("sample") {
+        // validated by clap
+        let percent = parse_sample_percent(percent).unwrap();
+        let seed: Option<u32> = matches.value_of("sampleseed").map(|s| s.parse().unwrap());
+        status!(ctx; "Sampling {}% of the rows.", percent.to_string().blue());
+        builder = builder.sample(percent, seed);
+        if let Some(rows) = matches.value_of("samplesize") {
+            builder = builder.limit(rows.parse().unwrap());
+        }
+    }
+    if let Some(filter) = matches.value_of("filter") {
+        status!(ctx; "Restricting rows by filter {}.", filter.blue());
+        builder = builder.filter(filter);
+        for value in matches.values_of("bind").into_iter().flatten() {
+            // validated by clap
+            builder = builder.bind(parse_bind(value).unwrap());
+        }
+    }
+    for aggregate in matches.values_of("aggregate").into_iter().flatten() {
+        // validated by clap
+        let (column, expression) = aggregate.split_once('=').unwrap();
+        status!(ctx; "Aggregating {} as {}.", expression.blue(), column.blue());
+        builder = builder.with_aggregate(expression, column);
+    }
+    for column in matches.values_of("groupby").into_iter().flatten() {
+        builder = builder.group_by(column);
+    }
+    // computed columns on the command line replace configured ones
+    let mut computed: Vec<(&str, &str)> = config
+        .computed()
+        .iter()
+        .map(|(column, expression)| (column.as_str(), expression.as_str()))
+        .collect();
+    for definition in matches.values_of("computed").into_iter().flatten() {
+        // validated by clap
+        let (column, expression) = definition.split_once('=').unwrap();
+        let name = |c: &str| normalize_identifier(c.rsplit_once(':').map_or(c, |(n, _)| n));
+        computed.retain(|(c, _)| name(c) != name(column));
+        computed.push((column, expression));
+    }
+    for (column, expression) in computed {
+        // a column may name the data type its values are cast to
+        builder = match parse_column_entry(column) {
+            Ok((column, Some(data_type))) => {
+                status!(
+                    ctx;
+                    "Adding column {} as {} of type {}.",
+                    column.blue(),
+                    expression.blue(),
+                    data_type.to_string().blue()
+                );
+                builder.with_expression(expression, column, data_type)
+            }
+            Ok((column, None)) => {
+                status!(ctx; "Adding column {} as {}.", column.blue(), expression.blue());
+                builder.with_computed(expression, column)
+            }
+            Err(e) => {
+                return Err((
+                    ExitCode::Config,
+                    format!("{} computed column: {}", "Invalid".red(), e),
+                ))
+            }
+        };
+    }
+    if let Some(keys_file) = matches.value_of("keysfile") {
+        // required by clap
+        let column = matches.value_of("keycolumn").unwrap();
+        let keys = match read_keys_file(Path::new(keys_file)) {
+            Ok(k) => k,
+            Err(e) => {
+                return Err((
+                    ExitCode::Input,
+                    format!(
+                        "{} to read keys file {}: {}",
+                        "Failed".red(),
+                        keys_file.yellow(),
+                        e
+                    ),
+                ));
+            }
+        };
+        status!(
+            ctx;
+            "Restricting rows to {} keys of {}.",
+            keys.len().to_string().blue(),
+            column.blue()
+        );
+        builder = builder.keys(column, keys);
+    }
+    if let Some(Chunk::Dates(p)) = chunk {
+        // validated by clap
+        let column = matches.value_of("partitioncolumn").unwrap();
+        status!(
+            ctx;
+            "Restricting rows to {} in partition {}.",
+            column.blue(),
+            p.label().blue()
+        );
+        builder = builder.range(column, BindValue::Date(p.start()), BindValue::Date(p.end()));
+    }
+    if let Some(Chunk::Table(name)) = chunk {
+        status!(ctx; "Restricting rows to table partition {}.", name.blue());
+        builder = builder.partition(name);
+    } else if let Some(name) = matches.value_of("partition") {
+        status!(ctx; "Restricting rows to table partition {}.", name.blue());
+        builder = builder.partition(name);
+    } else if let Some(name) = matches.value_of("subpartition") {
+        status!(ctx; "Restricting rows to table subpartition {}.", name.blue());
+        builder = builder.subpartition(name);
+    }
+    if let Some(owner) = matches.value_of("owner") {
+        builder = builder.owner(owner);
+    } else if let Some(owner) = matches
+        .value_of("preferowner")
+        .or_else(|| config.default_owner())
+    {
+        builder = builder.prefer_owner(owner);
+    }
+
+    Ok(builder)
+}
+
+///
+/// Restores the state of a previous incremental export if `--state` is
+/// given and restricts the query to rows added or changed since
+fn restore_state(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    table_name: &str,
+    mut builder: TableSelectionBuilder,
+) -> Result<(TableSelectionBuilder, Option<IncrementalState>), Failure> {
+    let incremental_column = matches.value_of("incrementalcolumn");
+    let cdc_flag = matches.is_present("cdc");
+
+    // restore state of previous incremental export
+    let incremental_state = match matches.value_of("state") {
+        Some(state_file) => {
+            let state = match IncrementalState::load(Path::new(state_file)) {
+                Ok(Some(st)) if st.matches(table_name, incremental_column) => st,
+                Ok(Some(_)) => {
+                    return Err((
+                        ExitCode::State,
+                        format!(
+                            "State file {} {} table {} and column {}.",
+                            state_file.yellow(),
+                            "does not match".red(),
+                            table_name.yellow(),
+                            incremental_column.unwrap_or("-").yellow()
+                        ),
+                    ));
+                }
+                Ok(None) => {
+                    status!(
+                        ctx;
+                        "No state file {} found; exporting all rows.",
+                        state_file.yellow()
+                    );
+                    IncrementalState::new(table_name, incremental_column)
+                }
+                Err(e) => {
+                    return Err((
+                        ExitCode::State,
+                        format!(
+                            "{} to load state file {}: {}",
+                            "Failed".red(),
+                            state_file.yellow(),
+                            e
+                        ),
+                    ));
+                }
+            };
+            if let (Some(column), Some(last_value)) = (incremental_column, state.last_value()) {
+                status!(
+                    ctx;
+                    "Exporting rows with {} greater than {:?}.",
+                    column.blue(),
+                    last_value
+                );
+                builder = builder.newer_than(column, last_value.to_column_value());
+            }
+            if let (true, Some(scn)) = (cdc_flag, state.last_scn()) {
+                status!(
+                    ctx;
+                    "Exporting rows changed since SCN {}.",
+                    scn.to_string().blue()
+                );
+                builder = builder.changed_since_scn(scn);
+            }
+            Some(state)
+        }
+        None => None,
+    };
+
+    Ok((builder, incremental_state))
+}
+
+///
+/// Samples the first rows if `--sample-rows` is given and warns about
+/// values not matching their declared types; fails on drift if requested
+fn check_drift(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    conn: &Arc<oracle::Connection>,
+    table_name: &str,
+    table_def: &TableDefinition,
+) -> Result<(), Failure> {
+    if let Some(rows) = matches.value_of("samplerows") {
+        // validated by clap
+        let rows: u64 = rows.parse().unwrap();
+        status!(ctx; "Sampling the first {} rows.", rows.to_string().blue());
+        let drifts = match table_def.sample(conn.as_ref(), rows) {
+            Ok(drifts) => drifts,
+            Err(e) => {
+                return Err((
+                    ExitCode::Load,
+                    format!(
+                        "{} to sample table {}: {}",
+                        "Failed".red(),
+                        table_name.yellow(),
+                        e
+                    ),
+                ));
+            }
+        };
+        for drift in &drifts {
+            eprintln!(
+                "{} column {} does not match its declared type: {}",
+                "Warning:".yellow(),
+                drift.column_name().yellow(),
+                drift.description()
+            );
+        }
+        if !drifts.is_empty() && matches.is_present("abortondrift") {
+            return Err((
+                ExitCode::Drift,
+                format!(
+                    "{} sampled values of {} columns do not match their declared types.",
+                    "Aborting:".red(),
+                    drifts.len()
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Picks the output profile of the target or format and applies the
+/// dialect and value format options; fails on conflicting options
+fn output_profile(ctx: &RunContext, matches: &ArgMatches) -> Result<OutputProfile, Failure> {
+    // possible values are enforced by clap
+    let pg_copy = matches.value_of("format") == Some("pg-copy");
+    let sqlite_flag = matches.value_of("format") == Some("sqlite");
+    // a SQLite database holds a single table with native values
+    if let Some(arg) = [
+        "append",
+        "state",
+        "encoding",
+        "bom",
+        "lobdir",
+        "dictionary",
+        "writethreads",
+        "encryptrecipient",
+    ]
+    .iter()
+    .find(|arg| sqlite_flag && matches.is_present(arg))
+    {
+        return Err((
+            ExitCode::Input,
+            format!(
+                "SQLite output {} with --{}.",
+                "cannot be combined".red(),
+                arg
+            ),
+        ));
+    }
+    let mut profile = match matches.value_of("target") {
+        Some(target) => {
+            status!(ctx; "Using output profile for {}.", target.blue());
+            OutputProfile::for_target(target).unwrap_or_default()
+        }
+        None if pg_copy => OutputProfile::pg_copy(),
+        None => OutputProfile::default(),
+    };
+    if matches.is_present("quoteall") {
+        profile.quote_style = csv::QuoteStyle::Always;
+    }
+    // encoding names are validated by clap
+    if let Some(encoding) = matches.value_of("encoding") {
+        profile.encoding = Encoding::parse(encoding).unwrap();
+    }
+    if matches.is_present("crlf") {
+        profile.terminator = csv::Terminator::CRLF;
+    } else if matches.is_present("lf") {
+        profile.terminator = csv::Terminator::Any(b'\n');
+    }
+    if matches.is_present("bom") {
+        if profile.encoding != Encoding::Utf8 {
+            return Err((
+                ExitCode::Input,
+                format!(
+                    "A byte order mark {} with UTF-8 encoding.",
+                    "can only be used".red()
+                ),
+            ));
+        }
+        profile.bom = true;
+    }
+    // boolean formats are validated by clap
+    if let Some(bool_format) = matches.value_of("boolformat") {
+        profile.value_format = profile
+            .value_format
+            .clone()
+            .bool_format(BoolFormat::parse(bool_format).unwrap());
+    }
+    // separators are validated by clap to be single characters
+    let decimal_separator = matches
+        .value_of("decimalseparator")
+        .and_then(|s| s.chars().next());
+    let thousands_separator = matches
+        .value_of("thousandsseparator")
+        .and_then(|s| s.chars().next());
+    if thousands_separator.is_some() && thousands_separator == decimal_separator.or(Some('.')) {
+        return Err((
+            ExitCode::Input,
+            format!("Decimal and thousands separator {}.", "must differ".red()),
+        ));
+    }
+    if let Some(separator) = decimal_separator {
+        profile.value_format = profile.value_format.clone().decimal_separator(separator);
+    }
+    if let Some(separator) = thousands_separator {
+        profile.value_format = profile.value_format.clone().thousands_separator(separator);
+    }
+    if let Some(scale) = matches.value_of("decimalscale") {
+        profile.value_format = profile
+            .value_format
+            .clone()
+            .decimal_scale(scale.parse().unwrap());
+    }
+    if matches.is_present("stripcontrolchars") {
+        profile.value_format = profile
+            .value_format
+            .clone()
+            .control_chars(ControlChars::Strip);
+    } else if let Some(c) = matches
+        .value_of("replacecontrolchars")
+        .and_then(|s| s.chars().next())
+    {
+        profile.value_format = profile
+            .value_format
+            .clone()
+            .control_chars(ControlChars::Replace(c));
+    }
+    profile.value_format = profile
+        .value_format
+        .clone()
+        .escape_newlines(matches.is_present("escapenewlines"))
+        .trim_trailing(matches.is_present("trimtrailing"));
+
+    Ok(profile)
+}
+
+///
+/// Sets up encryption for the recipients of `--encrypt-to`, if any
+fn output_encryption(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+) -> Result<Option<Encryption>, Failure> {
+    // exported data only reaches the disk encrypted
+    let encryption = match matches.values_of("encryptrecipient") {
+        Some(recipients) => {
+            let recipients: Vec<&str> = recipients.collect();
+            match Encryption::new(&recipients, matches.is_present("armor")) {
+                Ok(e) => {
+                    status!(
+                        ctx;
+                        "Encrypting output with {} for {} recipients.",
+                        match e.tool() {
+                            EncryptionTool::Age => "age",
+                            EncryptionTool::OpenPgp => "OpenPGP",
+                        },
+                        recipients.len().to_string().blue()
+                    );
+                    Some(e)
+                }
+                Err(e) => {
+                    return Err((
+                        ExitCode::Input,
+                        format!("Encryption {}: {}", "invalid".red(), e),
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
+
+    Ok(encryption)
+}
+
+///
+/// Creates the writer of a new or appended output file; `None` for a
+/// SQLite database, which is created once the header is known
+fn create_output(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    profile: &OutputProfile,
+    output_file_path: &Path,
+    write_path: &Path,
+    append_flag: bool,
+    encryption: Option<&Encryption>,
+) -> Result<Option<RowOutput>, Failure> {
+    if matches.value_of("format") == Some("sqlite") {
+        return Ok(None);
+    }
+    let output_file = output_file_path.display().to_string();
+    // validated by clap
+    let write_threads: usize = matches
+        .value_of("writethreads")
+        .map_or(1, |n| n.parse().unwrap());
+
+    let sink = if append_flag {
+        status!(ctx; "Appending to output file {}.", output_file.yellow());
+        OutputSink::append(output_file_path, profile.compression)
+    } else {
+        OutputSink::create(write_path, profile.compression, encryption)
+    };
+    // a byte order mark only belongs at the start of a new file
+    let sink = match (sink, profile.bom && !append_flag) {
+        (Ok(mut sink), true) => sink.write_all(UTF8_BOM).map(|_| sink),
+        (sink, _) => sink,
+    };
+    match sink {
+        Ok(sink) if write_threads > 1 => {
+            status!(
+                ctx;
+                "Serializing rows on {} threads.",
+                write_threads.to_string().blue()
+            );
+            Ok(Some(RowOutput::Pool(WriterPool::new(
+                write_threads,
+                profile,
+                sink,
+            ))))
+        }
+        Ok(sink) => Ok(Some(RowOutput::Csv(Box::new(
+            profile
+                .writer_builder()
+                .from_writer(Transcoder::new(sink, profile.encoding)),
+        )))),
+        Err(e) => Err((
+            ExitCode::Output,
+            format!(
+                "{} to create CSV output file {}: {}",
+                "Failed".red(),
+                output_file.yellow(),
+                e
+            ),
+        )),
+    }
+}
+
+///
+/// Removes a partial new file, or cuts an appended file back to its
+/// previous length
+fn discard_partial(
+    output_file_path: &Path,
+    write_path: &Path,
+    append_flag: bool,
+    previous_len: u64,
+) {
+    let output_file = output_file_path.display().to_string();
+    let discarded = if append_flag {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(output_file_path)
+            .and_then(|f| f.set_len(previous_len))
+    } else {
+        std::fs::remove_file(write_path)
+    };
+    match discarded {
+        Ok(()) => eprintln!(
+            "{} partial output of {}.",
+            "Discarded".yellow(),
+            output_file.yellow()
+        ),
+        Err(e) => eprintln!(
+            "{} to discard partial output of {}: {}",
+            "Failed".red(),
+            output_file.yellow(),
+            e
+        ),
+    }
+}
+
+///
+/// Creates the file of rejected rows, written with the header of the
+/// output and the reason of the rejection
+fn create_rejects(
+    path: &Path,
+    profile: &OutputProfile,
+    encryption: Option<&Encryption>,
+    header: &[String],
+) -> Result<csv::Writer<OutputSink>, Failure> {
+    // rejected rows are as sensitive as the exported ones
+    OutputSink::create(path, Compression::None, encryption)
+        .map(|sink| profile.writer_builder().from_writer(sink))
+        .map_err(csv::Error::from)
+        .and_then(|mut w| {
+            let mut rejects_header = header.to_vec();
+            rejects_header.push(String::from("REJECT_REASON"));
+            w.write_record(rejects_header).map(|_| w)
+        })
+        .map_err(|e| {
+            (
+                ExitCode::Output,
+                format!(
+                    "{} to create rejects file {}: {}",
+                    "Failed".red(),
+                    path.display().to_string().yellow(),
+                    e
+                ),
+            )
+        })
+}
+
+///
+/// Writes the PostgreSQL statement creating a table for the export
+fn write_ddl(
+    ctx: &RunContext,
+    ddl_file: &Path,
+    local_table: &str,
+    header: &[String],
+    table_def: &TableDefinition,
+) -> Result<(), Failure> {
+    let ddl_file = ddl_file.display().to_string();
+    let ddl = postgres::create_table(
+        local_table,
+        header,
+        table_def.columns(),
+        table_def.primary_key(),
+    );
+    if let Err(e) = std::fs::write(&ddl_file, ddl) {
+        return Err((
+            ExitCode::Output,
+            format!(
+                "{} to write DDL file {}: {}",
+                "Failed".red(),
+                ddl_file.yellow(),
+                e
+            ),
+        ));
+    }
+    status!(ctx; "PostgreSQL DDL written to {}.", ddl_file.yellow());
+    Ok(())
+}
+
+///
+/// Writes the type hints and the dictionary collected from the rows,
+/// if requested; failing to write them does not fail the export
+fn write_collected(
+    ctx: &RunContext,
+    pipeline: &RowPipeline,
+    hints_file: Option<PathBuf>,
+    dictionary_file: Option<PathBuf>,
+) {
+    if let (Some(h), Some(hints_file)) = (pipeline.hints(), hints_file) {
+        let hints_file = hints_file.display().to_string();
+        match h.write(Path::new(&hints_file)) {
+            Ok(()) => status!(ctx; "Type hints written to {}.", hints_file.yellow()),
+            Err(e) => eprintln!(
+                "{} to write type hints file {}: {}",
+                "Failed".red(),
+                hints_file.yellow(),
+                e
+            ),
+        }
+    }
+
+    if let (Some(d), Some(dictionary_file)) = (pipeline.dictionary_encoder(), dictionary_file) {
+        let dictionary_file = dictionary_file.display().to_string();
+        match d.write(Path::new(&dictionary_file)) {
+            Ok(()) => status!(ctx; "Dictionary written to {}.", dictionary_file.yellow()),
+            Err(e) => eprintln!(
+                "{} to write dictionary file {}: {}",
+                "Failed".red(),
+                dictionary_file.yellow(),
+                e
+            ),
+        }
+    }
+}
+
+///
+/// Exports the selected columns of a table into one output file,
+/// restricted to a chunk of the table if given; returns the number
+/// of rows once all rows were loaded and written
+pub fn export_table(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    config: &Config,
+    conn: &Arc<oracle::Connection>,
+    selection: Selection,
+    output_file_path: &Path,
+    chunk: Option<&Chunk>,
+) -> Result<u64, Failure> {
+    let table_name = selection.table_name;
+    let started = chrono::Utc::now();
+    let incremental_column = matches.value_of("incrementalcolumn");
+    let cdc_flag = matches.is_present("cdc");
+    let output_file = output_file_path.display().to_string();
+    // appending and incremental exports add to an existing output file
+    let append_flag =
+        (matches.is_present("append") || matches.is_present("state")) && output_file_path.exists();
+    // files written next to a chunk carry its label as well
+    let side_file = |name: &str| {
+        matches.value_of(name).map(|f| match chunk {
+            Some(c) => labelled_path(Path::new(f), c.label()),
+            None => PathBuf::from(f),
+        })
+    };
+
+    let builder = query_builder(ctx, matches, config, selection, chunk)?;
+
+    let (builder, mut incremental_state) = restore_state(ctx, matches, table_name, builder)?;
+
+    // run "build" to get table definition
+    let table_def = match builder.build(conn.as_ref()) {
+        Ok(df) => df,
+        Err(e) => {
+            return Err((
+                ExitCode::Definition,
+                format!(
+                    "{} to read table definition for table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            ));
+        }
+    };
+    status!(
+        ctx;
+        "{} read table definition for table {}.",
+        "Successfully".green(),
+        table_name.blue()
+    );
+
+    // sample the first rows to catch type drift before a long export
+    check_drift(ctx, matches, conn, table_name, &table_def)?;
+
+    // record the change number before loading so that changes
+    // committed during the export are picked up by the next run
+    let extraction_scn: Option<u64> = match matches.value_of("asofscn") {
+        Some(scn) => scn.parse().ok(),
+        None if cdc_flag || matches.is_present("recordscn") => match current_scn(conn.as_ref()) {
+            Ok(scn) => Some(scn),
+            Err(e) => {
+                return Err((
+                    ExitCode::State,
+                    format!("{} to read current SCN: {}", "Failed".red(), e),
+                ));
+            }
+        },
+        None => None,
+    };
+    if let Some(scn) = extraction_scn {
+        status!(ctx; "Extraction starts at SCN {}.", scn.to_string().blue());
+    }
+    if let (true, Some(st), Some(scn)) = (cdc_flag, incremental_state.as_mut(), extraction_scn) {
+        st.set_scn(scn);
+    }
+
+    // the incremental column must be exported to track its highest value
+    let incremental_index = match incremental_column {
+        Some(column) => match table_def
+            .header()
+            .iter()
+            .position(|cn| *cn == normalize_identifier(column))
+        {
+            Some(idx) => Some(idx),
+            None => {
+                return Err((
+                    ExitCode::State,
+                    format!(
+                        "Incremental column {} {} in the input file.",
+                        column.yellow(),
+                        "is missing".red()
+                    ),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let profile = output_profile(ctx, matches)?;
+    let value_format = profile.value_format.clone();
+    // possible values are enforced by clap
+    let pg_copy = matches.value_of("format") == Some("pg-copy");
+    let sqlite_flag = matches.value_of("format") == Some("sqlite");
+
+    // a new file is written under a temporary name and renamed once complete,
+    // so consumers never pick up a partial file; an appended file is cut
+    // back to its previous length on failure
+    let write_path = if append_flag || matches.is_present("noatomic") {
+        output_file_path.to_path_buf()
+    } else {
+        part_path(output_file_path)
+    };
+    let previous_len = if append_flag {
+        match std::fs::metadata(output_file_path) {
+            Ok(m) => m.len(),
+            Err(e) => {
+                return Err((
+                    ExitCode::Output,
+                    format!(
+                        "{} to read output file {}: {}",
+                        "Failed".red(),
+                        output_file.yellow(),
+                        e
+                    ),
+                ));
+            }
+        }
+    } else {
+        0
+    };
+
+    let encryption = output_encryption(ctx, matches)?;
+
+    // set up the transforms applied to every row
+    let mut pipeline = row_pipeline(
+        ctx,
+        matches,
+        config,
+        table_name,
+        &table_def,
+        output_file_path,
+        &value_format,
+    )?;
+
+    // create output writer; a SQLite database is created once the header is known
+    if let Some(output) = create_output(
+        ctx,
+        matches,
+        &profile,
+        output_file_path,
+        &write_path,
+        append_flag,
+        encryption.as_ref(),
+    )? {
+        pipeline = pipeline.write_to(output);
+    }
+
+    // write csv header; possible cases are enforced by clap
+    let mut header: Vec<String> = table_def
+        .header()
+        .into_iter()
+        .map(|cn| match matches.value_of("headercase") {
+            Some("lower") => cn.to_lowercase(),
+            Some("upper") => cn.to_uppercase(),
+            _ => cn,
+        })
+        .collect();
+
+    // rejected rows are written with the reason instead of a row hash;
+    // by default named after the output file, so that every chunk and
+    // export keeps its own; rejects are never compressed
+    let rejects_file = side_file("rejects").unwrap_or_else(|| {
+        let path = labelled_path(output_file_path, "rejects");
+        match path.extension() {
+            Some(ext) if ext == "gz" => path.with_extension(""),
+            _ => path,
+        }
+    });
+    let rejects_file = Some(rejects_file).filter(|_| pipeline.validates());
+    if let Some(path) = &rejects_file {
+        let writer = create_rejects(path, &profile, encryption.as_ref(), &header)?;
+        pipeline = pipeline.reject_into(writer);
+    }
+
+    if let Some(hash_column) = matches.value_of("rowhash") {
+        header.push(String::from(hash_column));
+    }
+    // tables created from the export have no owner and are named like the header
+    let local_table = table_name.rsplit('.').next().unwrap_or(table_name);
+    let local_table = match matches.value_of("headercase") {
+        Some("lower") => local_table.to_lowercase(),
+        Some("upper") => local_table.to_uppercase(),
+        _ => String::from(local_table),
+    };
+    if let Some(ddl_file) = side_file("pgddl") {
+        write_ddl(ctx, &ddl_file, &local_table, &header, &table_def)?;
+    }
+    if sqlite_flag {
+        match SqliteWriter::create(&write_path, &local_table, &header, table_def.columns()) {
+            Ok(w) => pipeline = pipeline.write_to(RowOutput::Sqlite(w)),
+            Err(e) => {
+                return Err((
+                    ExitCode::Output,
+                    format!(
+                        "{} to create SQLite database {}: {}",
+                        "Failed".red(),
+                        output_file.yellow(),
+                        e
+                    ),
+                ));
+            }
+        }
+    }
+    // the COPY text format and SQLite have no header line
+    let write_header = !matches.is_present("noheader") && !pg_copy && !sqlite_flag;
+    // the first failure to write ends the output; later rows are only drained
+    let mut write_error: Option<lib_oradb::Error> = None;
+    if write_header && !append_flag {
+        write_error = pipeline.write_header(header).err();
+    }
+
+    match table_def.estimate_rows(conn.as_ref()) {
+        Ok(Some(n)) => status!(ctx; "Table statistics estimate {} rows.", n.to_string().blue()),
+        Ok(None) => status!(ctx; "No table statistics available to estimate row count."),
+        Err(e) => eprintln!("{} to estimate row count: {}", "Failed".red(), e),
+    };
+
+    // laod the data
+    let data = match table_def.load_threaded() {
+        Ok(dt) => dt,
+        Err(e) => {
+            return Err((
+                ExitCode::Load,
+                format!(
+                    "{} to read data for table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            ));
+        }
+    };
+
+    // the reader runs on a thread of its own, sharing the connection
+    // watched for interrupts
+    let loader = {
+        let conn = Arc::clone(conn);
+        data.spawn(move || Ok(conn))
+    };
+    let mut row_number: u64 = 0;
+    while let Some(rows) = loader.recv() {
+        if write_error.is_some() {
+            continue;
+        }
+        for row in rows {
+            row_number += 1;
+            if let (Some(st), Some(idx)) = (incremental_state.as_mut(), incremental_index) {
+                st.observe(&row[idx]);
+            }
+            match pipeline.process(row) {
+                Ok(truncated) => {
+                    for (column, length) in truncated {
+                        eprintln!(
+                            "{} column {} of row {} from {} bytes.",
+                            "Truncated".yellow(),
+                            column.yellow(),
+                            row_number.to_string().yellow(),
+                            length
+                        );
+                    }
+                }
+                Err(e) => {
+                    write_error = Some(e);
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(e) = write_error.as_ref() {
+        eprintln!("{} to write output: {}", "Failed".red(), e);
+    }
+
+    if let Err(e) = pipeline.finish_rejects() {
+        eprintln!("{} to complete rejects file: {}", "Failed".red(), e);
+    }
+
+    // flush remaining data and complete compression
+    let finished = match pipeline.finish() {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{} to complete output file: {}", "Failed".red(), e);
+            false
+        }
+    };
+    let written = finished && write_error.is_none();
+
+    let load_succeeded = match loader.join() {
+        Ok(n) => {
+            status!(
+                ctx;
+                "Database loading completed {} with {} rows.",
+                "successfully".green(),
+                n.to_string().blue()
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!("{} during database loading: {}", "Failure".red(), e);
+            false
+        }
+    };
+
+    write_collected(
+        ctx,
+        &pipeline,
+        side_file("hints"),
+        side_file("dictionaryfile"),
+    );
+
+    // rejected and duplicate rows are delivered but not part of the output file
+    let rejected = pipeline.rejected();
+    let duplicates = pipeline.duplicates();
+    let rows_written = data.rows_delivered() - rejected - duplicates;
+
+    /*for row in data.rows() {
+        csv_out.serialize(row).expect("Failed to serialize row.");
+        counter += 1;
+    }*/
+
+    // anything left in the queue was never written
+    if data.queue_depth() > 0 {
+        eprintln!(
+            "{} {} of {} delivered rows remain unwritten.",
+            "Incomplete:".red(),
+            data.queue_depth().to_string().yellow(),
+            data.rows_delivered().to_string().yellow()
+        );
+    }
+
+    let complete =
+        load_succeeded && written && data.queue_depth() == 0 && !interrupt::interrupted();
+    if !complete {
+        // partial output must not be mistaken for a complete export
+        discard_partial(output_file_path, &write_path, append_flag, previous_len);
+    } else {
+        // a new file replaces earlier output only once it is complete
+        if write_path != output_file_path {
+            if let Err(e) = std::fs::rename(&write_path, output_file_path) {
+                return Err((
+                    ExitCode::Output,
+                    format!(
+                        "{} to move {} into place: {}",
+                        "Failed".red(),
+                        write_path.display().to_string().yellow(),
+                        e
+                    ),
+                ));
+            }
+        }
+        status!(
+            ctx;
+            "{} completed writing {} rows.",
+            "Successfully".green(),
+            rows_written.to_string().green()
+        );
+        // an appended file counts only what this run added
+        let bytes = std::fs::metadata(output_file_path)
+            .map(|m| m.len().saturating_sub(previous_len))
+            .unwrap_or(0);
+        ctx.add_export(rows_written, bytes);
+        if let Some(path) = &rejects_file {
+            let message = format!(
+                "{} rows rejected into {}.",
+                rejected.to_string().yellow(),
+                path.display().to_string().yellow()
+            );
+            if rejected > 0 {
+                eprintln!("{} {}", "Warning:".yellow(), message);
+            } else {
+                status!(ctx; "{}", message);
+            }
+        }
+        if matches.is_present("distincton") {
+            status!(
+                ctx;
+                "{} duplicate rows skipped.",
+                duplicates.to_string().yellow()
+            );
+        }
+
+        if let Some(control_file) = side_file("controlfile") {
+            let control_file = control_file.display().to_string();
+            // an appended file also holds the rows of earlier runs
+            let rows = if append_flag {
+                count_records(output_file_path, profile.delimiter, write_header)
+            } else {
+                Ok(rows_written)
+            };
+            match rows
+                .and_then(|rows| {
+                    ControlFile::new(
+                        table_name,
+                        output_file_path,
+                        rows,
+                        write_header,
+                        started,
+                        extraction_scn,
+                        data.select_sql(),
+                    )
+                    .map_err(|e| e.into())
+                })
+                .and_then(|cf| cf.write(Path::new(&control_file)))
+            {
+                Ok(()) => status!(ctx; "Control file written to {}.", control_file.yellow()),
+                Err(e) => eprintln!(
+                    "{} to write control file {}: {}",
+                    "Failed".red(),
+                    control_file.yellow(),
+                    e
+                ),
+            }
+        }
+
+        // only remember progress of a complete export
+        if let (Some(st), Some(state_file)) =
+            (incremental_state.as_mut(), matches.value_of("state"))
+        {
+            match st.write(Path::new(state_file)) {
+                Ok(()) => status!(ctx; "State written to {}.", state_file.yellow()),
+                Err(e) => eprintln!(
+                    "{} to write state file {}: {}",
+                    "Failed".red(),
+                    state_file.yellow(),
+                    e
+                ),
+            }
+        }
+    }
+
+    if complete {
+        Ok(rows_written)
+    } else if interrupt::interrupted() {
+        Err((
+            ExitCode::Interrupted,
+            format!("Export {}.", "interrupted".red()),
+        ))
+    } else {
+        Err((
+            ExitCode::Load,
+            format!(
+                "{} to export table {} into {}.",
+                "Failed".red(),
+                table_name.yellow(),
+                output_file_path.display().to_string().yellow()
+            ),
+        ))
+    }
+}
+
+///
+/// Exports a table one date partition at a time into separate files;
+/// partitions with an existing output file are skipped unless forced,
+/// failed ones are retried up to `--retries` times
+pub fn backfill(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    config: &Config,
+    conn: &Arc<oracle::Connection>,
+    selection: Selection,
+    output_file_path: &Path,
+) {
+    let table_name = selection.table_name;
+    // values are validated by clap
+    let granularity = Granularity::parse(matches.value_of("granularity").unwrap()).unwrap();
+    let from = parse_date(matches.value_of("from").unwrap()).unwrap();
+    let to = parse_date(matches.value_of("to").unwrap()).unwrap();
+    let retries: u32 = matches.value_of("retries").unwrap().parse().unwrap();
+    let force_flag = matches.is_present("force");
+
+    let partitions = match partitions(from, to, granularity) {
+        Ok(p) => p,
+        Err(e) => {
+            fail_run(
+                ctx,
+                ExitCode::Input,
+                Some(table_name),
+                format!("{} to plan backfill: {}", "Failed".red(), e),
+            );
+        }
+    };
+    status!(
+        ctx;
+        "Backfilling {} partitions.",
+        partitions.len().to_string().blue()
+    );
+
+    let mut failed: Vec<&str> = Vec::new();
+    let mut failures: Vec<Failure> = Vec::new();
+    for partition in &partitions {
+        let path = partition.file_path(output_file_path);
+        if path.exists() && !force_flag {
+            status!(
+                ctx;
+                "Partition {} already exported to {}; skipping.",
+                partition.label().blue(),
+                path.display().to_string().yellow()
+            );
+            continue;
+        }
+
+        status!(
+            ctx;
+            "Exporting partition {} into {}.",
+            partition.label().blue(),
+            path.display().to_string().yellow()
+        );
+        let mut attempt: u32 = 0;
+        // every failure is retried, not only failed loads
+        while let Err((code, message)) = export_table(
+            ctx,
+            matches,
+            config,
+            conn,
+            selection,
+            &path,
+            Some(&Chunk::Dates(partition)),
+        ) {
+            eprintln!("{}", message);
+            attempt += 1;
+            if attempt > retries || interrupt::interrupted() {
+                failed.push(partition.label());
+                failures.push((code, message));
+                break;
+            }
+            eprintln!(
+                "{} partition {} ({} of {}).",
+                "Retrying".yellow(),
+                partition.label().yellow(),
+                attempt,
+                retries
+            );
+        }
+        if interrupt::interrupted() {
+            return;
+        }
+    }
+
+    if !failed.is_empty() {
+        fail_run(
+            ctx,
+            failure_code(&failures),
+            Some(table_name),
+            format!(
+                "{} to export partitions {}.",
+                "Failed".red(),
+                failed.join(", ").yellow()
+            ),
+        );
+    }
+}
+
+///
+/// Exports every partition of a table into a file of its own named
+/// `<table>_<partition>` next to the output file, using up to
+/// `--parallel` connections; partitions with an existing file are
+/// skipped unless forced
+pub fn fan_out(
+    ctx: &RunContext,
+    matches: &ArgMatches,
+    config: &Config,
+    conn: &Arc<oracle::Connection>,
+    selection: Selection,
+    output_file_path: &Path,
+) {
+    let table_name = selection.table_name;
+    // validated by clap
+    let parallel: usize = matches
+        .value_of("parallel")
+        .map_or(1, |n| n.parse().unwrap());
+    let force_flag = matches.is_present("force");
+
+    // the owner qualifies the name the same way as in the data query
+    let table = match matches.value_of("owner") {
+        Some(owner) => TableName::parse(table_name).with_owner(owner),
+        None => Ok(TableName::parse(table_name)),
+    };
+    let partitions = match table.and_then(|t| list_partitions(conn.as_ref(), &t)) {
+        Ok(p) if p.is_empty() => fail_run(
+            ctx,
+            ExitCode::Definition,
+            Some(table_name),
+            format!(
+                "Table {} {}.",
+                table_name.yellow(),
+                "has no partitions".red()
+            ),
+        ),
+        Ok(p) => p,
+        Err(e) => fail_run(
+            ctx,
+            ExitCode::Definition,
+            Some(table_name),
+            format!(
+                "{} to list partitions of table {}: {}",
+                "Failed".red(),
+                table_name.yellow(),
+                e
+            ),
+        ),
+    };
+    status!(
+        ctx;
+        "Exporting {} partitions.",
+        partitions.len().to_string().blue()
+    );
+
+    // the file extension of the output file is kept, e.g. `.csv.gz`
+    let output_name = output_file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = output_name
+        .find('.')
+        .map_or(".csv", |idx| &output_name[idx..]);
+    let short_name = table_name.rsplit('.').next().unwrap_or(table_name);
+
+    let queue: Mutex<VecDeque<&PartitionInfo>> = Mutex::new(partitions.iter().collect());
+    let results: Mutex<Vec<Vec<String>>> = Mutex::new(Vec::new());
+    let failures: Mutex<Vec<Failure>> = Mutex::new(Vec::new());
+    let record_failure = |failure: Failure| {
+        if let Ok(mut f) = failures.lock() {
+            f.push(failure);
+        }
+    };
+    let export_partitions = |conn: &Arc<oracle::Connection>| loop {
+        let partition = match queue.lock().ok().and_then(|mut q| q.pop_front()) {
+            Some(p) if !interrupt::interrupted() => p,
+            _ => break,
+        };
+        let path = output_file_path.with_file_name(format!(
+            "{}_{}{}",
+            short_name,
+            partition.partition_name(),
+            extension
+        ));
+
+        let rows = if path.exists() && !force_flag {
+            status!(
+                ctx;
+                "Partition {} already exported to {}; skipping.",
+                partition.partition_name().blue(),
+                path.display().to_string().yellow()
+            );
+            String::from("skipped")
+        } else {
+            status!(
+                ctx;
+                "Exporting partition {} into {}.",
+                partition.partition_name().blue(),
+                path.display().to_string().yellow()
+            );
+            match export_table(
+                ctx,
+                matches,
+                config,
+                conn,
+                selection,
+                &path,
+                Some(&Chunk::Table(partition.partition_name())),
+            ) {
+                Ok(n) => n.to_string(),
+                Err(failure) => {
+                    record_failure(failure);
+                    String::from("failed")
+                }
+            }
+        };
+        if let Ok(mut r) = results.lock() {
+            r.push(vec![
+                String::from(partition.partition_name()),
+                path.display().to_string(),
+                rows,
+            ]);
+        }
+    };
+
+    if parallel <= 1 {
+        export_partitions(conn);
+    } else {
+        std::thread::scope(|scope| {
+            scope.spawn(|| export_partitions(conn));
+            for _ in 1..parallel.min(partitions.len()) {
+                scope.spawn(|| {
+                    // each worker reads through a connection of its own;
+                    // without one the remaining workers take its share
+                    match try_connect(config) {
+                        Ok(worker_conn) => {
+                            let worker_conn = Arc::new(worker_conn);
+                            interrupt::watch(Arc::clone(&worker_conn));
+                            export_partitions(&worker_conn);
+                        }
+                        Err(failure) => record_failure(failure),
+                    }
+                });
+            }
+        });
+    }
+
+    // summary in partition order
+    let mut results = results.into_inner().unwrap_or_default();
+    results.sort_by_key(|r| partitions.iter().position(|p| p.partition_name() == r[0]));
+    print_table(&["PARTITION_NAME", "FILE", "ROWS"], &results);
+
+    let failures = failures.into_inner().unwrap_or_default();
+    if !failures.is_empty() {
+        for (_, message) in &failures {
+            eprintln!("{}", message);
+        }
+        fail_run(
+            ctx,
+            failure_code(&failures),
+            Some(table_name),
+            format!("{} to export all partitions.", "Failed".red()),
+        );
+    }
+}
+
+///
+/// Picks the exit code for a set of failures: an interruption wins,
+/// a code shared by all failures is kept, anything else is a load failure
+fn failure_code(failures: &[Failure]) -> ExitCode {
+    if interrupt::interrupted() {
+        return ExitCode::Interrupted;
+    }
+    match failures.split_first() {
+        Some(((code, _), rest)) if rest.iter().all(|(c, _)| c == code) => *code,
+        _ => ExitCode::Load,
+    }
+}
@@ -0,0 +1,278 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Output type hints derived from observed data
+//!
+
+use lib_oradb::definition::ColumnValue;
+use std::path::Path;
+
+///
+/// Kind of value observed in a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ObservedKind {
+    Integer,
+    Decimal,
+    Text,
+    Boolean,
+    Date,
+    DateTime,
+    Binary,
+}
+
+///
+/// Statistics collected for a single column
+#[derive(Debug, Default)]
+struct ColumnStats {
+    /// kind of values seen so far
+    kind: Option<ObservedKind>,
+    /// number of null values
+    null_count: u64,
+    /// longest value in characters, or bytes for binary values
+    max_length: usize,
+    /// most digits in front of the decimal point
+    max_integer_digits: usize,
+    /// most digits after the decimal point
+    max_scale: usize,
+}
+
+///
+/// A single line in the hints file
+#[derive(Serialize)]
+struct HintRecord<'a> {
+    column_name: &'a str,
+    suggested_type: String,
+    nullable: bool,
+    null_count: u64,
+    max_length: usize,
+    precision: usize,
+    scale: usize,
+}
+
+///
+/// Collects per column statistics while exporting and
+/// derives suggested target types from them.
+pub struct HintCollector {
+    column_names: Vec<String>,
+    stats: Vec<ColumnStats>,
+}
+
+impl ColumnStats {
+    ///
+    /// Records a single value
+    fn observe(&mut self, value: &Option<ColumnValue>) {
+        let (kind, text) = match value {
+            None => {
+                self.null_count += 1;
+                return;
+            }
+            Some(ColumnValue::Varchar(v)) => (ObservedKind::Text, v.clone()),
+            Some(ColumnValue::Number(v)) => (ObservedKind::Integer, v.to_string()),
+            Some(ColumnValue::Float(v)) => (ObservedKind::Decimal, v.to_string()),
+            Some(ColumnValue::Decimal(v)) => (ObservedKind::Decimal, v.clone()),
+            Some(ColumnValue::Boolean(v)) => (ObservedKind::Boolean, v.to_string()),
+            Some(ColumnValue::Date(v)) => (ObservedKind::Date, v.format("%Y-%m-%d").to_string()),
+            Some(ColumnValue::DateTime(v)) => (
+                ObservedKind::DateTime,
+                v.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ),
+            Some(ColumnValue::Binary(v)) => {
+                self.kind = match self.kind {
+                    None | Some(ObservedKind::Binary) => Some(ObservedKind::Binary),
+                    Some(_) => Some(ObservedKind::Text),
+                };
+                self.max_length = self.max_length.max(v.len());
+                return;
+            }
+        };
+
+        self.kind = match self.kind {
+            None => Some(kind),
+            Some(k) if k == kind => Some(k),
+            // integers fit into decimals, anything else falls back to text
+            Some(ObservedKind::Integer) if kind == ObservedKind::Decimal => Some(kind),
+            Some(ObservedKind::Decimal) if kind == ObservedKind::Integer => {
+                Some(ObservedKind::Decimal)
+            }
+            Some(_) => Some(ObservedKind::Text),
+        };
+
+        self.max_length = self.max_length.max(text.chars().count());
+
+        if kind == ObservedKind::Integer || kind == ObservedKind::Decimal {
+            let digits = text.trim_start_matches('-');
+            let (integer_part, fraction_part) = match digits.find('.') {
+                Some(idx) => (&digits[..idx], &digits[idx + 1..]),
+                None => (digits, ""),
+            };
+            self.max_integer_digits = self.max_integer_digits.max(integer_part.len());
+            self.max_scale = self.max_scale.max(fraction_part.len());
+        }
+    }
+
+    ///
+    /// Suggests a target type for the observed values
+    fn suggested_type(&self) -> String {
+        match self.kind {
+            Some(ObservedKind::Integer) => format!("DECIMAL({},0)", self.max_integer_digits.max(1)),
+            Some(ObservedKind::Decimal) => format!(
+                "DECIMAL({},{})",
+                (self.max_integer_digits + self.max_scale).max(1),
+                self.max_scale
+            ),
+            Some(ObservedKind::Boolean) => String::from("BOOLEAN"),
+            Some(ObservedKind::Date) => String::from("DATE"),
+            Some(ObservedKind::DateTime) => String::from("TIMESTAMP"),
+            Some(ObservedKind::Binary) => format!("VARBINARY({})", self.max_length.max(1)),
+            Some(ObservedKind::Text) | None => format!("VARCHAR({})", self.max_length.max(1)),
+        }
+    }
+}
+
+impl HintCollector {
+    ///
+    /// Creates a new collector for the given columns
+    pub fn new(column_names: Vec<String>) -> HintCollector {
+        let stats = column_names
+            .iter()
+            .map(|_| ColumnStats::default())
+            .collect();
+
+        HintCollector {
+            column_names,
+            stats,
+        }
+    }
+
+    ///
+    /// Records the values of a single row
+    pub fn observe(&mut self, row: &[Option<ColumnValue>]) {
+        for (stats, value) in self.stats.iter_mut().zip(row.iter()) {
+            stats.observe(value);
+        }
+    }
+
+    ///
+    /// Writes hints as csv file
+    pub fn write(&self, filename: &Path) -> Result<(), csv::Error> {
+        let mut out = csv::Writer::from_path(filename)?;
+
+        for (column_name, stats) in self.column_names.iter().zip(self.stats.iter()) {
+            let is_decimal = matches!(
+                stats.kind,
+                Some(ObservedKind::Integer) | Some(ObservedKind::Decimal)
+            );
+            out.serialize(HintRecord {
+                column_name,
+                suggested_type: stats.suggested_type(),
+                nullable: stats.null_count > 0,
+                null_count: stats.null_count,
+                max_length: stats.max_length,
+                precision: if is_decimal {
+                    stats.max_integer_digits + stats.max_scale
+                } else {
+                    0
+                },
+                scale: if is_decimal { stats.max_scale } else { 0 },
+            })?;
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColumnStats, HintCollector};
+    use lib_oradb::definition::ColumnValue;
+
+    ///
+    /// Observes the given values in a fresh column
+    fn observed(values: &[Option<ColumnValue>]) -> ColumnStats {
+        let mut stats = ColumnStats::default();
+        for value in values {
+            stats.observe(value);
+        }
+
+        stats
+    }
+
+    #[test]
+    fn test_numbers_widen_to_decimal() {
+        let integers = observed(&[
+            Some(ColumnValue::Number(-12345)),
+            Some(ColumnValue::Number(7)),
+        ]);
+        assert_eq!("DECIMAL(5,0)", integers.suggested_type());
+
+        let mixed = observed(&[
+            Some(ColumnValue::Number(1234)),
+            Some(ColumnValue::Decimal(String::from("-1.125"))),
+        ]);
+        assert_eq!("DECIMAL(7,3)", mixed.suggested_type());
+    }
+
+    #[test]
+    fn test_mixed_kinds_fall_back_to_text() {
+        let stats = observed(&[
+            Some(ColumnValue::Number(42)),
+            Some(ColumnValue::Varchar(String::from("forty-two"))),
+            None,
+        ]);
+        assert_eq!("VARCHAR(9)", stats.suggested_type());
+        assert_eq!(1, stats.null_count);
+
+        let binary = observed(&[Some(ColumnValue::Binary(vec![0; 16]))]);
+        assert_eq!("VARBINARY(16)", binary.suggested_type());
+        let only_nulls = observed(&[None, None]);
+        assert_eq!("VARCHAR(1)", only_nulls.suggested_type());
+    }
+
+    #[test]
+    fn test_write_hints_file() {
+        let filename =
+            std::env::temp_dir().join(format!("csvdump-hints-test-{}.csv", std::process::id()));
+        let mut collector = HintCollector::new(vec![String::from("ID"), String::from("NAME")]);
+        collector.observe(&[Some(ColumnValue::Number(10)), None]);
+        collector.observe(&[
+            Some(ColumnValue::Number(200)),
+            Some(ColumnValue::Varchar(String::from("Ann"))),
+        ]);
+        collector.write(&filename).unwrap();
+
+        let contents = std::fs::read_to_string(&filename).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        assert_eq!(
+            "column_name,suggested_type,nullable,null_count,max_length,precision,scale\n\
+             ID,\"DECIMAL(3,0)\",false,0,3,3,0\n\
+             NAME,VARCHAR(3),true,1,3,0,0\n",
+            contents
+        );
+    }
+}
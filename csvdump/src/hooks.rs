@@ -0,0 +1,147 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Commands run after an export, e.g. to ship the output file
+//!
+
+use std::process::{Command, ExitStatus};
+
+///
+/// Configured commands of a run with the values for their placeholders
+/// `{output}`, `{table}`, `{rows}` and `{exit_code}`
+pub struct PostRunHooks {
+    /// command run after a successful export
+    success: Option<String>,
+    /// command run after a failed export
+    failure: Option<String>,
+    /// output file of the export
+    output: String,
+    /// table once its name is known
+    table: Option<String>,
+    /// rows written to all output files
+    rows: u64,
+}
+
+///
+/// Quotes a placeholder value as a single word for the shell
+#[cfg(not(windows))]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+///
+/// Quotes a placeholder value as a single word for the shell
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+///
+/// Runs a command line through the platform shell and waits for it
+#[cfg(not(windows))]
+fn run_shell(command: &str) -> std::io::Result<ExitStatus> {
+    Command::new("sh").arg("-c").arg(command).status()
+}
+
+///
+/// Runs a command line through the platform shell and waits for it
+#[cfg(windows)]
+fn run_shell(command: &str) -> std::io::Result<ExitStatus> {
+    Command::new("cmd").arg("/C").arg(command).status()
+}
+
+impl PostRunHooks {
+    pub fn new(success: Option<&str>, failure: Option<&str>, output: &str) -> PostRunHooks {
+        PostRunHooks {
+            success: success.map(String::from),
+            failure: failure.map(String::from),
+            output: String::from(output),
+            table: None,
+            rows: 0,
+        }
+    }
+
+    ///
+    /// Sets the table once its name is resolved
+    pub fn set_table(&mut self, table: &str) {
+        self.table = Some(String::from(table));
+    }
+
+    ///
+    /// Adds the rows of one complete output file, e.g. a partition
+    pub fn add_rows(&mut self, rows: u64) {
+        self.rows += rows;
+    }
+
+    ///
+    /// Replaces the placeholders of a command with quoted values;
+    /// other braces are left alone, e.g. for `awk '{print}'`
+    fn expand(&self, command: &str, exit_code: i32) -> String {
+        let values = [
+            ("{output}", shell_quote(&self.output)),
+            ("{table}", shell_quote(self.table.as_deref().unwrap_or(""))),
+            ("{rows}", self.rows.to_string()),
+            ("{exit_code}", exit_code.to_string()),
+        ];
+
+        // a single pass keeps placeholders within values as they are
+        let mut expanded = String::new();
+        let mut rest = command;
+        while let Some(pos) = rest.find('{') {
+            expanded.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            match values.iter().find(|(p, _)| rest.starts_with(p)) {
+                Some((placeholder, value)) => {
+                    expanded.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    expanded.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+
+        expanded
+    }
+
+    ///
+    /// Runs the success or failure command for an exit code, if one
+    /// is configured, returning the command line and how it ended
+    pub fn run(&self, exit_code: i32) -> Option<(String, std::io::Result<ExitStatus>)> {
+        let command = if exit_code == 0 {
+            self.success.as_ref()
+        } else {
+            self.failure.as_ref()
+        }?;
+        let command = self.expand(command, exit_code);
+        let status = run_shell(&command);
+
+        Some((command, status))
+    }
+}
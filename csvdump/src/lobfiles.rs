@@ -0,0 +1,145 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Export of LOB values into individual side files
+//!
+
+use lib_oradb::definition::{ColumnDefinition, ColumnValue, DataType};
+use std::path::{Path, PathBuf};
+
+///
+/// Writes the values of CLOB and BLOB columns into one file per row and
+/// column, named after key columns, and puts the relative path into the
+/// output record instead
+pub struct LobWriter {
+    /// directory the files are written to
+    directory: PathBuf,
+    /// directory as written into the output, relative to the output file
+    relative_dir: String,
+    /// positions of the key columns naming the files
+    key_columns: Vec<usize>,
+    /// positions and names of the LOB columns
+    lob_columns: Vec<(usize, String)>,
+}
+
+///
+/// Makes a key value safe for use in a file name
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl LobWriter {
+    ///
+    /// Creates a `LobWriter` and its directory, which is resolved
+    /// against the directory of the output file unless absolute
+    pub fn new<S: AsRef<str>>(
+        output_file: &Path,
+        lob_dir: &str,
+        key_names: &[S],
+        columns: &[ColumnDefinition],
+    ) -> Result<LobWriter, String> {
+        let mut key_columns = Vec::new();
+        for name in key_names {
+            // unquoted names are case-insensitive like on the database side
+            let name = name.as_ref();
+            match columns.iter().position(|col| {
+                col.column_name() == name || col.column_name() == name.to_uppercase()
+            }) {
+                Some(idx) => key_columns.push(idx),
+                None => return Err(format!("Key column {} is not exported", name)),
+            }
+        }
+
+        let lob_columns: Vec<(usize, String)> = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| matches!(col.data_type(), DataType::CLob | DataType::BLob))
+            .map(|(idx, col)| (idx, String::from(col.column_name())))
+            .collect();
+        if lob_columns.is_empty() {
+            return Err(String::from("No CLOB or BLOB columns are exported"));
+        }
+        if let Some((_, name)) = lob_columns
+            .iter()
+            .find(|(idx, _)| key_columns.contains(idx))
+        {
+            return Err(format!("LOB column {} cannot name files", name));
+        }
+
+        let directory = output_file
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(lob_dir);
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("Cannot create {}: {}", directory.display(), e))?;
+
+        Ok(LobWriter {
+            directory,
+            relative_dir: String::from(lob_dir.trim_end_matches('/')),
+            key_columns,
+            lob_columns,
+        })
+    }
+
+    ///
+    /// Writes the LOB values of a row into files and replaces them in the
+    /// formatted record by the relative file paths; NULL values keep
+    /// their formatted text and get no file
+    pub fn write(&self, row: &[Option<ColumnValue>], record: &mut [String]) -> std::io::Result<()> {
+        let key: Vec<String> = self
+            .key_columns
+            .iter()
+            .map(|idx| sanitize(&record[*idx]))
+            .collect();
+        let key = key.join("_");
+
+        for (idx, column_name) in &self.lob_columns {
+            let content: &[u8] = match &row[*idx] {
+                None => continue,
+                Some(ColumnValue::Binary(v)) => v,
+                Some(ColumnValue::Varchar(v)) => v.as_bytes(),
+                // masking may have replaced the value
+                Some(_) => record[*idx].as_bytes(),
+            };
+            let file_name = format!("{}_{}.bin", key, column_name);
+            std::fs::write(self.directory.join(&file_name), content)?;
+
+            record[*idx] = format!("{}/{}", self.relative_dir, file_name);
+        }
+
+        Ok(())
+    }
+}
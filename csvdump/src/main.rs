@@ -25,378 +25,3081 @@
  * SUCH DAMAGE.
  */
 
+extern crate chrono;
 extern crate clap;
 extern crate toml;
 #[macro_use]
 extern crate serde;
 extern crate colored;
 extern crate csv;
+extern crate flate2;
 extern crate lib_oradb;
 extern crate log;
 extern crate oracle;
-extern crate simplelog;
+extern crate regex;
+extern crate serde_json;
+extern crate sha2;
+#[cfg(unix)]
+extern crate signal_hook;
 
+///
+/// Prints a progress message unless `--quiet` is set; within a run
+/// the setting is taken from its context, e.g. `status!(ctx; "...")`
+macro_rules! status {
+    ($ctx:expr; $($arg:tt)*) => {
+        if !$ctx.quiet() {
+            println!($($arg)*);
+        }
+    };
+    ($($arg:tt)*) => {
+        if !$crate::QUIET.load(std::sync::atomic::Ordering::SeqCst) {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod backfill;
 mod config;
+mod context;
+mod control;
+mod dedupe;
+mod defaults;
+mod delivery;
+mod dictionary;
+mod diff;
+mod export;
+mod hints;
+mod hooks;
+mod interrupt;
+mod lobfiles;
+mod masking;
+mod metrics;
+mod naming;
+mod output;
+mod pipeline;
+mod pool;
+mod postgres;
+mod profile;
+mod retention;
+mod rowhash;
+mod sqlite;
+mod state;
+mod stats;
+mod truncate;
+mod usage;
+mod validation;
+mod wizard;
+
+use backfill::parse_date;
+use chrono::TimeZone;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, Shell, SubCommand};
+use colored::*;
+use config::{Config, ConnectionOverrides};
+use context::RunContext;
+use control::ControlFile;
+use delivery::SftpTarget;
+use diff::{Change, RowDiff};
+use export::{backfill, export_table, fan_out, Selection};
+use hooks::PostRunHooks;
+use lib_oradb::definition::{
+    current_scn, describe_table, initialize_session, insert_rows, list_partitions, list_tables,
+    BindValue, DataType, RowSeed, TableName, TableSelectionBuilder, ValueFormat,
+    DEFAULT_DATETIME_FORMAT, DEFAULT_DATE_FORMAT,
+};
+use lib_oradb::logging::{LogConfig, LogFormat};
+use metrics::RunMetrics;
+use naming::TableNamer;
+use profile::OutputProfile;
+use retention::{control_files_for, RetentionPolicy};
+use stats::TableProfiler;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use usage::CommandUsage;
+use wizard::Prompter;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+///
+/// Exit codes reported to calling scripts
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExitCode {
+    /// input, column or exclude file unreadable, or invalid option combination
+    Input = 2,
+    /// configuration file, control file or input file not loadable
+    Config = 5,
+    /// database connection or session setup failed
+    Connect = 10,
+    /// table name could not be determined
+    TableName = 11,
+    /// table definition could not be read
+    Definition = 12,
+    /// reading, sampling or backfilling table data failed
+    Load = 13,
+    /// output file exists and overwriting was not requested
+    Exists = 14,
+    /// output file or target table could not be written
+    Output = 15,
+    /// masking or dictionary encoding could not be set up
+    Masking = 16,
+    /// row hash column clashes with an exported column
+    RowHash = 17,
+    /// incremental state or system change number unavailable
+    State = 18,
+    /// output file does not match its control file
+    Verify = 19,
+    /// sampled values do not match their declared column types
+    Drift = 20,
+    /// validation rules could not be set up
+    Validation = 21,
+    /// compared exports differ
+    Diff = 22,
+    /// post-export command failed
+    Hook = 23,
+    /// export was interrupted by SIGINT or SIGTERM
+    Interrupted = 130,
+}
+
+impl ExitCode {
+    ///
+    /// Gets the phase of a run the exit code is reported in
+    fn phase(self) -> &'static str {
+        match self {
+            ExitCode::Input | ExitCode::Config | ExitCode::Exists => "setup",
+            ExitCode::Connect => "connect",
+            ExitCode::TableName | ExitCode::Definition => "definition",
+            ExitCode::Drift => "sample",
+            ExitCode::Load | ExitCode::Interrupted => "load",
+            ExitCode::Output | ExitCode::Masking | ExitCode::RowHash | ExitCode::Validation => {
+                "output"
+            }
+            ExitCode::State => "state",
+            ExitCode::Verify => "verify",
+            ExitCode::Diff => "diff",
+            ExitCode::Hook => "hook",
+        }
+    }
+}
+
+/// set if errors are reported as JSON objects
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+/// set if progress messages are suppressed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+///
+/// Error object printed with `--error-format json`
+#[derive(Serialize)]
+struct ErrorReport<'a> {
+    code: i32,
+    message: &'a str,
+    table: Option<&'a str>,
+    phase: &'static str,
+}
+
+///
+/// Writes the metrics file, if requested, for a run ending with an exit code
+fn write_metrics(ctx: &RunContext, exit_code: i32) {
+    if let Err(e) = ctx.write_metrics(exit_code) {
+        eprintln!("{} to write metrics file: {}", "Failed".red(), e);
+    }
+}
+
+///
+/// Runs the configured post-export command for an exit code, if any,
+/// returning whether it succeeded
+fn run_hooks(ctx: &RunContext, exit_code: i32) -> bool {
+    // taken out so a command runs once even if it fails
+    let hooks = match ctx.take_hooks() {
+        Some(h) => h,
+        None => return true,
+    };
+    let succeeded = match hooks.run(exit_code) {
+        None => true,
+        Some((command, Ok(status))) if status.success() => {
+            status!(
+                ctx;
+                "Post-export command {} {}.",
+                command.yellow(),
+                "succeeded".green()
+            );
+            true
+        }
+        Some((command, Ok(status))) => {
+            eprintln!(
+                "Post-export command {} {} with {}.",
+                command.yellow(),
+                "failed".red(),
+                status
+            );
+            false
+        }
+        Some((command, Err(e))) => {
+            eprintln!(
+                "{} to run post-export command {}: {}",
+                "Failed".red(),
+                command.yellow(),
+                e
+            );
+            false
+        }
+    };
+    // a failed success command still leads to the failure command
+    if !succeeded && exit_code == 0 {
+        ctx.set_hooks(hooks);
+    }
+
+    succeeded
+}
+
+///
+/// Prints an error in the requested error format
+fn report_error(json_errors: bool, code: ExitCode, table: Option<&str>, message: &str) {
+    if json_errors {
+        let report = ErrorReport {
+            code: code as i32,
+            message,
+            table,
+            phase: code.phase(),
+        };
+        // serializing strings and numbers cannot fail
+        eprintln!("{}", serde_json::to_string(&report).unwrap());
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+///
+/// Reports an error in the requested error format and exits
+fn fail(code: ExitCode, table: Option<&str>, message: String) -> ! {
+    report_error(JSON_ERRORS.load(Ordering::SeqCst), code, table, &message);
+    std::process::exit(code as i32);
+}
+
+///
+/// Reports an error of a run and exits, after writing the metrics
+/// and running the post-failure command of the run
+fn fail_run(ctx: &RunContext, code: ExitCode, table: Option<&str>, message: String) -> ! {
+    write_metrics(ctx, code as i32);
+    report_error(ctx.json_errors(), code, table, &message);
+    run_hooks(ctx, code as i32);
+    std::process::exit(code as i32);
+}
+
+///
+/// Exit code and message of a failure that is left to the caller to report
+type Failure = (ExitCode, String);
+
+///
+/// Reads column names from file; `*` selects all columns
+/// and names prefixed with `!` are left out
+fn read_parameters_file(
+    filename: &Path,
+    uppercase_flag: bool,
+) -> Result<Vec<String>, std::io::Error> {
+    let fulltext = std::fs::read_to_string(filename)?;
+    let separated_lines: Vec<&str> = fulltext.lines().collect();
+    let cleaned_cols: Vec<String> = separated_lines
+        .into_iter()
+        .map(|colname| {
+            let (prefix, name) = match colname.trim().strip_prefix('!') {
+                Some(name) => ("!", name.trim()),
+                None => ("", colname.trim()),
+            };
+            // quoted identifiers keep their case
+            if uppercase_flag && !name.starts_with('"') {
+                format!("{}{}", prefix, name.to_uppercase())
+            } else {
+                format!("{}{}", prefix, name)
+            }
+        })
+        .filter(|colname| !colname.is_empty())
+        .collect();
+
+    Ok(cleaned_cols)
+}
+
+///
+/// Checks that an argument is a single character
+fn single_char(text: String) -> Result<(), String> {
+    match text.chars().count() {
+        1 => Ok(()),
+        _ => Err(String::from("expected a single character")),
+    }
+}
+
+///
+/// Parses a sample percentage given as `PERCENT` or `PERCENT%`
+fn parse_sample_percent(text: &str) -> Result<f64, String> {
+    match text.strip_suffix('%').unwrap_or(text).parse::<f64>() {
+        // the range Oracle accepts in a SAMPLE clause
+        Ok(p) if (0.000001..100.0).contains(&p) => Ok(p),
+        _ => Err(format!(
+            "{} is not a sample percentage between 0.000001 and 100 (exclusive)",
+            text
+        )),
+    }
+}
+
+///
+/// Parses the data type of a column override such as `varchar`, `number(10,2)`
+/// or `timestamp`; names are case-insensitive
+fn parse_type_name(text: &str) -> Option<DataType> {
+    let text = text.trim().to_lowercase();
+    let (name, arguments) = match text.strip_suffix(')').and_then(|t| t.split_once('(')) {
+        Some((name, arguments)) => (name.trim(), Some(arguments)),
+        None => (text.as_str(), None),
+    };
+    let numbers = match arguments {
+        Some(a) => Some(
+            a.split(',')
+                .map(|n| n.trim().parse().ok())
+                .collect::<Option<Vec<u32>>>()?,
+        ),
+        None => None,
+    };
+
+    match (name, numbers.as_deref()) {
+        // the longest VARCHAR2 with standard string size
+        ("varchar" | "varchar2" | "text", None) => Some(DataType::VarChar(4000)),
+        ("varchar" | "varchar2", Some(&[length])) if length > 0 => Some(DataType::VarChar(length)),
+        ("number" | "decimal", None) => Some(DataType::Number(None, None)),
+        ("number" | "decimal", Some(&[precision])) => Some(DataType::Number(Some(precision), None)),
+        ("number" | "decimal", Some(&[precision, scale])) => {
+            Some(DataType::Number(Some(precision), Some(scale as i32)))
+        }
+        ("integer", None) => Some(DataType::Number(Some(18), Some(0))),
+        ("float" | "double", None) => Some(DataType::BinaryDouble),
+        ("date", None) => Some(DataType::Date),
+        ("timestamp", None) => Some(DataType::DateTime),
+        ("clob", None) => Some(DataType::CLob),
+        _ => None,
+    }
+}
+
+///
+/// Splits an input file entry `COLUMN[:TYPE]` into the column name and the
+/// data type the column is exported as
+fn parse_column_entry(text: &str) -> Result<(&str, Option<DataType>), String> {
+    // quoted names may contain colons themselves
+    let (name, type_name) = match text.rsplit_once(':') {
+        Some((name, type_name)) if !type_name.contains('"') => (name.trim(), type_name),
+        _ => return Ok((text, None)),
+    };
+    if name.is_empty() || name == "*" || name.starts_with('!') {
+        return Err(format!("{} does not name a single selected column", text));
+    }
+    match parse_type_name(type_name) {
+        Some(data_type) => Ok((name, Some(data_type))),
+        None => Err(format!(
+            "{} is not a supported data type, expected varchar[(LENGTH)], \
+             number[(PRECISION[,SCALE])], integer, float, date, timestamp or clob",
+            type_name
+        )),
+    }
+}
+
+///
+/// Parses a filter bind value given as `[int:|num:|float:|date:|ts:|str:]VALUE`;
+/// values without type prefix are bound as text
+fn parse_bind(text: &str) -> Result<BindValue, String> {
+    let invalid = |kind: &str| format!("{} is not a valid {} bind value", text, kind);
+    let (kind, value) = text.split_once(':').unwrap_or(("str", text));
+
+    match kind {
+        "int" => value
+            .parse()
+            .map(BindValue::Number)
+            .map_err(|_| invalid(kind)),
+        "num" => value
+            .parse::<f64>()
+            .map(|_| BindValue::Decimal(String::from(value)))
+            .map_err(|_| invalid(kind)),
+        "float" => value
+            .parse()
+            .map(BindValue::Float)
+            .map_err(|_| invalid(kind)),
+        "date" => chrono::NaiveDate::parse_from_str(value, DEFAULT_DATE_FORMAT)
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| BindValue::Date(chrono::Utc.from_utc_datetime(&d)))
+            .ok_or_else(|| invalid(kind)),
+        "ts" => chrono::NaiveDateTime::parse_from_str(value, DEFAULT_DATETIME_FORMAT)
+            .map(|d| BindValue::DateTime(chrono::Utc.from_utc_datetime(&d)))
+            .map_err(|_| invalid(kind)),
+        "str" => Ok(BindValue::Varchar(String::from(value))),
+        // a colon without known prefix is part of the text
+        _ => Ok(BindValue::Varchar(String::from(text))),
+    }
+}
+
+///
+/// Reads key values, one per line with optional bind type prefix,
+/// from a keys file; empty lines are skipped
+fn read_keys_file(filename: &Path) -> Result<Vec<BindValue>, String> {
+    let text = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| parse_bind(line.trim()).map_err(|e| format!("line {}: {}", idx + 1, e)))
+        .collect()
+}
+
+///
+/// Reads connection settings given on the command line
+fn connection_overrides(matches: &ArgMatches) -> Result<ConnectionOverrides, String> {
+    let dbpass = match matches.value_of("passwordenv") {
+        Some(var) => match std::env::var(var) {
+            Ok(p) => Some(p),
+            Err(_) => return Err(format!("environment variable {} is not set", var)),
+        },
+        None => None,
+    };
+    Ok(ConnectionOverrides {
+        dbhost: matches.value_of("host").map(String::from),
+        // validated by clap
+        port: matches.value_of("port").map(|p| p.parse().unwrap()),
+        service_name: matches.value_of("service").map(String::from),
+        sid: matches.value_of("sid").map(String::from),
+        dbuser: matches.value_of("user").map(String::from),
+        dbpass,
+        connect_string: matches.value_of("connectstring").map(String::from),
+    })
+}
+
+///
+/// Loads configuration file, if any, with command line and environment
+/// settings applied or exits; only an explicitly named file must exist
+fn load_config(matches: &ArgMatches) -> Config {
+    match try_load_config(matches) {
+        Ok(config) => config,
+        Err((code, message)) => fail(code, None, message),
+    }
+}
+
+///
+/// Loads the configuration file, or the environment settings without one,
+/// and applies connection settings of the command line
+fn try_load_config(matches: &ArgMatches) -> Result<Config, Failure> {
+    let config_name = match matches.value_of("config") {
+        Some(name) => Some(name),
+        None if Path::new("config.toml").exists() => Some("config.toml"),
+        None => None,
+    };
+    let mut config = match config_name {
+        Some(config_name) => {
+            status!("Using configuration file {}.", config_name.yellow());
+            match Config::load(&std::path::PathBuf::from(config_name)) {
+                Ok(c) => c,
+                Err(e) => {
+                    return Err((
+                        ExitCode::Config,
+                        format!(
+                            "Configuration file {} {} to load: {}",
+                            config_name.yellow(),
+                            "failed".red(),
+                            e
+                        ),
+                    ));
+                }
+            }
+        }
+        None => {
+            status!("No configuration file; using command line and environment settings.");
+            match Config::from_env() {
+                Ok(c) => c,
+                Err(e) => {
+                    return Err((
+                        ExitCode::Config,
+                        format!("Environment settings {}: {}", "invalid".red(), e),
+                    ));
+                }
+            }
+        }
+    };
+
+    match connection_overrides(matches) {
+        Ok(overrides) => config.apply(overrides),
+        Err(e) => {
+            return Err((
+                ExitCode::Config,
+                format!("Connection settings {}: {}", "invalid".red(), e),
+            ));
+        }
+    }
+
+    Ok(config)
+}
+
+///
+/// Connects to database or exits
+fn connect(config: &Config) -> oracle::Connection {
+    match try_connect(config) {
+        Ok(conn) => conn,
+        Err((code, message)) => fail(code, None, message),
+    }
+}
+
+///
+/// Connects to database and initializes the session
+fn try_connect(config: &Config) -> Result<oracle::Connection, Failure> {
+    status!("Attempting database connection.");
+    let conn = match config.connect() {
+        Ok(c) => {
+            status!("Database connection {}.", "succeeded".green());
+            c
+        }
+        Err(e) => {
+            return Err((
+                ExitCode::Connect,
+                format!("Database connection {}: {}", "failed".red(), e),
+            ));
+        }
+    };
+
+    if let Err(e) = initialize_session(&conn, &config.session_options()) {
+        return Err((
+            ExitCode::Connect,
+            format!("Session initialization {}: {}", "failed".red(), e),
+        ));
+    }
+
+    Ok(conn)
+}
+
+///
+/// Reads column names from a csv header and writes them as column input file
+fn columns_from_csv(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    // we can unwrap CSV because it's a required parameter
+    let csv_file = sub_matches.value_of("CSV").unwrap();
+    let uppercase_flag = sub_matches.is_present("uppercase");
+
+    let mut reader = match csv::ReaderBuilder::new()
+        .delimiter(sub_matches.value_of("delimiter").unwrap_or(",").as_bytes()[0])
+        .from_path(csv_file)
+    {
+        Ok(r) => r,
+        Err(e) => {
+            fail(
+                ExitCode::Input,
+                None,
+                format!(
+                    "Reading CSV file {} {}: {}",
+                    csv_file.yellow(),
+                    "failed".red(),
+                    e
+                ),
+            );
+        }
+    };
+    let column_names: Vec<String> = match reader.headers() {
+        Ok(h) => h
+            .iter()
+            .map(|colname| {
+                if uppercase_flag {
+                    String::from(colname.trim()).to_uppercase()
+                } else {
+                    String::from(colname.trim())
+                }
+            })
+            .filter(|colname| !colname.is_empty())
+            .collect(),
+        Err(e) => {
+            fail(
+                ExitCode::Input,
+                None,
+                format!(
+                    "Reading header of CSV file {} {}: {}",
+                    csv_file.yellow(),
+                    "failed".red(),
+                    e
+                ),
+            );
+        }
+    };
+
+    // optionally check that the columns exist in the database
+    if let Some(table_name) = sub_matches.value_of("validate") {
+        let config = load_config(matches);
+        let conn = connect(&config);
+        let builder = column_names
+            .iter()
+            .fold(TableSelectionBuilder::new(table_name), |b, cn| b.with(cn));
+        if let Err(e) = builder.build(&conn) {
+            fail(
+                ExitCode::Definition,
+                Some(table_name),
+                format!(
+                    "{} to validate columns against table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+        eprintln!(
+            "All columns {} in table {}.",
+            "found".green(),
+            table_name.blue()
+        );
+    }
+
+    let contents: String = column_names.iter().map(|cn| format!("{}\n", cn)).collect();
+    match sub_matches.value_of("output") {
+        Some(output_file) => {
+            if let Err(e) = std::fs::write(output_file, contents) {
+                fail(
+                    ExitCode::Output,
+                    None,
+                    format!(
+                        "{} to write column file {}: {}",
+                        "Failed".red(),
+                        output_file.yellow(),
+                        e
+                    ),
+                );
+            }
+            status!(
+                "Wrote {} columns to {}.",
+                column_names.len().to_string().blue(),
+                output_file.yellow()
+            );
+        }
+        None => print!("{}", contents),
+    }
+}
+
+///
+/// Prints rows as left aligned columns below a header
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(idx, title)| {
+            rows.iter()
+                .map(|row| row[idx].len())
+                .chain(std::iter::once(title.len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_line = |cells: Vec<&str>| -> String {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+    };
+
+    println!("{}", format_line(header.to_vec()).bold());
+    for row in rows {
+        println!("{}", format_line(row.iter().map(|c| c.as_str()).collect()));
+    }
+}
+
+///
+/// Lists accessible tables
+fn print_tables(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    let config = load_config(matches);
+    let conn = connect(&config);
+
+    let tables = match list_tables(
+        &conn,
+        sub_matches.value_of("owner"),
+        sub_matches.value_of("like"),
+    ) {
+        Ok(t) => t,
+        Err(e) => {
+            fail(
+                ExitCode::Definition,
+                None,
+                format!("{} to list tables: {}", "Failed".red(), e),
+            );
+        }
+    };
+
+    let rows: Vec<Vec<String>> = tables
+        .iter()
+        .map(|t| {
+            vec![
+                String::from(t.owner()),
+                String::from(t.table_name()),
+                t.num_rows().map(|n| n.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(&["OWNER", "TABLE_NAME", "NUM_ROWS"], &rows);
+}
+
+///
+/// Gets the output file name; warehouse targets compress their
+/// output, so the name gets a `.gz` extension if it lacks one
+fn output_file_name(matches: &ArgMatches) -> String {
+    // clap provides a default output file
+    let output_file = matches.value_of("output").unwrap();
+    let profile = matches
+        .value_of("target")
+        .and_then(OutputProfile::for_target);
+    match profile {
+        Some(profile) => profile.file_name(output_file),
+        None => String::from(output_file),
+    }
+}
+
+///
+/// Lists the partitions of a table
+fn print_partitions(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    // we can unwrap TABLE because it's a required parameter
+    let table_name = sub_matches.value_of("TABLE").unwrap();
+    let config = load_config(matches);
+    let conn = connect(&config);
+
+    let partitions = match list_partitions(&conn, &TableName::parse(table_name)) {
+        Ok(p) => p,
+        Err(e) => {
+            fail(
+                ExitCode::Definition,
+                Some(table_name),
+                format!(
+                    "{} to list partitions of table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+
+    let rows: Vec<Vec<String>> = partitions
+        .iter()
+        .map(|p| {
+            vec![
+                String::from(p.owner()),
+                String::from(p.partition_name()),
+                p.position().to_string(),
+                p.num_rows().map(|n| n.to_string()).unwrap_or_default(),
+                p.subpartition_count().to_string(),
+                String::from(p.high_value().unwrap_or_default()),
+            ]
+        })
+        .collect();
+    print_table(
+        &[
+            "OWNER",
+            "PARTITION_NAME",
+            "POSITION",
+            "NUM_ROWS",
+            "SUBPARTITIONS",
+            "HIGH_VALUE",
+        ],
+        &rows,
+    );
+}
+
+///
+/// Prints column names, types and nullability of a table
+fn print_description(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    // we can unwrap TABLE because it's a required parameter
+    let table_name = sub_matches.value_of("TABLE").unwrap();
+    let config = load_config(matches);
+    let conn = connect(&config);
+
+    let columns = match describe_table(&conn, &TableName::parse(table_name)) {
+        Ok(c) => c,
+        Err(e) => {
+            fail(
+                ExitCode::Definition,
+                Some(table_name),
+                format!(
+                    "{} to describe table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+
+    let rows: Vec<Vec<String>> = columns
+        .iter()
+        .map(|c| {
+            vec![
+                String::from(c.column_name()),
+                c.data_type().to_string(),
+                String::from(if c.nullable() { "Y" } else { "N" }),
+            ]
+        })
+        .collect();
+    print_table(&["COLUMN_NAME", "DATA_TYPE", "NULLABLE"], &rows);
+}
+
+///
+/// Gets a wizard answer or exits if input ended or failed
+fn answered<T>(answer: std::io::Result<T>) -> T {
+    match answer {
+        Ok(a) => a,
+        Err(e) => fail(
+            ExitCode::Input,
+            None,
+            format!("Setup wizard {}: {}", "stopped".red(), e),
+        ),
+    }
+}
+
+///
+/// Asks for connection details, a table and its columns, then writes
+/// a configuration file and a column input file for exporting them
+fn run_wizard(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    let config_file = matches.value_of("config").unwrap_or("config.toml");
+    let force_flag = sub_matches.is_present("force");
+    let refuse_existing = |file_name: &str| {
+        if Path::new(file_name).exists() && !force_flag {
+            fail(
+                ExitCode::Exists,
+                None,
+                format!(
+                    "File {} {}; use --force to overwrite it.",
+                    file_name.yellow(),
+                    "exists".red()
+                ),
+            );
+        }
+    };
+    refuse_existing(config_file);
+    // settings on the command line become the suggested answers
+    let overrides = match connection_overrides(matches) {
+        Ok(o) => o,
+        Err(e) => fail(
+            ExitCode::Config,
+            None,
+            format!("Connection settings {}: {}", "invalid".red(), e),
+        ),
+    };
+
+    let stdin = std::io::stdin();
+    let mut prompter = Prompter::new(stdin.lock(), std::io::stdout());
+    answered(prompter.say(&format!(
+        "This wizard writes {} and a column input file for one table.",
+        config_file.yellow()
+    )));
+    let dbhost = answered(prompter.ask("Database host", overrides.dbhost.as_deref()));
+    let default_port = overrides.port.unwrap_or(1521).to_string();
+    let port = loop {
+        match answered(prompter.ask("Listener port", Some(&default_port))).parse::<u16>() {
+            Ok(p) if p > 0 => break p,
+            _ => answered(prompter.say("Please enter a port from 1 to 65535.")),
+        }
+    };
+    let service_name = answered(prompter.ask("Service name", overrides.service_name.as_deref()));
+    let dbuser = answered(prompter.ask("Database user", overrides.dbuser.as_deref()));
+    let (dbpass, store_password) = match overrides.dbpass {
+        Some(p) => (p, false),
+        None => (
+            answered(prompter.ask("Password (shown while typing)", None)),
+            answered(prompter.confirm(
+                "Store the password in the configuration file instead of setting CSVDUMP_DBPASS",
+                false,
+            )),
+        ),
+    };
+
+    let mut settings = toml::value::Table::new();
+    settings.insert(String::from("dbhost"), toml::Value::String(dbhost));
+    settings.insert(String::from("port"), toml::Value::Integer(i64::from(port)));
+    settings.insert(
+        String::from("service_name"),
+        toml::Value::String(service_name),
+    );
+    settings.insert(String::from("dbuser"), toml::Value::String(dbuser.clone()));
+    let mut connect_settings = settings.clone();
+    connect_settings.insert(String::from("dbpass"), toml::Value::String(dbpass));
+    if store_password {
+        settings = connect_settings.clone();
+    }
+    let config = match Config::from_settings(connect_settings) {
+        Ok(c) => c,
+        Err(e) => fail(
+            ExitCode::Config,
+            None,
+            format!("Connection settings {}: {}", "invalid".red(), e),
+        ),
+    };
+    let conn = connect(&config);
+
+    let pattern = answered(prompter.ask_optional("Table name filter, e.g. EMP%"));
+    let tables = match list_tables(&conn, None, pattern.as_deref()) {
+        Ok(t) if t.is_empty() => fail(
+            ExitCode::Definition,
+            None,
+            format!("{} accessible tables found.", "No".red()),
+        ),
+        Ok(t) => t,
+        Err(e) => fail(
+            ExitCode::Definition,
+            None,
+            format!("{} to list tables: {}", "Failed".red(), e),
+        ),
+    };
+    let rows: Vec<Vec<String>> = tables
+        .iter()
+        .enumerate()
+        .map(|(idx, t)| {
+            vec![
+                (idx + 1).to_string(),
+                String::from(t.owner()),
+                String::from(t.table_name()),
+            ]
+        })
+        .collect();
+    print_table(&["#", "OWNER", "TABLE_NAME"], &rows);
+    let table = &tables[answered(prompter.choose("Table to export", tables.len()))];
+    // tables of other schemas are named with their owner
+    let table_name = if table.owner().eq_ignore_ascii_case(&dbuser) {
+        String::from(table.table_name())
+    } else {
+        format!("{}.{}", table.owner(), table.table_name())
+    };
+
+    let columns = match describe_table(&conn, &TableName::parse(&table_name)) {
+        Ok(c) => c,
+        Err(e) => fail(
+            ExitCode::Definition,
+            Some(&table_name),
+            format!(
+                "{} to describe table {}: {}",
+                "Failed".red(),
+                table_name.yellow(),
+                e
+            ),
+        ),
+    };
+    let rows: Vec<Vec<String>> = columns
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| {
+            vec![
+                (idx + 1).to_string(),
+                String::from(c.column_name()),
+                c.data_type().to_string(),
+            ]
+        })
+        .collect();
+    print_table(&["#", "COLUMN_NAME", "DATA_TYPE"], &rows);
+    let selection = answered(prompter.choose_many("Columns to export", columns.len()));
+
+    // the file name leads back to the table when exporting
+    let column_file = sub_matches
+        .value_of("output")
+        .map(String::from)
+        .unwrap_or_else(|| format!("{}.txt", table_name));
+    refuse_existing(&column_file);
+    // settings consist of strings and numbers only
+    let config_text = toml::to_string(&toml::Value::Table(settings)).unwrap();
+    let column_text: String = selection
+        .iter()
+        .map(|idx| format!("{}\n", columns[*idx].column_name()))
+        .collect();
+    for (file_name, contents) in [
+        (config_file, config_text),
+        (column_file.as_str(), column_text),
+    ] {
+        if let Err(e) = std::fs::write(file_name, contents) {
+            fail(
+                ExitCode::Output,
+                None,
+                format!("{} to write {}: {}", "Failed".red(), file_name.yellow(), e),
+            );
+        }
+    }
+
+    status!(
+        "Wrote configuration file {} and {} columns to {}.",
+        config_file.yellow(),
+        selection.len().to_string().blue(),
+        column_file.yellow()
+    );
+    if !store_password && matches.value_of("passwordenv").is_none() {
+        status!("Set CSVDUMP_DBPASS to the password before exporting.");
+    }
+    status!("Export the table with: csvdump {}", column_file);
+}
+
+///
+/// Checks an output file against its control file
+fn verify_output(sub_matches: &ArgMatches) {
+    // we can unwrap CONTROL because it's a required parameter
+    let control_file = sub_matches.value_of("CONTROL").unwrap();
+    let control = match ControlFile::load(Path::new(control_file)) {
+        Ok(cf) => cf,
+        Err(e) => {
+            fail(
+                ExitCode::Config,
+                None,
+                format!(
+                    "{} to load control file {}: {}",
+                    "Failed".red(),
+                    control_file.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+
+    let output_file = sub_matches
+        .value_of("file")
+        .unwrap_or_else(|| control.output_file());
+    let delimiter = sub_matches.value_of("delimiter").unwrap_or(",").as_bytes()[0];
+    match control.verify(Path::new(output_file), delimiter) {
+        Ok(mismatches) if mismatches.is_empty() => status!(
+            "Output file {} {} control file {}.",
+            output_file.yellow(),
+            "matches".green(),
+            control_file.yellow()
+        ),
+        Ok(mismatches) => fail(
+            ExitCode::Verify,
+            None,
+            format!(
+                "Output file {} {}: {}",
+                output_file.yellow(),
+                "mismatch".red(),
+                mismatches.join("; ")
+            ),
+        ),
+        Err(e) => {
+            fail(
+                ExitCode::Verify,
+                None,
+                format!(
+                    "{} to verify output file {}: {}",
+                    "Failed".red(),
+                    output_file.yellow(),
+                    e
+                ),
+            );
+        }
+    }
+}
+
+///
+/// Reads header and records of a CSV file
+fn read_csv(filename: &str, delimiter: u8) -> csv::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_path(filename)?;
+    let header = reader.headers()?.iter().map(String::from).collect();
+    let records = reader
+        .records()
+        .map(|record| record.map(|r| r.iter().map(String::from).collect()))
+        .collect::<csv::Result<Vec<Vec<String>>>>()?;
+
+    Ok((header, records))
+}
+
+///
+/// Compares an export with a later export or with the current
+/// table contents and prints the differing rows; returns whether
+/// the exports match
+fn diff_exports(matches: &ArgMatches, sub_matches: &ArgMatches) -> bool {
+    let delimiter = sub_matches.value_of("delimiter").unwrap_or(",").as_bytes()[0];
+    let read = |filename: &str| match read_csv(filename, delimiter) {
+        Ok(contents) => contents,
+        Err(e) => {
+            fail(
+                ExitCode::Input,
+                None,
+                format!(
+                    "Reading CSV file {} {}: {}",
+                    filename.yellow(),
+                    "failed".red(),
+                    e
+                ),
+            );
+        }
+    };
+    // we can unwrap OLD because it's a required parameter
+    let (header, old_records) = read(sub_matches.value_of("OLD").unwrap());
+    let key_names: Option<Vec<String>> = sub_matches
+        .values_of("key")
+        .map(|keys| keys.map(String::from).collect());
+    let table_name = sub_matches.value_of("table");
+
+    let changes = match sub_matches.value_of("NEW") {
+        Some(new_file) => {
+            let (new_header, new_records) = read(new_file);
+            if new_header != header {
+                fail(
+                    ExitCode::Input,
+                    None,
+                    format!(
+                        "Header of {} {} the header of the old export.",
+                        new_file.yellow(),
+                        "differs from".red()
+                    ),
+                );
+            }
+            // we can unwrap key because NEW requires it
+            RowDiff::new(&key_names.unwrap(), &header)
+                .and_then(|d| d.compare(old_records, new_records, |v: &String| v.clone()))
+        }
+        None => {
+            // we can unwrap table because it's required without NEW
+            let table_name = table_name.unwrap();
+            let config = load_config(matches);
+            let conn = connect(&config);
+            let table_def = match header
+                .iter()
+                .fold(TableSelectionBuilder::new(table_name), |b, cn| b.with(cn))
+                .build(&conn)
+            {
+                Ok(td) => td,
+                Err(e) => {
+                    fail(
+                        ExitCode::Definition,
+                        Some(table_name),
+                        format!(
+                            "{} to read definition of table {}: {}",
+                            "Failed".red(),
+                            table_name.yellow(),
+                            e
+                        ),
+                    );
+                }
+            };
+            let primary_key = table_def.primary_key().map(|pk| pk.to_vec());
+            let key_names = match key_names.or(primary_key) {
+                Some(k) => k,
+                None => fail(
+                    ExitCode::Input,
+                    Some(table_name),
+                    format!(
+                        "Table {} has no primary key, {} to match rows.",
+                        table_name.yellow(),
+                        "set --key".red()
+                    ),
+                ),
+            };
+
+            // read the old export back into the data model of the table
+            let value_format = ValueFormat::new();
+            let seed = RowSeed::new(&table_def);
+            let mut old_rows = Vec::with_capacity(old_records.len());
+            for (idx, record) in old_records.iter().enumerate() {
+                match seed.parse(record, &value_format) {
+                    Ok(row) => old_rows.push(row.values().to_vec()),
+                    Err(e) => {
+                        fail(
+                            ExitCode::Input,
+                            Some(table_name),
+                            format!(
+                                "{} to read row {} of the old export: {}",
+                                "Failed".red(),
+                                idx + 1,
+                                e
+                            ),
+                        );
+                    }
+                }
+            }
+
+            let header = table_def.header();
+            let data = match table_def.load(&conn) {
+                Ok(d) => d,
+                Err(e) => {
+                    fail(
+                        ExitCode::Load,
+                        Some(table_name),
+                        format!(
+                            "{} to load table {}: {}",
+                            "Failed".red(),
+                            table_name.yellow(),
+                            e
+                        ),
+                    );
+                }
+            };
+            let new_rows = data.rows().iter().map(|r| r.values().to_vec()).collect();
+            RowDiff::new(&key_names, &header)
+                .and_then(|d| d.compare(old_rows, new_rows, |v| value_format.format(v)))
+        }
+    };
+
+    let changes = match changes {
+        Ok(c) => c,
+        Err(e) => fail(
+            ExitCode::Input,
+            table_name,
+            format!("{} to compare rows: {}", "Failed".red(), e),
+        ),
+    };
+    if changes.is_empty() {
+        status!("Exports {}.", "match".green());
+        return true;
+    }
+
+    let mut counts = [0; 3];
+    for change in changes.iter() {
+        counts[match change {
+            Change::Inserted(_) => 0,
+            Change::Deleted(_) => 1,
+            Change::Changed(_, _) => 2,
+        }] += 1;
+        println!("{}", change);
+    }
+    // differences are a result, not a failure
+    status!(
+        "Exports {}: {} inserted, {} deleted, {} changed rows.",
+        "differ".red(),
+        counts[0],
+        counts[1],
+        counts[2]
+    );
+
+    false
+}
+
+///
+/// Copies all rows of a table into a table of the target database.
+/// Rows are inserted while reading and committed once all of them
+/// have been copied; on failure the target is rolled back.
+fn copy_table(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    // we can unwrap TABLE and target because they are required parameters
+    let table_name = sub_matches.value_of("TABLE").unwrap();
+    let target_file = sub_matches.value_of("target").unwrap();
+    let target_table = TableName::parse(sub_matches.value_of("targettable").unwrap_or(table_name));
+    // numeric values are validated by clap
+    let batch_size: usize = sub_matches.value_of("batchsize").unwrap().parse().unwrap();
+
+    let config = load_config(matches);
+    let conn = connect(&config);
+    let target_config = match Config::load_file(Path::new(target_file)) {
+        Ok(c) => c,
+        Err(e) => {
+            fail(
+                ExitCode::Config,
+                None,
+                format!(
+                    "Configuration file {} {} to load: {}",
+                    target_file.yellow(),
+                    "failed".red(),
+                    e
+                ),
+            );
+        }
+    };
+    let target_conn = connect(&target_config);
+
+    let table_def = match TableSelectionBuilder::new(table_name)
+        .all_columns()
+        .batch_size(batch_size as u32)
+        .build(&conn)
+    {
+        Ok(td) => td,
+        Err(e) => {
+            fail(
+                ExitCode::Definition,
+                Some(table_name),
+                format!(
+                    "{} to read definition of table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+    let columns = table_def.columns().to_vec();
+    let data = match table_def.load_threaded() {
+        Ok(dt) => dt,
+        Err(e) => {
+            fail(
+                ExitCode::Load,
+                Some(table_name),
+                format!(
+                    "{} to read data for table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+
+    let loader = data.spawn(move || Ok(conn));
+    let insert = || -> lib_oradb::Result<u64> {
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut inserted = 0;
+        while let Some(rows) = loader.recv() {
+            batch.extend(rows);
+            if batch.len() >= batch_size {
+                inserted += insert_rows(&target_conn, &target_table, &columns, &batch)?;
+                batch.clear();
+            }
+        }
+
+        Ok(inserted + insert_rows(&target_conn, &target_table, &columns, &batch)?)
+    };
+    let insert_result = insert();
+    let load_result = loader.join();
+
+    let outcome = match (load_result, insert_result) {
+        (Err(e), _) => Err((
+            ExitCode::Load,
+            format!(
+                "{} to read data for table {}: {}",
+                "Failed".red(),
+                table_name.yellow(),
+                e
+            ),
+        )),
+        (_, Err(e)) => Err((
+            ExitCode::Output,
+            format!(
+                "{} to insert into table {}: {}",
+                "Failed".red(),
+                target_table.to_string().yellow(),
+                e
+            ),
+        )),
+        (Ok(_), Ok(inserted)) => target_conn.commit().map(|_| inserted).map_err(|e| {
+            (
+                ExitCode::Output,
+                format!(
+                    "{} to commit table {}: {}",
+                    "Failed".red(),
+                    target_table.to_string().yellow(),
+                    e
+                ),
+            )
+        }),
+    };
+
+    match outcome {
+        Ok(inserted) => status!(
+            "Copied {} rows into table {}.",
+            inserted.to_string().blue(),
+            target_table.to_string().yellow()
+        ),
+        Err((code, message)) => {
+            if let Err(e) = target_conn.rollback() {
+                eprintln!("{} to roll back target: {}", "Failed".red(), e);
+            }
+            fail(code, Some(table_name), message);
+        }
+    }
+}
+
+///
+/// Scans a table and reports null counts, distinct values, value
+/// ranges and lengths of each column
+fn profile_table(matches: &ArgMatches, sub_matches: &ArgMatches) {
+    // we can unwrap TABLE because it's a required parameter
+    let table_name = sub_matches.value_of("TABLE").unwrap();
+    let config = load_config(matches);
+    let conn = Arc::new(connect(&config));
+    // cancel the scan on Ctrl-C
+    if let Err(e) = interrupt::install() {
+        eprintln!("{} to install signal handler: {}", "Failed".red(), e);
+    }
+    interrupt::watch(Arc::clone(&conn));
+
+    let table_def = match TableSelectionBuilder::new(table_name)
+        .all_columns()
+        .build(conn.as_ref())
+    {
+        Ok(td) => td,
+        Err(e) => {
+            fail(
+                ExitCode::Definition,
+                Some(table_name),
+                format!(
+                    "{} to read definition of table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+    let mut profiler = TableProfiler::new(table_def.columns());
+    let data = match table_def.load_threaded() {
+        Ok(dt) => dt,
+        Err(e) => {
+            fail(
+                ExitCode::Load,
+                Some(table_name),
+                format!(
+                    "{} to read data for table {}: {}",
+                    "Failed".red(),
+                    table_name.yellow(),
+                    e
+                ),
+            );
+        }
+    };
+
+    status!("Scanning table {}.", table_name.blue());
+    let loader = {
+        let conn = Arc::clone(&conn);
+        data.spawn(move || Ok(conn))
+    };
+    while let Some(rows) = loader.recv() {
+        for row in rows {
+            profiler.add(&row);
+        }
+    }
+    if let Err(e) = loader.join() {
+        let code = if interrupt::interrupted() {
+            ExitCode::Interrupted
+        } else {
+            ExitCode::Load
+        };
+        fail(
+            code,
+            Some(table_name),
+            format!(
+                "{} to read data for table {}: {}",
+                "Failed".red(),
+                table_name.yellow(),
+                e
+            ),
+        );
+    }
 
-use clap::{App, Arg};
-use colored::*;
-use config::Config;
-use lib_oradb::definition::TableSelectionBuilder;
-use lib_oradb::definition::RowIndicator;
-use std::path::Path;
-use std::sync::{Arc,RwLock};
+    let report = profiler.report();
+    // reports consist of strings and numbers only
+    let contents = match sub_matches.value_of("format") {
+        Some("csv") => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for column in &report {
+                writer.serialize(column).unwrap();
+            }
+            String::from_utf8(writer.into_inner().unwrap()).unwrap()
+        }
+        _ => format!("{}\n", serde_json::to_string_pretty(&report).unwrap()),
+    };
+    match sub_matches.value_of("output") {
+        Some(output_file) => {
+            if let Err(e) = std::fs::write(output_file, contents) {
+                fail(
+                    ExitCode::Output,
+                    Some(table_name),
+                    format!(
+                        "{} to write profile {}: {}",
+                        "Failed".red(),
+                        output_file.yellow(),
+                        e
+                    ),
+                );
+            }
+            status!(
+                "Profile of {} columns written to {}.",
+                report.len().to_string().blue(),
+                output_file.yellow()
+            );
+        }
+        None => print!("{}", contents),
+    }
+}
 
-const VERSION: &str = env!("CARGO_PKG_VERSION");
+///
+/// Prints the current system change number of the database
+fn print_current_scn(matches: &ArgMatches) {
+    let config = load_config(matches);
+    let conn = connect(&config);
+
+    match current_scn(&conn) {
+        Ok(scn) => println!("{}", scn),
+        Err(e) => {
+            fail(
+                ExitCode::State,
+                None,
+                format!("{} to read current SCN: {}", "Failed".red(), e),
+            );
+        }
+    }
+}
 
 ///
-/// Reads column names from file
-fn read_parameters_file(
-    filename: &Path,
-    uppercase_flag: bool,
-) -> Result<Vec<String>, std::io::Error> {
-    let fulltext = std::fs::read_to_string(filename)?;
-    let separated_lines: Vec<&str> = fulltext.lines().collect();
-    let cleaned_cols: Vec<String> = separated_lines
-        .into_iter()
-        .map(|colname| {
-            if uppercase_flag {
-                String::from(colname.trim()).to_uppercase()
-            } else {
-                String::from(colname.trim())
-            }
-        })
-        .filter(|colname| colname.len() > 0)
-        .collect();
+/// Checks configuration file, environment and command line settings
+/// without connecting; problems are reported together
+fn check_config(matches: &ArgMatches) {
+    let config = load_config(matches);
 
-    Ok(cleaned_cols)
+    let problems = config.check_connection();
+    if !problems.is_empty() {
+        fail(
+            ExitCode::Config,
+            None,
+            format!(
+                "Connection settings {}:\n  - {}",
+                "incomplete".red(),
+                problems.join("\n  - ")
+            ),
+        );
+    }
+
+    // the connect string holds no credentials
+    println!(
+        "Configuration is {}; connecting to {}.",
+        "valid".green(),
+        config.connect_string().unwrap_or_default().yellow()
+    );
 }
 
-fn main() {
-    let matches = App::new("CSV TABLE DUMP")
+///
+/// Removes earlier exports in the output directory beyond the retention
+/// limits, together with control files describing them
+fn prune_exports(ctx: &RunContext, matches: &ArgMatches, pattern: &str, output_file_path: &Path) {
+    // numbers are validated by clap
+    let policy = match RetentionPolicy::new(
+        pattern,
+        matches.value_of("keeplast").map(|n| n.parse().unwrap()),
+        matches.value_of("keepdays").map(|n| n.parse().unwrap()),
+    ) {
+        Ok(p) => p,
+        Err(e) => {
+            fail_run(
+                ctx,
+                ExitCode::Input,
+                None,
+                format!("{} to set up retention: {}", "Failed".red(), e),
+            );
+        }
+    };
+
+    let output_dir = match output_file_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let current = output_file_path.canonicalize().ok();
+    let expired: Vec<PathBuf> = match policy.expired(output_dir) {
+        // never prune the export just written
+        Ok(files) => files
+            .into_iter()
+            .filter(|f| f.canonicalize().ok() != current)
+            .collect(),
+        Err(e) => {
+            eprintln!("{} to list earlier exports: {}", "Failed".red(), e);
+            return;
+        }
+    };
+    // look up control files before their output files are gone
+    let control_dir = match matches.value_of("controlfile").map(Path::new) {
+        Some(cf) => cf.parent().filter(|d| !d.as_os_str().is_empty()),
+        None => None,
+    }
+    .unwrap_or(output_dir);
+    let control_files = match control_files_for(control_dir, &expired) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{} to list earlier control files: {}", "Failed".red(), e);
+            Vec::new()
+        }
+    };
+
+    for file in expired.iter().chain(&control_files) {
+        match std::fs::remove_file(file) {
+            Ok(()) => status!(ctx; "Pruned {}.", file.display().to_string().yellow()),
+            Err(e) => eprintln!(
+                "{} to prune {}: {}",
+                "Failed".red(),
+                file.display().to_string().yellow(),
+                e
+            ),
+        }
+    }
+}
+
+///
+/// Adds the arguments of an export, used both without a subcommand
+/// and by the `export` subcommand
+fn with_export_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app
+    .arg(
+        Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Sets output filename, or sftp://[user@]host[:port]/path to upload the finished file")
+            .takes_value(true)
+            .default_value("output.csv"),
+    )
+    .arg(
+        Arg::with_name("quoteall")
+            .short("q")
+            .long("quoteall")
+            .help("Puts quotation marks around all values"),
+    )
+    .arg(
+        Arg::with_name("target")
+            .long("target")
+            .value_name("WAREHOUSE")
+            .help("Formats output for loading into the given data warehouse, gzip compressed")
+            .possible_values(&["snowflake", "bigquery", "redshift"])
+            .conflicts_with("quoteall")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Writes CSV, the tab separated text format of PostgreSQL's COPY FROM STDIN without header, or a SQLite database")
+            .possible_values(&["csv", "pg-copy", "sqlite"])
+            .conflicts_with_all(&["target", "quoteall"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("pgddl")
+            .long("pg-ddl")
+            .value_name("FILE")
+            .help("Writes a PostgreSQL CREATE TABLE statement translated from the exported column types")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Overwrites existing output file if set"),
+    )
+    .arg(
+        Arg::with_name("append")
+            .long("append")
+            .help("Adds rows to an existing output file without writing the header again")
+            .conflicts_with_all(&["force", "backfill"]),
+    )
+    .arg(
+        Arg::with_name("noatomic")
+            .long("no-atomic")
+            .help("Writes directly into the output file instead of renaming a .part file when complete"),
+    )
+    .arg(
+        Arg::with_name("uppercase")
+            .short("u")
+            .long("uppercase")
+            .help("Uppercase all column names"),
+    )
+    .arg(
+        Arg::with_name("tablename")
+            .short("n")
+            .long("tablename")
+            .help("Overrides table name (default is input filename), e.g. OWNER.TABLE@DBLINK")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("floatnumbers")
+            .long("float-numbers")
+            .help("Reads decimal NUMBER columns as floating point instead of exact values"),
+    )
+    .arg(
+        Arg::with_name("unknowntypes")
+            .long("unknown-types")
+            .value_name("POLICY")
+            .help("Sets handling of columns with unsupported data types")
+            .possible_values(&["fail", "skip-column", "as-string"])
+            .default_value("fail")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("columnorder")
+            .long("column-order")
+            .value_name("ORDER")
+            .help("Sets whether output columns follow the input file or the table definition")
+            .possible_values(&["input", "table"])
+            .default_value("input")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("noheader")
+            .long("no-header")
+            .help("Omits the header row"),
+    )
+    .arg(
+        Arg::with_name("headercase")
+            .long("header-case")
+            .value_name("CASE")
+            .help("Sets the case of column names in the header row")
+            .possible_values(&["lower", "upper", "original"])
+            .conflicts_with("noheader")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("encoding")
+            .long("encoding")
+            .value_name("ENCODING")
+            .help("Sets the character encoding of the output file")
+            .possible_values(&["utf-8", "windows-1252", "latin1"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("boolformat")
+            .long("bool-format")
+            .value_name("FORMAT")
+            .help("Sets how boolean values are written")
+            .possible_values(&["true-false", "1-0", "y-n"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("decimalseparator")
+            .long("decimal-separator")
+            .value_name("CHAR")
+            .help("Sets the decimal separator of non-integer numbers, e.g. \",\"")
+            .validator(single_char)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("thousandsseparator")
+            .long("thousands-separator")
+            .value_name("CHAR")
+            .help("Separates groups of thousands in non-integer numbers")
+            .validator(single_char)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("decimalscale")
+            .long("decimal-scale")
+            .value_name("DIGITS")
+            .help("Rounds or pads non-integer numbers to a fixed number of fraction digits")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err(String::from("expected a number of digits")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("stripcontrolchars")
+            .long("strip-control-chars")
+            .help("Leaves control characters such as tabs and NULs out of text values")
+            .conflicts_with("replacecontrolchars"),
+    )
+    .arg(
+        Arg::with_name("replacecontrolchars")
+            .long("replace-control-chars")
+            .value_name("CHAR")
+            .help("Replaces control characters in text values with the given character")
+            .validator(single_char)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("escapenewlines")
+            .long("escape-newlines")
+            .help("Writes line breaks in text values as \\n instead of stripping or replacing them"),
+    )
+    .arg(
+        Arg::with_name("trimtrailing")
+            .long("trim-trailing")
+            .help("Removes trailing whitespace from text values"),
+    )
+    .arg(
+        Arg::with_name("lobdir")
+            .long("lob-dir")
+            .value_name("DIR")
+            .help("Writes CLOB and BLOB values into files in the given directory, relative to the output file, and exports their paths instead")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("lobkey")
+            .long("lob-key")
+            .value_name("COLNAMES")
+            .help("Sets the columns naming LOB files as <key>_<column>.bin instead of the primary key")
+            .requires("lobdir")
+            .takes_value(true)
+            .use_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("maxfieldlength")
+            .long("max-field-length")
+            .value_name("BYTES")
+            .help("Truncates text values longer than the given number of bytes, warning with the row number")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("must be a positive number")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("ellipsis")
+            .long("ellipsis")
+            .help("Ends truncated values with \"...\" instead of cutting them off")
+            .requires("maxfieldlength"),
+    )
+    .arg(
+        Arg::with_name("bom")
+            .long("bom")
+            .help("Starts the output file with a UTF-8 byte order mark"),
+    )
+    .arg(
+        Arg::with_name("encryptrecipient")
+            .long("encrypt-recipient")
+            .value_name("KEY")
+            .help("Encrypts output and rejects for an age public key or OpenPGP key ID (may be repeated)")
+            .multiple(true)
+            .number_of_values(1)
+            .conflicts_with_all(&["append", "state", "lobdir", "dictionary"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("armor")
+            .long("armor")
+            .help("Writes encrypted files ASCII armored instead of binary")
+            .requires("encryptrecipient"),
+    )
+    .arg(
+        Arg::with_name("crlf")
+            .long("crlf")
+            .help("Ends lines with CR LF instead of the platform default")
+            .conflicts_with("lf"),
+    )
+    .arg(
+        Arg::with_name("lf")
+            .long("lf")
+            .help("Ends lines with LF instead of the platform default"),
+    )
+    .arg(
+        Arg::with_name("hints")
+            .long("hints")
+            .value_name("FILE")
+            .help("Writes suggested target column types derived from exported data")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("dictionary")
+            .long("dictionary")
+            .value_name("COLNAME")
+            .help("Replaces values of this column by integer codes (may be repeated)")
+            .multiple(true)
+            .number_of_values(1)
+            .requires("dictionaryfile")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("dictionaryfile")
+            .long("dictionary-file")
+            .value_name("FILE")
+            .help("Writes the codes of dictionary encoded columns into this file")
+            .requires("dictionary")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("rejects")
+            .long("rejects")
+            .value_name("FILE")
+            .help("Writes rows failing the validation rules of the config file into this file instead of next to the output file, e.g. orders_rejects.csv for orders.csv")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("rowhash")
+            .long("row-hash")
+            .value_name("COLNAME")
+            .help("Appends a column with a hash of all exported values of each row")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("distinct")
+            .long("distinct")
+            .help("Removes duplicate rows in the database with SELECT DISTINCT"),
+    )
+    .arg(
+        Arg::with_name("materialize")
+            .long("materialize")
+            .help("Stages filtered rows in a temporary table before fetching them"),
+    )
+    .arg(
+        Arg::with_name("distincton")
+            .long("distinct-on")
+            .value_name("COLNAMES")
+            .help("Skips rows whose values in the given columns were already written; for selections DISTINCT does not support, e.g. with LOB columns")
+            .takes_value(true)
+            .use_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("rowhashalgorithm")
+            .long("row-hash-algorithm")
+            .value_name("ALGORITHM")
+            .help("Sets the digest used for the row hash column")
+            .takes_value(true)
+            .possible_values(&["sha256", "sha512"])
+            .default_value("sha256"),
+    )
+    .arg(
+        Arg::with_name("controlfile")
+            .long("control-file")
+            .value_name("FILE")
+            .help("Writes row count, checksum and timing of the export into a control file")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("metricsfile")
+            .long("metrics-file")
+            .value_name("FILE")
+            .help("Writes rows, bytes, duration and outcome of the run as Prometheus textfile metrics")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("allpartitions")
+            .long("all-partitions")
+            .help("Exports each partition of the table into a file of its own named <table>_<partition>")
+            .conflicts_with_all(&["partition", "subpartition", "backfill", "append", "state"]),
+    )
+    .arg(
+        Arg::with_name("parallel")
+            .long("parallel")
+            .value_name("N")
+            .help("Sets how many partitions are exported at the same time (default 1)")
+            .takes_value(true)
+            .requires("allpartitions")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("must be a positive number")),
+            }),
+    )
+    .arg(
+        Arg::with_name("partition")
+            .long("partition")
+            .value_name("NAME")
+            .help("Exports only the rows of the named table partition")
+            .takes_value(true)
+            .conflicts_with("subpartition"),
+    )
+    .arg(
+        Arg::with_name("subpartition")
+            .long("subpartition")
+            .value_name("NAME")
+            .help("Exports only the rows of the named table subpartition")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("filter")
+            .long("filter")
+            .value_name("CONDITION")
+            .help("Restricts exported rows by a WHERE condition with placeholders, e.g. \"AU_KAUFDAT > :1\"")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("bind")
+            .long("bind")
+            .value_name("VALUE")
+            .help("Binds the next filter placeholder; prefix with int:, num:, float:, date: or ts: for typed values")
+            .multiple(true)
+            .number_of_values(1)
+            .requires("filter")
+            .validator(|v| parse_bind(&v).map(|_| ()))
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("aggregate")
+            .long("aggregate")
+            .value_name("COLNAME=EXPRESSION")
+            .help("Adds an aggregate column, e.g. \"TOTAL=SUM(AMOUNT)\"; other columns have to be grouped")
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|v| match v.split_once('=') {
+                Some((column, expression)) if !column.is_empty() && !expression.is_empty() => {
+                    Ok(())
+                }
+                _ => Err(String::from("expected COLNAME=EXPRESSION")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("computed")
+            .long("computed")
+            .value_name("COLNAME[:TYPE]=EXPRESSION")
+            .help("Appends a computed column, e.g. \"SOURCE='PROD'\" or \"MONTH:date=TRUNC(DAY,'MM')\"")
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|v| match v.split_once('=') {
+                Some((column, expression)) if !column.is_empty() && !expression.is_empty() => {
+                    parse_column_entry(column).map(|_| ())
+                }
+                _ => Err(String::from("expected COLNAME[:TYPE]=EXPRESSION")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("groupby")
+            .long("group-by")
+            .value_name("COLNAMES")
+            .help("Groups exported rows by the given columns")
+            .takes_value(true)
+            .use_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("keysfile")
+            .long("keys-file")
+            .value_name("FILE")
+            .help("Exports only rows whose key column matches a value in the file, one per line; prefix values like --bind for typed keys")
+            .requires("keycolumn")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("keycolumn")
+            .long("key-column")
+            .value_name("COLNAME")
+            .help("Sets the column matched against the keys file")
+            .requires("keysfile")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("preferowner")
+            .long("prefer-owner")
+            .value_name("OWNER")
+            .help("Sets the owner to use if the table exists for several owners")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("owner")
+            .long("owner")
+            .value_name("OWNER")
+            .help("Reads the table from the schema of this owner")
+            .conflicts_with("preferowner")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("incrementalcolumn")
+            .long("incremental-column")
+            .value_name("COLNAME")
+            .help("Only exports rows where this column exceeds the value of the last run")
+            .requires("state")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("asofscn")
+            .long("as-of-scn")
+            .value_name("SCN")
+            .help("Reads table data as of the given system change number")
+            .validator(|v| {
+                v.parse::<u64>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("SCN must be a positive number"))
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("sample")
+            .long("sample")
+            .value_name("PERCENT")
+            .help("Exports a random sample of about the given percentage of rows, e.g. 1%")
+            .validator(|v| parse_sample_percent(&v).map(|_| ()))
+            .conflicts_with_all(&["state", "incrementalcolumn", "cdc"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("sampleseed")
+            .long("sample-seed")
+            .value_name("SEED")
+            .help("Selects the same sample on every run as long as the table is unchanged")
+            .validator(|v| {
+                v.parse::<u32>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("sample seed must be a positive number"))
+            })
+            .requires("sample")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("samplesize")
+            .long("sample-size")
+            .value_name("ROWS")
+            .help("Stops the sample after the given number of rows")
+            .validator(|v| {
+                v.parse::<u64>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("sample size must be a positive number"))
+            })
+            .requires("sample")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("fetchsize")
+            .long("fetch-size")
+            .value_name("ROWS")
+            .help("Sets the number of rows fetched per database round trip")
+            .validator(|v| match v.parse::<u32>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("fetch size must be a positive number")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("batchsize")
+            .long("batch-size")
+            .value_name("ROWS")
+            .help("Sets the number of rows passed from the reader to the writer at once")
+            .validator(|v| match v.parse::<u32>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("batch size must be a positive number")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("writethreads")
+            .long("write-threads")
+            .value_name("N")
+            .help("Sets the number of threads serializing rows to CSV")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("number of write threads must be positive")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("memorylimit")
+            .long("memory-limit")
+            .value_name("MB")
+            .help("Pauses reading above this much memory; the fetch size is kept, needs /proc")
+            .validator(|v| match v.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("memory limit must be a positive number")),
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("backfill")
+            .long("backfill")
+            .help("Exports one file per date partition, skipping partitions already exported")
+            .requires_all(&["partitioncolumn", "from", "to"])
+            // zsh completions cannot resolve conflicts with argument groups
+            .conflicts_with_all(&["incrementalcolumn", "cdc"]),
+    )
+    .arg(
+        Arg::with_name("partitioncolumn")
+            .long("partition-column")
+            .value_name("COLUMN")
+            .help("Sets the date column partitioning a backfill")
+            .requires("backfill")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("from")
+            .long("from")
+            .value_name("DATE")
+            .help("Sets the first backfill partition as YYYY, YYYY-MM or YYYY-MM-DD")
+            .requires("backfill")
+            .validator(|v| {
+                parse_date(&v)
+                    .map(|_| ())
+                    .ok_or_else(|| String::from("date must be YYYY, YYYY-MM or YYYY-MM-DD"))
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("to")
+            .long("to")
+            .value_name("DATE")
+            .help("Sets the last backfill partition as YYYY, YYYY-MM or YYYY-MM-DD")
+            .requires("backfill")
+            .validator(|v| {
+                parse_date(&v)
+                    .map(|_| ())
+                    .ok_or_else(|| String::from("date must be YYYY, YYYY-MM or YYYY-MM-DD"))
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("granularity")
+            .long("granularity")
+            .value_name("PERIOD")
+            .help("Sets the length of a backfill partition")
+            .possible_values(&["day", "month", "year"])
+            .default_value("month")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("retries")
+            .long("retries")
+            .value_name("COUNT")
+            .help("Sets how often a failed backfill partition is retried")
+            .validator(|v| {
+                v.parse::<u32>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("retries must be a positive number"))
+            })
+            .default_value("2")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("samplerows")
+            .long("sample-rows")
+            .value_name("ROWS")
+            .help("Checks the first rows against the declared column types before exporting")
+            .validator(|v| {
+                v.parse::<u64>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("sample size must be a positive number"))
+            })
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("abortondrift")
+            .long("abort-on-drift")
+            .help("Aborts if sampled values do not match the declared column types")
+            .requires("samplerows"),
+    )
+    .arg(
+        Arg::with_name("recordscn")
+            .long("record-scn")
+            .help("Records the system change number at extraction start in the control file"),
+    )
+    .arg(
+        Arg::with_name("cdc")
+            .long("cdc")
+            .help("Only exports rows changed since the last run based on ORA_ROWSCN")
+            .requires("state"),
+    )
+    .group(
+        ArgGroup::with_name("incremental")
+            .args(&["incrementalcolumn", "cdc"])
+            .multiple(true),
+    )
+    .arg(
+        Arg::with_name("state")
+            .long("state")
+            .value_name("FILE")
+            .help("Sets the state file remembering the progress of the last run")
+            .requires("incremental")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("FILE")
+            .help("Sets a file of columns to leave out; without selected columns, all others are exported")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("prunepattern")
+            .long("prune-pattern")
+            .value_name("REGEX")
+            .help("Sets the pattern of whole earlier export file names in the output directory to prune")
+            .requires("retention")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("keeplast")
+            .long("keep-last")
+            .value_name("COUNT")
+            .help("Keeps only the given number of most recent exports")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("number of exports must be at least 1")),
+            })
+            .requires("prunepattern")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("keepdays")
+            .long("keep-days")
+            .value_name("DAYS")
+            .help("Keeps only exports modified within the given number of days")
+            .validator(|v| {
+                v.parse::<u64>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("days must be a positive number"))
+            })
+            .requires("prunepattern")
+            .takes_value(true),
+    )
+    .group(
+        ArgGroup::with_name("retention")
+            .args(&["keeplast", "keepdays"])
+            .multiple(true),
+    )
+    .arg(
+        Arg::with_name("INPUT")
+            .help("Sets the input file listing one COLUMN[:TYPE] per line")
+            .required_unless("helpjson")
+            .index(1),
+    )
+}
+
+///
+/// Defines the command line of all modes
+fn cli<'a, 'b>() -> App<'a, 'b> {
+    let app = App::new("CSV TABLE DUMP")
         .version(VERSION)
         .author("Christian Moerz <chris@ny-central.org>")
         .about("Exports Oracle database table data into CSV")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("config")
                 .short("c")
                 .long("config")
                 .value_name("FILE")
                 .help("Sets a custom config file")
+                .global(true)
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("output")
-                .short("o")
-                .long("output")
-                .value_name("FILE")
-                .help("Sets output filename")
-                .takes_value(true)
-                .default_value("output.csv"),
+            Arg::with_name("host")
+                .long("host")
+                .value_name("HOST")
+                .help("Sets the database host, overriding the config file")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .value_name("PORT")
+                .help("Sets the listener port, overriding the config file")
+                .validator(|v| {
+                    v.parse::<u16>()
+                        .map(|_| ())
+                        .map_err(|_| String::from("port must be a number up to 65535"))
+                })
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("service")
+                .long("service")
+                .value_name("NAME")
+                .help("Sets the database service name, overriding the config file")
+                .conflicts_with("sid")
+                .global(true)
+                .takes_value(true),
         )
         .arg(
-            Arg::with_name("quoteall")
-                .short("q")
-                .long("quoteall")
-                .help("Puts quotation marks around all values"),
+            Arg::with_name("sid")
+                .long("sid")
+                .value_name("SID")
+                .help("Sets the database SID, overriding the config file")
+                .global(true)
+                .takes_value(true),
         )
         .arg(
-            Arg::with_name("force")
-                .short("f")
-                .long("force")
-                .help("Overwrites existing output file if set"),
+            Arg::with_name("connectstring")
+                .long("connect-string")
+                .value_name("STRING")
+                .help("Sets a full connect string, overriding host, port and service")
+                .conflicts_with_all(&["host", "port", "service", "sid"])
+                .global(true)
+                .takes_value(true),
         )
         .arg(
-            Arg::with_name("uppercase")
-                .short("u")
-                .long("uppercase")
-                .help("Uppercase all column names"),
+            Arg::with_name("user")
+                .long("user")
+                .value_name("USER")
+                .help("Sets the database user, overriding the config file")
+                .global(true)
+                .takes_value(true),
         )
         .arg(
-            Arg::with_name("tablename")
-                .short("n")
-                .long("tablename")
-                .help("Overrides table name (default is input filename)")
+            Arg::with_name("passwordenv")
+                .long("password-env")
+                .value_name("VAR")
+                .help("Reads the database password from an environment variable")
+                .global(true)
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("INPUT")
-                .help("Sets the input file to use")
-                .required(true)
-                .index(1),
+            Arg::with_name("errorformat")
+                .long("error-format")
+                .value_name("FORMAT")
+                .help("Reports fatal errors as text or as a JSON object with code, message, table and phase")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text"),
         )
         .arg(
             Arg::with_name("v")
                 .short("v")
                 .multiple(true)
+                .global(true)
                 .help("Sets the level of verbosity"),
         )
-        .get_matches();
-
-    if matches.occurrences_of("v") > 0 {
-        let _ = simplelog::SimpleLogger::init(
-            match matches.occurrences_of("v") {
-                1 => log::LevelFilter::Error,
-                2 => log::LevelFilter::Warn,
-                3 => log::LevelFilter::Info,
-                4 => log::LevelFilter::Debug,
-                _ => log::LevelFilter::Trace,
-            },
-            simplelog::Config::default(),
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .global(true)
+                .help("Prints only warnings and errors, no progress messages"),
+        )
+        .arg(
+            Arg::with_name("nocolor")
+                .long("no-color")
+                .global(true)
+                .help("Disables colored output; also set by the NO_COLOR environment variable"),
+        )
+        .arg(
+            Arg::with_name("logfile")
+                .long("log-file")
+                .value_name("FILE")
+                .help("Appends log messages to a file instead of the terminal")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logformat")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("Writes log messages as text or as one JSON object per line")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::with_name("helpjson")
+                .long("help-json")
+                .help("Prints all subcommands and options as JSON for use by other tools"),
         );
-    }
+    with_export_args(app)
+        .subcommand(with_export_args(SubCommand::with_name("export").about(
+            "Exports table data into CSV; the default when no subcommand is given",
+        )))
+        .subcommand(
+            SubCommand::with_name("list-tables")
+                .about("Lists accessible tables")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("OWNER")
+                        .help("Only lists tables of this owner")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("like")
+                        .long("like")
+                        .value_name("PATTERN")
+                        .help("Only lists tables matching this LIKE pattern, e.g. AU%")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list-partitions")
+                .about("Lists the partitions of a table")
+                .arg(
+                    Arg::with_name("TABLE")
+                        .help("Sets the partitioned table, optionally as OWNER.TABLE")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("describe")
+                .visible_alias("schema")
+                .about("Prints columns, data types and nullability of a table")
+                .arg(
+                    Arg::with_name("TABLE")
+                        .help("Sets the table to describe, optionally as OWNER.TABLE")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Checks row count, size and checksum of an output file against its control file")
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Sets the output file to check (default is the file named in the control file)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("delimiter")
+                        .short("d")
+                        .long("delimiter")
+                        .value_name("CHAR")
+                        .help("Sets the field delimiter of the output file")
+                        .default_value(","),
+                )
+                .arg(
+                    Arg::with_name("CONTROL")
+                        .help("Sets the control file written by the export")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("copy")
+                .about("Copies the rows of a table into a table of a second database using batched inserts")
+                .arg(
+                    Arg::with_name("target")
+                        .long("target")
+                        .value_name("FILE")
+                        .help("Sets the configuration file of the target database")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("targettable")
+                        .long("target-table")
+                        .value_name("TABLE")
+                        .help("Sets the target table, optionally as OWNER.TABLE (default is the source table name)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("batchsize")
+                        .long("batch-size")
+                        .value_name("ROWS")
+                        .help("Sets the number of rows inserted per database round trip")
+                        .validator(|v| match v.parse::<u32>() {
+                            Ok(n) if n > 0 => Ok(()),
+                            _ => Err(String::from("batch size must be a positive number")),
+                        })
+                        .default_value("1000"),
+                )
+                .arg(
+                    Arg::with_name("TABLE")
+                        .help("Sets the table to copy, optionally as OWNER.TABLE")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Reports inserted, deleted and changed rows between two CSV exports or between a CSV export and its table")
+                .arg(
+                    Arg::with_name("table")
+                        .long("table")
+                        .value_name("TABLE")
+                        .help("Compares the old export with the current rows of this table")
+                        .takes_value(true)
+                        .conflicts_with("NEW"),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .value_name("COLNAMES")
+                        .help("Sets the comma separated columns matching rows (default with --table is the primary key)")
+                        .takes_value(true)
+                        .use_delimiter(true),
+                )
+                .arg(
+                    Arg::with_name("delimiter")
+                        .short("d")
+                        .long("delimiter")
+                        .value_name("CHAR")
+                        .help("Sets the field delimiter of the CSV files")
+                        .default_value(","),
+                )
+                .arg(
+                    Arg::with_name("OLD")
+                        .help("Sets the earlier CSV export")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("NEW")
+                        .help("Sets the later CSV export")
+                        .required_unless("table")
+                        .requires("key")
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check-config").about(
+                "Checks configuration file, environment and connection settings without connecting",
+            ),
+        )
+        .subcommand(
+            SubCommand::with_name("profile")
+                .about("Reports null counts, distinct values, value ranges and lengths of each column of a table")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Sets the report format")
+                        .possible_values(&["json", "csv"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets the report file (default is standard output)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("TABLE")
+                        .help("Sets the table to profile")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Asks for connection details, a table and its columns, then writes config.toml and a column input file")
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets the column input file to write (default is the table name with .txt)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .short("f")
+                        .long("force")
+                        .help("Overwrites existing configuration and column files"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("completions")
+                .about("Prints a shell completion script to standard output")
+                .arg(
+                    Arg::with_name("SHELL")
+                        .help("Sets the shell to generate completions for")
+                        .possible_values(&Shell::variants())
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("current-scn")
+                .about("Prints the current system change number for use with --as-of-scn"),
+        )
+        .subcommand(
+            SubCommand::with_name("columns-from-csv")
+                .about("Creates a column input file from the header of an existing CSV file")
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets output filename (default is standard output)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("uppercase")
+                        .short("u")
+                        .long("uppercase")
+                        .help("Uppercase all column names"),
+                )
+                .arg(
+                    Arg::with_name("delimiter")
+                        .short("d")
+                        .long("delimiter")
+                        .value_name("CHAR")
+                        .help("Sets the field delimiter of the CSV file")
+                        .default_value(","),
+                )
+                .arg(
+                    Arg::with_name("validate")
+                        .long("validate")
+                        .value_name("TABLE")
+                        .help("Checks that all columns exist in the given table")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("CSV")
+                        .help("Sets the CSV file to read")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+}
 
-    let start_stamp = std::time::SystemTime::now();
+fn main() {
+    let matches = cli().get_matches();
 
-    let config_name = matches.value_of("config").unwrap_or("config.toml");
-    println!("Using configuration file {}.", config_name.yellow());
-    let config = match Config::load(&std::path::PathBuf::from(config_name)) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!(
-                "Configuration file {} {} to load: {}",
-                config_name.yellow(),
-                "failed".red(),
-                e
+    // machine-readable errors must not contain terminal colors
+    if matches.value_of("errorformat") == Some("json") {
+        JSON_ERRORS.store(true, Ordering::SeqCst);
+        colored::control::set_override(false);
+    }
+    // NO_COLOR is honored even if colors are forced otherwise
+    if matches.is_present("nocolor") || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    {
+        colored::control::set_override(false);
+    }
+    if matches.is_present("quiet") {
+        QUIET.store(true, Ordering::SeqCst);
+    }
+
+    // logging to a file defaults to informational messages
+    let level = match matches.occurrences_of("v") {
+        0 if matches.occurrences_of("logfile") + matches.occurrences_of("logformat") > 0 => {
+            Some(log::LevelFilter::Info)
+        }
+        0 => None,
+        1 => Some(log::LevelFilter::Error),
+        2 => Some(log::LevelFilter::Warn),
+        3 => Some(log::LevelFilter::Info),
+        4 => Some(log::LevelFilter::Debug),
+        _ => Some(log::LevelFilter::Trace),
+    };
+    if let Some(level) = level {
+        // log formats are validated by clap
+        let mut log_config = LogConfig::new(level)
+            .format(LogFormat::parse(matches.value_of("logformat").unwrap()).unwrap());
+        if let Some(log_file) = matches.value_of("logfile") {
+            log_config = log_config.file(log_file);
+        }
+        if let Err(e) = log_config.init() {
+            fail(
+                ExitCode::Config,
+                None,
+                format!("{} to set up logging: {}", "Failed".red(), e),
             );
-            std::process::exit(5);
         }
+    }
+
+    if matches.is_present("helpjson") {
+        let app = cli();
+        // the description consists of strings, numbers and flags only
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&CommandUsage::new(&app)).unwrap()
+        );
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("completions") {
+        // shells are validated by clap
+        let shell = sub_matches.value_of("SHELL").unwrap().parse().unwrap();
+        cli().gen_completions_to("csvdump", shell, &mut std::io::stdout());
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("profile") {
+        profile_table(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("init") {
+        run_wizard(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("columns-from-csv") {
+        columns_from_csv(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("list-tables") {
+        print_tables(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("list-partitions") {
+        print_partitions(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("describe") {
+        print_description(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("verify") {
+        verify_output(sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("copy") {
+        copy_table(&matches, sub_matches);
+        return;
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("diff") {
+        if !diff_exports(&matches, sub_matches) {
+            std::process::exit(ExitCode::Diff as i32);
+        }
+        return;
+    }
+    if matches.subcommand_matches("current-scn").is_some() {
+        print_current_scn(&matches);
+        return;
+    }
+    if matches.subcommand_matches("check-config").is_some() {
+        check_config(&matches);
+        return;
+    }
+    // exporting without a subcommand is kept for existing scripts
+    let matches = match matches.subcommand_matches("export") {
+        Some(sub_matches) => sub_matches.clone(),
+        None => matches,
+    };
+    let ctx = RunContext::new(
+        matches.is_present("quiet"),
+        matches.value_of("errorformat") == Some("json"),
+    );
+    if let Some(metrics_file) = matches.value_of("metricsfile") {
+        let output_file = output_file_name(&matches);
+        ctx.set_metrics(RunMetrics::new(Path::new(metrics_file), &output_file));
+    }
+
+    let start_stamp = std::time::SystemTime::now();
+
+    let config = match try_load_config(&matches) {
+        Ok(config) => config,
+        Err((code, message)) => fail_run(&ctx, code, None, message),
     };
 
     // we can unwrap INPUT because it's a required parameter
     let data_file = matches.value_of("INPUT").unwrap();
 
     let force_flag = matches.is_present("force");
-    let quote_flag = matches.is_present("quoteall");
     let uppercase_flag = matches.is_present("uppercase");
-    let output_file = matches.value_of("output").unwrap();
+    let output_file = output_file_name(&matches);
+    let output_file = output_file.as_str();
+    if config.post_success().is_some() || config.post_failure().is_some() {
+        ctx.set_hooks(PostRunHooks::new(
+            config.post_success(),
+            config.post_failure(),
+            output_file,
+        ));
+    }
 
-    let output_file_path = std::path::PathBuf::from(output_file);
-    if output_file_path.exists() & !force_flag {
-        eprintln!(
-            "Output file {} exists but force flag not set. {}",
-            output_file.yellow(),
-            "Will not overwrite.".red()
+    // a remote file is exported locally first and uploaded once complete
+    let sftp_target = match SftpTarget::parse(output_file) {
+        Some(Ok(target)) => Some(target),
+        Some(Err(e)) => fail_run(
+            &ctx,
+            ExitCode::Input,
+            None,
+            format!(
+                "SFTP output {} {}: {}",
+                output_file.yellow(),
+                "invalid".red(),
+                e
+            ),
+        ),
+        None => None,
+    };
+    if let Some(arg) = [
+        "append",
+        "state",
+        "backfill",
+        "allpartitions",
+        "prunepattern",
+    ]
+    .iter()
+    .find(|arg| sftp_target.is_some() && matches.is_present(arg))
+    {
+        fail_run(
+            &ctx,
+            ExitCode::Input,
+            None,
+            format!("SFTP output {} with --{}.", "cannot be combined".red(), arg),
+        );
+    }
+    let output_file_path = match &sftp_target {
+        Some(target) => std::env::temp_dir().join(format!(
+            "csvdump-{}-{}",
+            std::process::id(),
+            target.file_name()
+        )),
+        None => std::path::PathBuf::from(output_file),
+    };
+    // appending and incremental exports add to an existing output file,
+    // backfills check the file of each partition instead
+    let append_flag =
+        (matches.is_present("append") || matches.is_present("state")) && output_file_path.exists();
+    if output_file_path.exists()
+        & !force_flag
+        & !append_flag
+        & !matches.is_present("backfill")
+        & !matches.is_present("allpartitions")
+    {
+        fail_run(
+            &ctx,
+            ExitCode::Exists,
+            None,
+            format!(
+                "Output file {} exists but force flag not set. {}",
+                output_file.yellow(),
+                "Will not overwrite.".red()
+            ),
         );
-        std::process::exit(14);
     }
 
     let data_file_path = std::path::PathBuf::from(data_file);
     if !data_file_path.exists() {
-        eprintln!("Input file {} {}.", data_file.yellow(), "not found".red());
-        std::process::exit(5);
+        fail_run(
+            &ctx,
+            ExitCode::Config,
+            None,
+            format!("Input file {} {}.", data_file.yellow(), "not found".red()),
+        );
     }
-    println!("Loading input file {}.", data_file.yellow());
-    let column_names = match read_parameters_file(&data_file_path, uppercase_flag) {
+    status!(ctx; "Loading input file {}.", data_file.yellow());
+    let mut column_names = match read_parameters_file(&data_file_path, uppercase_flag) {
         Ok(cn) => cn,
-        Err(e) => {
-            eprintln!(
+        Err(e) => fail_run(
+            &ctx,
+            ExitCode::Input,
+            None,
+            format!(
                 "Reading input file {} {}: {}",
                 data_file.yellow(),
                 "failed".red(),
                 e
+            ),
+        ),
+    };
+
+    if let Some(exclude_file) = matches.value_of("exclude") {
+        match read_parameters_file(Path::new(exclude_file), uppercase_flag) {
+            Ok(names) => column_names.extend(
+                names
+                    .into_iter()
+                    .map(|cn| format!("!{}", cn.trim_start_matches('!'))),
+            ),
+            Err(e) => fail_run(
+                &ctx,
+                ExitCode::Input,
+                None,
+                format!(
+                    "Reading exclude file {} {}: {}",
+                    exclude_file.yellow(),
+                    "failed".red(),
+                    e
+                ),
+            ),
+        }
+    }
+
+    for cn in &column_names {
+        if let Err(e) = parse_column_entry(cn) {
+            fail_run(
+                &ctx,
+                ExitCode::Input,
+                None,
+                format!(
+                    "Input file {} {}: {}",
+                    data_file.yellow(),
+                    "invalid".red(),
+                    e
+                ),
             );
-            std::process::exit(2)
         }
-    };
+    }
 
-    println!(
+    status!(
+        ctx;
         "Input file requests {} columns:",
         column_names.len().to_string().blue()
     );
     for cn in &column_names {
-        println!("{} * {}", " ".repeat(10), cn.blue());
+        status!(ctx; "{} * {}", " ".repeat(10), cn.blue());
     }
-    println!("Attempting database connection.");
-    let conn = match config.connect() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Database connection {}: {}", "failed".red(), e);
-            std::process::exit(10);
-        }
+    let conn = match try_connect(&config) {
+        Ok(conn) => Arc::new(conn),
+        Err((code, message)) => fail_run(&ctx, code, None, message),
     };
-    println!("Database connection {}.", "succeeded".green());
+    // cancel running queries on Ctrl-C so partial output can be discarded
+    if let Err(e) = interrupt::install() {
+        eprintln!("{} to install signal handler: {}", "Failed".red(), e);
+    }
+    interrupt::watch(Arc::clone(&conn));
 
     // if table name is overridden by input parameter, take user specified
     // table name, otherwise attempt to extract from input filename
     let table_name: String = match matches.value_of("tablename") {
         Some(tn) => String::from(tn),
         None => match data_file_path.file_stem() {
-            Some(st) => st.to_string_lossy().to_string(),
+            Some(st) => match TableNamer::new(config.naming()) {
+                Ok(namer) => namer.table_name(&st.to_string_lossy()),
+                Err(e) => {
+                    fail_run(
+                        &ctx,
+                        ExitCode::TableName,
+                        None,
+                        format!("{} to set up table naming: {}", "Failed".red(), e),
+                    );
+                }
+            },
             None => {
-                eprintln!(
-                    "{} to extract table name from file name {}.",
-                    "Failed".red(),
-                    data_file.yellow()
+                fail_run(
+                    &ctx,
+                    ExitCode::TableName,
+                    None,
+                    format!(
+                        "{} to extract table name from file name {}.",
+                        "Failed".red(),
+                        data_file.yellow()
+                    ),
                 );
-                std::process::exit(11);
             }
         },
     };
+    ctx.set_table(&table_name);
 
-    println!(
+    status!(
+        ctx;
         "Attempting to read table definition for {}.",
         table_name.blue()
     );
 
-    // set up table selection builder to construct
-    // meta data query about table column information
-    let mut builder = TableSelectionBuilder::new(&table_name);
-    for cn in &column_names {
-        // add specified column names
-        builder = builder.with(cn);
-    }
-
-    // run "build" to get table definition
-    let table_def = match builder.build(&conn) {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!(
-                "{} to read table definition for table {}: {}",
-                "Failed".red(),
-                table_name.yellow(),
-                e
-            );
-            std::process::exit(12);
-        }
+    let selection = Selection {
+        table_name: &table_name,
+        column_names: &column_names,
     };
-    println!(
-        "{} read table definition for table {}.",
-        "Successfully".green(),
-        table_name.blue()
-    );
-
-    // create output writer
-    let csv_build = if quote_flag {
-        csv::WriterBuilder::new().quote_style(csv::QuoteStyle::Always).from_path(output_file_path)
+    // a failed backfill or fan-out exits right away
+    let result = if matches.is_present("backfill") {
+        backfill(&ctx, &matches, &config, &conn, selection, &output_file_path);
+        Ok(())
+    } else if matches.is_present("allpartitions") {
+        fan_out(&ctx, &matches, &config, &conn, selection, &output_file_path);
+        Ok(())
     } else {
-        csv::Writer::from_path(output_file_path)
-    };
-    let mut csv_out = match csv_build {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!(
-                "{} to create CSV output file {}: {}",
-                "Failed".red(),
-                output_file.yellow(),
-                e
-            );
-            std::process::exit(15);
-        }
+        export_table(
+            &ctx,
+            &matches,
+            &config,
+            &conn,
+            selection,
+            &output_file_path,
+            None,
+        )
+        .map(|_| ())
     };
 
-    // write csv header
-    csv_out
-        .serialize(table_def.header())
-        .expect("Failed to serialize header.");
+    if interrupt::interrupted() {
+        fail_run(
+            &ctx,
+            ExitCode::Interrupted,
+            Some(&table_name),
+            format!("Export {}.", "interrupted".red()),
+        );
+    }
+    if let Err((code, message)) = result {
+        fail_run(&ctx, code, Some(&table_name), message);
+    }
+
+    // older exports are only pruned once a new one is complete
+    if let Some(pattern) = matches.value_of("prunepattern") {
+        prune_exports(&ctx, &matches, pattern, &output_file_path);
+    }
 
-    // laod the data
-    let data = match table_def.load_threaded() {
-        Ok(dt) => dt,
-        Err(e) => {
+    if let Some(target) = &sftp_target {
+        status!(ctx; "Uploading output to {}.", output_file.yellow());
+        if let Err(e) = target.upload(&output_file_path, config.sftp_identity_file()) {
+            // the complete export stays available for another attempt
+            fail_run(
+                &ctx,
+                ExitCode::Output,
+                Some(&table_name),
+                format!(
+                    "{} to upload {}: {}; the export is kept in {}.",
+                    "Failed".red(),
+                    output_file.yellow(),
+                    e,
+                    output_file_path.display().to_string().yellow()
+                ),
+            );
+        }
+        status!(ctx; "Upload {}.", "succeeded".green());
+        if let Err(e) = std::fs::remove_file(&output_file_path) {
             eprintln!(
-                "{} to read data for table {}: {}",
+                "{} to remove local copy {}: {}",
                 "Failed".red(),
-                table_name.yellow(),
+                output_file_path.display().to_string().yellow(),
                 e
             );
-            std::process::exit(13);
-        }
-    };
-
-    let counter: Arc<RwLock<u64>> = Arc::new(RwLock::new(0));
-    let thread_count = counter.clone();
-    let thread_queue = data.pipe().clone();
-    let t_handle = std::thread::spawn(move || {
-        let mut error_count: u16 = 0;
-        loop {
-            let is_empty: bool = match thread_queue.read() {
-                Ok(q) => q.is_empty(),
-                Err(e) => {
-                    eprintln!(
-                        "{} to acquire read lock on data queue: {}",
-                        "Failed".red(),
-                        e
-                    );
-                    error_count += 1;
-
-                    if error_count > 3 {
-                        panic!("Failed to acquire read lock beyond threshold.");
-                    }
-
-                    true
-                }
-            };
-            if is_empty {
-                std::thread::sleep(std::time::Duration::from_secs(1));
-                continue;
-            }
-
-            let next_row : RowIndicator = match thread_queue.write() {
-                Ok(mut q) => {
-                    match q.pop_front() {
-                        Some(i) => i,
-                        None => {
-                            eprintln!("Failed to retrieve element from queue.");
-                            continue;
-                        }
-                    }
-                },
-                Err(e) => {
-                    eprintln!(
-                        "{} to acquire read lock on data queue: {}",
-                        "Failed".red(),
-                        e
-                    );
-                    error_count += 1;
-
-                    if error_count > 3 {
-                        panic!("Failed to acquire read lock beyond threshold.");
-                    } else {
-                        continue;
-                    }
-                }
-            };
-
-            match next_row {
-                RowIndicator::MoreToCome(row) => csv_out.serialize(row).expect("Failed to serialize row."),
-                RowIndicator::EndOfData => break
-            };
-
-            match thread_count.write() {
-                Ok(mut c) => *c += 1,
-                Err(e) => eprintln!("{} to increment row counter: {}", "Failed".red(), e )
-            };
         }
-    });
-
-    match data.execute(&conn) {
-        Ok(()) => println!("Database loading completed {}.", "successfully".green()),
-        Err(e) => eprintln!("{} during database loading: {}", "Failure".red(), e )
-    };
-
-    println!("Waiting for writer thread to complete.");
-    if let Err(e) = t_handle.join() {
-        eprintln!("{} waiting for writer thread: {:?}", "Failed".red(), e );
-    } else {
-        println!("Writer thread shut down {}", "successfully".green());
     }
 
-    /*for row in data.rows() {
-        csv_out.serialize(row).expect("Failed to serialize row.");
-        counter += 1;
-    }*/
-
-    match counter.read() {
-        Ok(c) => println!(
-            "{} completed writing {} rows.",
-            "Successfully".green(),
-            (*c).to_string().green()
-        ),
-        Err(e) => eprintln!("{} to calculate final row count: {}", "Failed".red(), e ),
-    };
-
     match start_stamp.elapsed() {
-        Ok(t) => println!("Task completed in {} seconds.", t.as_secs()),
-        Err(e) => eprintln!("{} to measure elapsed time: {}", "Failed".red(), e)
+        Ok(t) => status!(ctx; "Task completed in {} seconds.", t.as_secs()),
+        Err(e) => eprintln!("{} to measure elapsed time: {}", "Failed".red(), e),
     };
+    if !run_hooks(&ctx, 0) {
+        fail_run(
+            &ctx,
+            ExitCode::Hook,
+            Some(&table_name),
+            format!("Post-success command {}.", "failed".red()),
+        );
+    }
+    write_metrics(&ctx, 0);
 }
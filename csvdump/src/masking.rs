@@ -0,0 +1,436 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Masking of sensitive column values
+//!
+
+use lib_oradb::definition::{ColumnValue, TableName, ValueFormat};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+const FIRST_NAMES: &[&str] = &[
+    "Anna", "Ben", "Clara", "David", "Emma", "Felix", "Greta", "Hans", "Ida", "Jonas", "Katrin",
+    "Lukas", "Maria", "Noah", "Olga", "Paul", "Rosa", "Stefan", "Theresa", "Uwe",
+];
+const LAST_NAMES: &[&str] = &[
+    "Bauer",
+    "Fischer",
+    "Gruber",
+    "Hofer",
+    "Huber",
+    "Koch",
+    "Lang",
+    "Mayer",
+    "Moser",
+    "Muller",
+    "Novak",
+    "Pichler",
+    "Schmid",
+    "Schneider",
+    "Steiner",
+    "Wagner",
+    "Weber",
+    "Wolf",
+];
+const STREETS: &[&str] = &[
+    "Bahnhofstrasse",
+    "Gartenweg",
+    "Hauptplatz",
+    "Kirchengasse",
+    "Lindenallee",
+    "Muhlweg",
+    "Parkstrasse",
+    "Ringstrasse",
+    "Schulgasse",
+    "Waldweg",
+];
+const CITIES: &[&str] = &[
+    "Altdorf",
+    "Bergheim",
+    "Feldkirchen",
+    "Hausen",
+    "Kirchberg",
+    "Neustadt",
+    "Oberdorf",
+    "Steinach",
+    "Unterberg",
+    "Waldhofen",
+];
+
+///
+/// Kinds of synthetic values
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntheticKind {
+    FirstName,
+    LastName,
+    FullName,
+    Street,
+    City,
+    Postcode,
+    Email,
+}
+
+///
+/// Masking strategy assigned to a column
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum MaskStrategy {
+    /// replaces value with salted SHA-256 hex digest
+    Hash { salt: String },
+    /// replaces value with a fixed text
+    Fixed { value: String },
+    /// keeps the start and end of a value and masks everything in between
+    Redact {
+        #[serde(default)]
+        keep_start: usize,
+        #[serde(default)]
+        keep_end: usize,
+        #[serde(default = "default_mask_char")]
+        mask_char: char,
+    },
+    /// replaces value with a plausible, deterministically chosen fake value
+    Synthetic {
+        kind: SyntheticKind,
+        #[serde(default)]
+        salt: String,
+    },
+}
+
+fn default_mask_char() -> char {
+    '*'
+}
+
+///
+/// Applies configured masking strategies to exported rows
+pub struct Masker {
+    /// masking strategy for each exported column, by position
+    strategies: Vec<Option<MaskStrategy>>,
+    /// format used to turn non-text values into text before masking
+    value_format: ValueFormat,
+}
+
+///
+/// Calculates salted SHA-256 digest of a value
+fn digest(salt: &str, value: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(value.as_bytes());
+
+    hasher.finalize().to_vec()
+}
+
+///
+/// Picks an entry from a list based on a digest
+fn pick<'a>(list: &[&'a str], digest: &[u8]) -> &'a str {
+    let index = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as usize;
+
+    list[index % list.len()]
+}
+
+impl MaskStrategy {
+    ///
+    /// Masks a single text value
+    fn apply(&self, value: &str) -> String {
+        match self {
+            MaskStrategy::Hash { salt } => digest(salt, value)
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            MaskStrategy::Fixed { value } => value.clone(),
+            MaskStrategy::Redact {
+                keep_start,
+                keep_end,
+                mask_char,
+            } => {
+                let chars: Vec<char> = value.chars().collect();
+                chars
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, c)| {
+                        if idx < *keep_start || idx + keep_end >= chars.len() {
+                            *c
+                        } else {
+                            *mask_char
+                        }
+                    })
+                    .collect()
+            }
+            MaskStrategy::Synthetic { kind, salt } => {
+                let d = digest(salt, value);
+                match kind {
+                    SyntheticKind::FirstName => String::from(pick(FIRST_NAMES, &d)),
+                    SyntheticKind::LastName => String::from(pick(LAST_NAMES, &d)),
+                    SyntheticKind::FullName => {
+                        format!("{} {}", pick(FIRST_NAMES, &d), pick(LAST_NAMES, &d[4..]))
+                    }
+                    SyntheticKind::Street => {
+                        format!("{} {}", pick(STREETS, &d), 1 + d[4] as u32 % 150)
+                    }
+                    SyntheticKind::City => String::from(pick(CITIES, &d)),
+                    SyntheticKind::Postcode => {
+                        format!("{:05}", u32::from_be_bytes([0, d[4], d[5], d[6]]) % 100_000)
+                    }
+                    SyntheticKind::Email => format!(
+                        "{}.{}@example.com",
+                        pick(FIRST_NAMES, &d).to_lowercase(),
+                        pick(LAST_NAMES, &d[4..]).to_lowercase()
+                    ),
+                }
+            }
+        }
+    }
+}
+
+///
+/// Whether the table part of a `TABLE.COLUMN` rule names `table`;
+/// an owner in the rule has to match as well
+fn targets(scope: &str, table: &TableName) -> bool {
+    let scope = TableName::parse(scope);
+
+    scope.table() == table.table() && scope.owner().is_none_or(|o| table.owner() == Some(o))
+}
+
+impl Masker {
+    ///
+    /// Creates a masker for the given header of `table`. Rules keyed
+    /// by column name apply to every table exporting that column; rules
+    /// keyed `TABLE.COLUMN` or `OWNER.TABLE.COLUMN` apply to that table
+    /// only and fail if it does not export the column.
+    pub fn new(
+        rules: &BTreeMap<String, MaskStrategy>,
+        table: &TableName,
+        header: &[String],
+    ) -> Result<Masker, String> {
+        let mut strategies: Vec<Option<MaskStrategy>> = vec![None; header.len()];
+        // table rules are applied last, so that they take precedence
+        let mut scoped: Vec<(&str, &str, &MaskStrategy)> = Vec::new();
+        for (key, strategy) in rules {
+            match key.rsplit_once('.') {
+                Some((scope, column)) if targets(scope, table) => {
+                    scoped.push((key, column, strategy))
+                }
+                // rules of other tables are left to their exports
+                Some(_) => (),
+                None => {
+                    if let Some(idx) = header.iter().position(|cn| cn == key) {
+                        strategies[idx] = Some(strategy.clone());
+                    }
+                }
+            }
+        }
+        for (key, column, strategy) in scoped {
+            match header.iter().position(|cn| cn == column) {
+                Some(idx) => strategies[idx] = Some(strategy.clone()),
+                None => return Err(format!("Masked column {} is not exported", key)),
+            }
+        }
+
+        Ok(Masker {
+            strategies,
+            value_format: ValueFormat::new(),
+        })
+    }
+
+    ///
+    /// Gets the number of exported columns masked
+    pub fn masked_columns(&self) -> usize {
+        self.strategies.iter().filter(|s| s.is_some()).count()
+    }
+
+    ///
+    /// Masks values of a row in place; NULL values remain NULL
+    pub fn apply(&self, row: &mut [Option<ColumnValue>]) {
+        for (strategy, value) in self.strategies.iter().zip(row.iter_mut()) {
+            if let (Some(strategy), Some(_)) = (strategy, value.as_ref()) {
+                let text = self.value_format.format(value);
+                *value = Some(ColumnValue::Varchar(strategy.apply(&text)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MaskStrategy, Masker, SyntheticKind, CITIES, FIRST_NAMES, LAST_NAMES};
+    use lib_oradb::definition::{ColumnValue, TableName};
+    use std::collections::BTreeMap;
+
+    fn synthetic(kind: SyntheticKind, salt: &str) -> MaskStrategy {
+        MaskStrategy::Synthetic {
+            kind,
+            salt: String::from(salt),
+        }
+    }
+
+    #[test]
+    fn test_salted_hash() {
+        let hash = MaskStrategy::Hash {
+            salt: String::from("pepper"),
+        };
+        assert_eq!(
+            "c07782f4d815b05d5431e996f5e20312127d6b07165ce4fa3cf03d9a9eac1b79",
+            hash.apply("alice@example.org")
+        );
+
+        let other_salt = MaskStrategy::Hash {
+            salt: String::from("salt"),
+        };
+        assert_ne!(
+            hash.apply("alice@example.org"),
+            other_salt.apply("alice@example.org")
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let redact = MaskStrategy::Redact {
+            keep_start: 2,
+            keep_end: 3,
+            mask_char: '#',
+        };
+        assert_eq!("AT#######234", redact.apply("AT6112345234"));
+        assert_eq!("ab", redact.apply("ab"));
+    }
+
+    #[test]
+    fn test_synthetic_values() {
+        let first_name = synthetic(SyntheticKind::FirstName, "s1");
+        assert!(FIRST_NAMES.contains(&first_name.apply("Christian").as_str()));
+        // the same input always yields the same fake value
+        assert_eq!(first_name.apply("Christian"), first_name.apply("Christian"));
+
+        let full_name = synthetic(SyntheticKind::FullName, "s1").apply("Christian Moerz");
+        let (first, last) = full_name.split_once(' ').unwrap();
+        assert!(FIRST_NAMES.contains(&first) && LAST_NAMES.contains(&last));
+
+        assert!(CITIES.contains(&synthetic(SyntheticKind::City, "").apply("Wien").as_str()));
+
+        let postcode = synthetic(SyntheticKind::Postcode, "").apply("1010");
+        assert_eq!(5, postcode.len());
+        assert!(postcode.chars().all(|c| c.is_ascii_digit()));
+
+        let email = synthetic(SyntheticKind::Email, "").apply("alice@example.org");
+        assert!(email.ends_with("@example.com"));
+        assert_eq!(email, email.to_lowercase());
+    }
+
+    #[test]
+    fn test_synthetic_salt_changes_values() {
+        // with 20 first names, some of many inputs must map differently
+        let unsalted = synthetic(SyntheticKind::FirstName, "");
+        let salted = synthetic(SyntheticKind::FirstName, "other");
+        assert!((0..20)
+            .map(|n| n.to_string())
+            .any(|v| unsalted.apply(&v) != salted.apply(&v)));
+    }
+
+    #[test]
+    fn test_masker() {
+        let header = vec![String::from("ID"), String::from("NAME")];
+        let mut rules = BTreeMap::new();
+        rules.insert(
+            String::from("ID"),
+            MaskStrategy::Fixed {
+                value: String::from("X"),
+            },
+        );
+        let table = TableName::parse("EMP");
+        let masker = Masker::new(&rules, &table, &header).unwrap();
+
+        let mut row = vec![
+            Some(ColumnValue::Number(42)),
+            Some(ColumnValue::Varchar(String::from("Anna"))),
+        ];
+        masker.apply(&mut row);
+        assert_eq!(Some(ColumnValue::Varchar(String::from("X"))), row[0]);
+        assert_eq!(Some(ColumnValue::Varchar(String::from("Anna"))), row[1]);
+
+        // NULL values remain NULL
+        let mut row = vec![None, None];
+        masker.apply(&mut row);
+        assert_eq!(None, row[0]);
+
+        // a column rule is skipped by tables without the column
+        rules.insert(
+            String::from("EMAIL"),
+            MaskStrategy::Fixed {
+                value: String::new(),
+            },
+        );
+        assert_eq!(
+            1,
+            Masker::new(&rules, &table, &header)
+                .unwrap()
+                .masked_columns()
+        );
+    }
+
+    #[test]
+    fn test_masker_rules_of_several_tables() {
+        let fixed = |value: &str| MaskStrategy::Fixed {
+            value: String::from(value),
+        };
+        let mut rules = BTreeMap::new();
+        rules.insert(String::from("NAME"), fixed("any"));
+        rules.insert(String::from("CUSTOMERS.EMAIL"), fixed("customer"));
+        rules.insert(String::from("CUSTOMERS.NAME"), fixed("customer"));
+        rules.insert(String::from("SCOTT.ORDERS.NOTE"), fixed("order"));
+
+        // rules of other tables do not apply
+        let header = vec![String::from("ID"), String::from("NAME")];
+        let masker = Masker::new(&rules, &TableName::parse("EMP"), &header).unwrap();
+        let mut row = vec![
+            Some(ColumnValue::Number(1)),
+            Some(ColumnValue::Varchar(String::from("Anna"))),
+        ];
+        masker.apply(&mut row);
+        assert_eq!(Some(ColumnValue::Number(1)), row[0]);
+        assert_eq!(Some(ColumnValue::Varchar(String::from("any"))), row[1]);
+
+        // table rules take precedence over column rules
+        let header = vec![String::from("NAME"), String::from("EMAIL")];
+        let masker = Masker::new(&rules, &TableName::parse("customers"), &header).unwrap();
+        let mut row = vec![
+            Some(ColumnValue::Varchar(String::from("Anna"))),
+            Some(ColumnValue::Varchar(String::from("anna@example.org"))),
+        ];
+        masker.apply(&mut row);
+        assert_eq!(Some(ColumnValue::Varchar(String::from("customer"))), row[0]);
+        assert_eq!(Some(ColumnValue::Varchar(String::from("customer"))), row[1]);
+
+        // a table rule fails if its table does not export the column
+        let header = vec![String::from("NAME")];
+        assert!(Masker::new(&rules, &TableName::parse("CUSTOMERS"), &header).is_err());
+
+        // an owner in the rule has to match
+        let header = vec![String::from("ID")];
+        assert!(Masker::new(&rules, &TableName::parse("ORDERS"), &header).is_ok());
+        assert!(Masker::new(&rules, &TableName::parse("HR.ORDERS"), &header).is_ok());
+        assert!(Masker::new(&rules, &TableName::parse("SCOTT.ORDERS"), &header).is_err());
+    }
+}
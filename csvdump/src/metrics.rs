@@ -0,0 +1,144 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Run metrics in the Prometheus textfile format
+//!
+
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+///
+/// Totals of one csvdump run, written for the node exporter's
+/// textfile collector once the run ends
+pub struct RunMetrics {
+    /// file the metrics are written to
+    path: PathBuf,
+    /// output file naming the export in the labels
+    output: String,
+    /// time the run started
+    started: SystemTime,
+    /// rows written to all output files
+    rows: u64,
+    /// bytes added to all output files
+    bytes: u64,
+}
+
+///
+/// Escapes a label value, e.g. a quoted file name
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl RunMetrics {
+    pub fn new(path: &Path, output: &str) -> RunMetrics {
+        RunMetrics {
+            path: path.to_path_buf(),
+            output: String::from(output),
+            started: SystemTime::now(),
+            rows: 0,
+            bytes: 0,
+        }
+    }
+
+    ///
+    /// Adds the totals of one complete output file, e.g. a partition
+    pub fn add_export(&mut self, rows: u64, bytes: u64) {
+        self.rows += rows;
+        self.bytes += bytes;
+    }
+
+    ///
+    /// Formats the metrics of a run ending with an exit code
+    fn render(&self, exit_code: i32, ended: SystemTime) -> String {
+        let labels = format!("{{output=\"{}\"}}", escape_label(&self.output));
+        let duration = ended
+            .duration_since(self.started)
+            .unwrap_or(Duration::ZERO)
+            .as_secs_f64();
+        let timestamp = ended
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs_f64();
+        let metrics: [(&str, &str, String); 6] = [
+            (
+                "csvdump_rows_exported",
+                "Rows written to the output by the last run.",
+                self.rows.to_string(),
+            ),
+            (
+                "csvdump_bytes_written",
+                "Bytes added to the output by the last run.",
+                self.bytes.to_string(),
+            ),
+            (
+                "csvdump_duration_seconds",
+                "Duration of the last run.",
+                format!("{:.3}", duration),
+            ),
+            (
+                "csvdump_success",
+                "Whether the last run completed successfully.",
+                String::from(if exit_code == 0 { "1" } else { "0" }),
+            ),
+            (
+                "csvdump_exit_code",
+                "Exit code of the last run.",
+                exit_code.to_string(),
+            ),
+            (
+                "csvdump_last_run_timestamp_seconds",
+                "Time the last run ended.",
+                format!("{:.3}", timestamp),
+            ),
+        ];
+
+        metrics
+            .iter()
+            .map(|(name, help, value)| {
+                format!(
+                    "# HELP {} {}\n# TYPE {} gauge\n{}{} {}\n",
+                    name, help, name, name, labels, value
+                )
+            })
+            .collect()
+    }
+
+    ///
+    /// Writes the metrics of a run ending with an exit code; the file
+    /// is replaced at once so a collector never reads it half-written
+    pub fn write(&self, exit_code: i32) -> Result<()> {
+        let mut temp_path = self.path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, self.render(exit_code, SystemTime::now()))?;
+
+        std::fs::rename(&temp_path, &self.path)
+    }
+}
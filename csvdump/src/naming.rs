@@ -0,0 +1,133 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Derivation of table names from input file names
+//!
+
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+///
+/// Configured rules for deriving table names, applied in order:
+/// mapping, pattern, then prefix and suffix stripping
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingStrategy {
+    /// file name stems mapped to table names
+    mapping: BTreeMap<String, String>,
+    /// csv file with further `stem,table` mappings
+    mapping_file: Option<String>,
+    /// regular expression capturing the table name in group `table` or group 1
+    pattern: Option<String>,
+    /// prefixes removed from the file name stem
+    strip_prefixes: Vec<String>,
+    /// suffixes removed from the file name stem
+    strip_suffixes: Vec<String>,
+}
+
+///
+/// Resolves table names according to a `NamingStrategy`
+pub struct TableNamer<'a> {
+    strategy: &'a NamingStrategy,
+    mapping: BTreeMap<String, String>,
+    pattern: Option<Regex>,
+}
+
+impl<'a> TableNamer<'a> {
+    ///
+    /// Loads the mapping file and compiles the pattern of a strategy
+    pub fn new(strategy: &'a NamingStrategy) -> Result<TableNamer<'a>, String> {
+        let mut mapping = strategy.mapping.clone();
+        if let Some(mapping_file) = &strategy.mapping_file {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_path(Path::new(mapping_file))
+                .map_err(|e| format!("Failed to open mapping file {}: {}", mapping_file, e))?;
+            for record in reader.records() {
+                let record = record
+                    .map_err(|e| format!("Failed to read mapping file {}: {}", mapping_file, e))?;
+                match (record.get(0), record.get(1)) {
+                    (Some(stem), Some(table)) => {
+                        mapping.insert(String::from(stem.trim()), String::from(table.trim()));
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Mapping file {} needs two columns per line",
+                            mapping_file
+                        ))
+                    }
+                }
+            }
+        }
+
+        let pattern = match &strategy.pattern {
+            Some(p) => Some(Regex::new(p).map_err(|e| format!("Invalid naming pattern: {}", e))?),
+            None => None,
+        };
+
+        Ok(TableNamer {
+            strategy,
+            mapping,
+            pattern,
+        })
+    }
+
+    ///
+    /// Derives the table name for a file name stem
+    pub fn table_name(&self, stem: &str) -> String {
+        if let Some(table) = self.mapping.get(stem) {
+            return table.clone();
+        }
+
+        if let Some(captures) = self.pattern.as_ref().and_then(|p| p.captures(stem)) {
+            if let Some(table) = captures.name("table").or_else(|| captures.get(1)) {
+                return String::from(table.as_str());
+            }
+        }
+
+        let mut name = stem;
+        if let Some(rest) = self
+            .strategy
+            .strip_prefixes
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix.as_str()))
+        {
+            name = rest;
+        }
+        if let Some(rest) = self
+            .strategy
+            .strip_suffixes
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix.as_str()))
+        {
+            name = rest;
+        }
+
+        String::from(name)
+    }
+}
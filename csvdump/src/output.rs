@@ -0,0 +1,422 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Output file handling
+//!
+
+use flate2::write::GzEncoder;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Error, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+///
+/// Compression applied to output files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+///
+/// Character encoding of output files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Windows1252,
+    Latin1,
+}
+
+/// UTF-8 byte order mark
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// characters Windows-1252 maps to bytes 0x80 to 0x9F; unassigned bytes are `None`
+const WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+impl Encoding {
+    ///
+    /// Parses an encoding name as accepted on the command line
+    pub fn parse(name: &str) -> Option<Encoding> {
+        match name.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "windows-1252" | "cp1252" => Some(Encoding::Windows1252),
+            "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Encodes a single character; `None` if the encoding lacks it
+    fn encode_char(self, c: char) -> Option<u8> {
+        let code = c as u32;
+        match self {
+            Encoding::Utf8 => None,
+            Encoding::Latin1 if code <= 0xFF => Some(code as u8),
+            Encoding::Latin1 => None,
+            Encoding::Windows1252 if code < 0x80 || (0xA0..=0xFF).contains(&code) => {
+                Some(code as u8)
+            }
+            Encoding::Windows1252 => WINDOWS_1252_HIGH
+                .iter()
+                .position(|m| *m == Some(c))
+                .map(|idx| 0x80 + idx as u8),
+        }
+    }
+}
+
+///
+/// Converts UTF-8 written by the csv writer into the output encoding;
+/// characters missing from the encoding fail the write
+pub struct Transcoder<W: Write> {
+    inner: W,
+    encoding: Encoding,
+    /// trailing bytes of an incomplete UTF-8 sequence
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Transcoder<W> {
+    ///
+    /// Wraps a writer with the given output encoding
+    pub fn new(inner: W, encoding: Encoding) -> Transcoder<W> {
+        Transcoder {
+            inner,
+            encoding,
+            pending: Vec::new(),
+        }
+    }
+
+    ///
+    /// Returns the wrapped writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for Transcoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.encoding == Encoding::Utf8 {
+            return self.inner.write(buf);
+        }
+
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // an incomplete sequence at the end is completed by the next write
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+            }
+        };
+
+        let text = std::str::from_utf8(&self.pending[..valid]).unwrap();
+        let mut encoded: Vec<u8> = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            match self.encoding.encode_char(c) {
+                Some(b) => encoded.push(b),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "character U+{:04X} cannot be encoded in {:?}",
+                            c as u32, self.encoding
+                        ),
+                    ))
+                }
+            }
+        }
+        self.inner.write_all(&encoded)?;
+        self.pending.drain(..valid);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+///
+/// Gets the path an output file is written to before it is renamed
+/// into place, e.g. `output.csv.part` for `output.csv`
+pub fn part_path(filename: &Path) -> PathBuf {
+    let mut part = filename.as_os_str().to_owned();
+    part.push(".part");
+
+    PathBuf::from(part)
+}
+
+///
+/// Tool encrypting output files for their recipients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncryptionTool {
+    Age,
+    OpenPgp,
+}
+
+///
+/// Encryption of output files by an external tool, so that
+/// exported data never reaches the disk unencrypted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encryption {
+    tool: EncryptionTool,
+    /// public keys, key IDs or e-mail addresses to encrypt for
+    recipients: Vec<String>,
+    /// whether output is ASCII armored instead of binary
+    armor: bool,
+}
+
+impl Encryption {
+    ///
+    /// Chooses the tool from the recipients: age public keys and SSH
+    /// keys are encrypted with age, anything else with OpenPGP
+    pub fn new(recipients: &[&str], armor: bool) -> Result<Encryption, String> {
+        let is_age = |r: &&str| r.starts_with("age1") || r.starts_with("ssh-");
+        let tool = if recipients.iter().all(is_age) {
+            EncryptionTool::Age
+        } else if !recipients.iter().any(is_age) {
+            EncryptionTool::OpenPgp
+        } else {
+            return Err(String::from("age and OpenPGP recipients cannot be mixed"));
+        };
+
+        Ok(Encryption {
+            tool,
+            recipients: recipients.iter().map(|r| String::from(*r)).collect(),
+            armor,
+        })
+    }
+
+    pub fn tool(&self) -> EncryptionTool {
+        self.tool
+    }
+
+    ///
+    /// Builds the command encrypting standard input to standard output
+    fn command(&self) -> Command {
+        let mut command = match self.tool {
+            EncryptionTool::Age => {
+                let mut c = Command::new("age");
+                c.arg("--encrypt");
+                c
+            }
+            EncryptionTool::OpenPgp => {
+                let mut c = Command::new("gpg");
+                // recipients are chosen explicitly by the operator,
+                // so keys need not be certified in the web of trust
+                c.args(["--batch", "--quiet", "--trust-model", "always", "--encrypt"]);
+                c
+            }
+        };
+        for recipient in &self.recipients {
+            command.arg("--recipient").arg(recipient);
+        }
+        if self.armor {
+            command.arg("--armor");
+        }
+
+        command
+    }
+}
+
+///
+/// File receiving output directly or through an encryption tool
+pub enum OutputFile {
+    Direct(File),
+    Encrypted {
+        tool: Child,
+        input: ChildStdin,
+        file: File,
+    },
+}
+
+impl OutputFile {
+    ///
+    /// Starts an encryption tool writing into a file
+    fn encrypted(file: File, encryption: &Encryption) -> std::io::Result<OutputFile> {
+        let mut command = encryption.command();
+        let mut tool = command
+            .stdin(Stdio::piped())
+            .stdout(file.try_clone()?)
+            .spawn()
+            .map_err(|e| {
+                let program = command.get_program().to_string_lossy();
+                Error::new(e.kind(), format!("failed to start {}: {}", program, e))
+            })?;
+        // stdin is piped above
+        let input = tool.stdin.take().unwrap();
+
+        Ok(OutputFile::Encrypted { tool, input, file })
+    }
+
+    ///
+    /// Waits for encryption to complete and syncs the file to disk
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Direct(file) => file.sync_all(),
+            OutputFile::Encrypted {
+                mut tool,
+                input,
+                file,
+            } => {
+                // closing the input lets the tool write its last block
+                drop(input);
+                let status = tool.wait()?;
+                if !status.success() {
+                    return Err(Error::other(format!("encryption failed with {}", status)));
+                }
+
+                file.sync_all()
+            }
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputFile::Direct(f) => f.write(buf),
+            OutputFile::Encrypted { input, .. } => input.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Direct(f) => f.flush(),
+            OutputFile::Encrypted { input, .. } => input.flush(),
+        }
+    }
+}
+
+///
+/// Destination for exported data
+pub enum OutputSink {
+    Plain(BufWriter<OutputFile>),
+    Gzip(GzEncoder<BufWriter<OutputFile>>),
+}
+
+impl OutputSink {
+    ///
+    /// Creates output file with requested compression and encryption
+    pub fn create(
+        filename: &Path,
+        compression: Compression,
+        encryption: Option<&Encryption>,
+    ) -> std::io::Result<OutputSink> {
+        let file = File::create(filename)?;
+        let file = match encryption {
+            Some(e) => OutputFile::encrypted(file, e)?,
+            None => OutputFile::Direct(file),
+        };
+
+        Ok(OutputSink::wrap(file, compression))
+    }
+
+    ///
+    /// Opens output file for appending; compressed data is added as a new gzip member
+    pub fn append(filename: &Path, compression: Compression) -> std::io::Result<OutputSink> {
+        let file = OpenOptions::new().append(true).open(filename)?;
+
+        Ok(OutputSink::wrap(OutputFile::Direct(file), compression))
+    }
+
+    ///
+    /// Wraps an opened file with requested compression
+    fn wrap(file: OutputFile, compression: Compression) -> OutputSink {
+        let file = BufWriter::new(file);
+
+        match compression {
+            Compression::None => OutputSink::Plain(file),
+            Compression::Gzip => {
+                OutputSink::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+        }
+    }
+
+    ///
+    /// Flushes all pending data, completes compression and encryption
+    /// and syncs the file to disk so that a subsequent rename exposes
+    /// complete data
+    pub fn finish(self) -> std::io::Result<()> {
+        let file = match self {
+            OutputSink::Plain(f) => f,
+            OutputSink::Gzip(gz) => gz.finish()?,
+        };
+
+        file.into_inner()
+            .map_err(|e| Error::new(e.error().kind(), e.error().to_string()))?
+            .finish()
+    }
+}
+
+impl Write for OutputSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputSink::Plain(f) => f.write(buf),
+            OutputSink::Gzip(gz) => gz.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputSink::Plain(f) => f.flush(),
+            OutputSink::Gzip(gz) => gz.flush(),
+        }
+    }
+}
@@ -0,0 +1,400 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Chain of transforms applied to every loaded row before it is written
+//!
+
+use crate::dedupe::Deduplicator;
+use crate::defaults::NullDefaults;
+use crate::dictionary::DictionaryEncoder;
+use crate::hints::HintCollector;
+use crate::lobfiles::LobWriter;
+use crate::masking::Masker;
+use crate::output::{OutputSink, Transcoder};
+use crate::pool::WriterPool;
+use crate::rowhash::RowHasher;
+use crate::sqlite::SqliteWriter;
+use crate::truncate::Truncator;
+use crate::validation::Validator;
+use lib_oradb::definition::{ColumnValue, ValueFormat};
+use lib_oradb::Error;
+
+///
+/// Wraps a failure to write rows out
+fn serialization_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::SerializationError(e.to_string())
+}
+
+///
+/// Destination of the rows passing the pipeline
+pub enum RowOutput {
+    /// text records serialized on the calling thread
+    Csv(Box<csv::Writer<Transcoder<OutputSink>>>),
+    /// text records serialized on several threads
+    Pool(WriterPool<OutputSink>),
+    /// native values inserted into a SQLite database
+    Sqlite(SqliteWriter),
+}
+
+impl RowOutput {
+    ///
+    /// Writes a header line; a SQLite database has none
+    pub fn write_header(&mut self, header: Vec<String>) -> Result<(), Error> {
+        match self {
+            RowOutput::Csv(w) => w.write_record(header).map_err(Error::from),
+            RowOutput::Pool(w) => w.write_record(header).map_err(serialization_error),
+            RowOutput::Sqlite(_) => Ok(()),
+        }
+    }
+
+    ///
+    /// Writes the record of a row; SQLite takes native values from `row`
+    fn write(&mut self, row: &[Option<ColumnValue>], record: Vec<String>) -> Result<(), Error> {
+        match self {
+            RowOutput::Csv(w) => w.write_record(record).map_err(Error::from),
+            RowOutput::Pool(w) => w.write_record(record).map_err(serialization_error),
+            RowOutput::Sqlite(w) => w.write(row, &record).map_err(serialization_error),
+        }
+    }
+
+    ///
+    /// Flushes remaining data, completes compression and closes the output
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            RowOutput::Csv(w) => w
+                .into_inner()
+                .map_err(|e| serialization_error(e.error()))
+                .and_then(|transcoder| {
+                    transcoder
+                        .into_inner()
+                        .finish()
+                        .map_err(serialization_error)
+                }),
+            RowOutput::Pool(w) => w
+                .finish()
+                .and_then(|sink| sink.finish())
+                .map_err(serialization_error),
+            RowOutput::Sqlite(w) => w.finish().map_err(serialization_error),
+        }
+    }
+}
+
+///
+/// Passes rows through deduplication, NULL defaults, validation,
+/// masking, rejection, type hints, LOB files, truncation, dictionary
+/// encoding and row hashing, in this order, into the output.
+/// Each stage is optional; a pipeline without stages writes rows as
+/// formatted.
+pub struct RowPipeline {
+    value_format: ValueFormat,
+    deduplicator: Option<Deduplicator>,
+    null_defaults: Option<NullDefaults>,
+    validator: Option<Validator>,
+    masker: Option<Masker>,
+    /// receives rows failing validation together with the reason
+    rejects: Option<csv::Writer<OutputSink>>,
+    hints: Option<HintCollector>,
+    lob_writer: Option<LobWriter>,
+    truncator: Option<Truncator>,
+    dictionary: Option<DictionaryEncoder>,
+    row_hasher: Option<RowHasher>,
+    output: Option<RowOutput>,
+    /// number of rows written into the rejects file
+    rejected: u64,
+    /// number of rows skipped as duplicates
+    duplicates: u64,
+}
+
+impl RowPipeline {
+    ///
+    /// Creates a `RowPipeline` formatting values with `value_format`
+    pub fn new(value_format: ValueFormat) -> RowPipeline {
+        RowPipeline {
+            value_format,
+            deduplicator: None,
+            null_defaults: None,
+            validator: None,
+            masker: None,
+            rejects: None,
+            hints: None,
+            lob_writer: None,
+            truncator: None,
+            dictionary: None,
+            row_hasher: None,
+            output: None,
+            rejected: 0,
+            duplicates: 0,
+        }
+    }
+
+    ///
+    /// Skips rows whose keys were seen before
+    pub fn deduplicate(mut self, deduplicator: Deduplicator) -> Self {
+        self.deduplicator = Some(deduplicator);
+        self
+    }
+
+    ///
+    /// Replaces NULL values before rows are validated
+    pub fn null_defaults(mut self, null_defaults: NullDefaults) -> Self {
+        self.null_defaults = Some(null_defaults);
+        self
+    }
+
+    ///
+    /// Checks rows before they are masked
+    pub fn validate(mut self, validator: Validator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    ///
+    /// Masks sensitive columns
+    pub fn mask(mut self, masker: Masker) -> Self {
+        self.masker = Some(masker);
+        self
+    }
+
+    ///
+    /// Writes rows failing validation into `rejects` instead of the output;
+    /// without a writer they are exported like any other row
+    pub fn reject_into(mut self, rejects: csv::Writer<OutputSink>) -> Self {
+        self.rejects = Some(rejects);
+        self
+    }
+
+    ///
+    /// Collects statistics of the exported rows for type hints
+    pub fn collect_hints(mut self, hints: HintCollector) -> Self {
+        self.hints = Some(hints);
+        self
+    }
+
+    ///
+    /// Moves LOB values into side files
+    pub fn lob_files(mut self, lob_writer: LobWriter) -> Self {
+        self.lob_writer = Some(lob_writer);
+        self
+    }
+
+    ///
+    /// Cuts oversized text values
+    pub fn truncate(mut self, truncator: Truncator) -> Self {
+        self.truncator = Some(truncator);
+        self
+    }
+
+    ///
+    /// Replaces values of low-cardinality columns by their codes
+    pub fn dictionary(mut self, dictionary: DictionaryEncoder) -> Self {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    ///
+    /// Appends a hash of each row
+    pub fn hash_rows(mut self, row_hasher: RowHasher) -> Self {
+        self.row_hasher = Some(row_hasher);
+        self
+    }
+
+    ///
+    /// Sets the destination of the rows
+    pub fn write_to(mut self, output: RowOutput) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    ///
+    /// Whether rows are validated
+    pub fn validates(&self) -> bool {
+        self.validator.is_some()
+    }
+
+    ///
+    /// Writes a header line into the output
+    pub fn write_header(&mut self, header: Vec<String>) -> Result<(), Error> {
+        match self.output.as_mut() {
+            Some(output) => output.write_header(header),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Passes a row through all stages into the rejects file or the output;
+    /// returns the names and original lengths of truncated columns
+    pub fn process(
+        &mut self,
+        mut row: Vec<Option<ColumnValue>>,
+    ) -> Result<Vec<(&str, usize)>, Error> {
+        if self
+            .deduplicator
+            .as_mut()
+            .is_some_and(|d| d.is_duplicate(&row))
+        {
+            self.duplicates += 1;
+            return Ok(Vec::new());
+        }
+        if let Some(n) = self.null_defaults.as_ref() {
+            n.apply(&mut row);
+        }
+        let reject_reason = self.validator.as_ref().and_then(|v| v.check(&row));
+        if let Some(m) = self.masker.as_ref() {
+            m.apply(&mut row);
+        }
+        if let (Some(reason), Some(r)) = (reject_reason, self.rejects.as_mut()) {
+            let mut record = self.value_format.format_row(&row);
+            record.push(reason);
+            r.write_record(record)?;
+            self.rejected += 1;
+            return Ok(Vec::new());
+        }
+        if let Some(h) = self.hints.as_mut() {
+            h.observe(&row);
+        }
+        let mut record = self.value_format.format_row(&row);
+        if let Some(w) = self.lob_writer.as_ref() {
+            w.write(&row, &mut record).map_err(serialization_error)?;
+        }
+        let truncated = self
+            .truncator
+            .as_ref()
+            .map(|t| t.apply(&mut record))
+            .unwrap_or_default();
+        if let Some(d) = self.dictionary.as_mut() {
+            d.encode(&row, &mut record);
+        }
+        if let Some(rh) = self.row_hasher.as_ref() {
+            record.push(rh.hash(&row));
+        }
+        if let Some(output) = self.output.as_mut() {
+            output.write(&row, record)?;
+        }
+
+        Ok(truncated)
+    }
+
+    ///
+    /// Completes the rejects file, if any
+    pub fn finish_rejects(&mut self) -> std::io::Result<()> {
+        match self.rejects.take() {
+            Some(r) => r
+                .into_inner()
+                .map_err(|e| std::io::Error::new(e.error().kind(), e.error().to_string()))
+                .and_then(|sink| sink.finish()),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Completes the output; fails if no output was set
+    pub fn finish(&mut self) -> Result<(), Error> {
+        match self.output.take() {
+            Some(output) => output.finish(),
+            None => Err(serialization_error("no output to complete")),
+        }
+    }
+
+    ///
+    /// Gets the statistics collected for type hints
+    pub fn hints(&self) -> Option<&HintCollector> {
+        self.hints.as_ref()
+    }
+
+    ///
+    /// Gets the dictionary of the encoded columns
+    pub fn dictionary_encoder(&self) -> Option<&DictionaryEncoder> {
+        self.dictionary.as_ref()
+    }
+
+    ///
+    /// Gets the number of rows written into the rejects file
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    ///
+    /// Gets the number of rows skipped as duplicates
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RowOutput, RowPipeline};
+    use crate::dedupe::Deduplicator;
+    use crate::output::{Compression, OutputSink, Transcoder};
+    use crate::profile::OutputProfile;
+    use crate::rowhash::RowHasher;
+    use lib_oradb::definition::{ColumnValue, ValueFormat};
+
+    #[test]
+    fn test_rows_pass_stages_into_output() {
+        let path =
+            std::env::temp_dir().join(format!("csvdump_pipeline_{}.csv", std::process::id()));
+        let profile = OutputProfile::default();
+        let sink = OutputSink::create(&path, Compression::None, None).unwrap();
+        let writer = profile
+            .writer_builder()
+            .from_writer(Transcoder::new(sink, profile.encoding));
+        let mut header = vec![String::from("ID"), String::from("NAME")];
+        let mut pipeline = RowPipeline::new(ValueFormat::default())
+            .deduplicate(Deduplicator::new(&["ID"], &header).unwrap())
+            .hash_rows(RowHasher::default())
+            .write_to(RowOutput::Csv(Box::new(writer)));
+
+        header.push(String::from("ROW_HASH"));
+        pipeline.write_header(header).unwrap();
+        for (id, name) in [(1, "a"), (2, "b"), (1, "c")] {
+            let row = vec![
+                Some(ColumnValue::Number(id)),
+                Some(ColumnValue::Varchar(String::from(name))),
+            ];
+            assert!(pipeline.process(row).unwrap().is_empty());
+        }
+        pipeline.finish().unwrap();
+        assert_eq!(1, pipeline.duplicates());
+        assert_eq!(0, pipeline.rejected());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(3, lines.len());
+        // the row hash is appended after the exported columns
+        assert_eq!("ID,NAME,ROW_HASH", lines[0]);
+        assert!(lines[1].starts_with("1,a,"));
+        assert!(lines[2].starts_with("2,b,"));
+    }
+
+    #[test]
+    fn test_finish_without_output() {
+        let mut pipeline = RowPipeline::new(ValueFormat::default());
+        assert!(pipeline.finish_rejects().is_ok());
+        assert!(pipeline.finish().is_err());
+    }
+}
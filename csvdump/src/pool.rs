@@ -0,0 +1,188 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Parallel serialization of records with output in record order
+//!
+
+use crate::output::Transcoder;
+use crate::profile::OutputProfile;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// number of records serialized by a worker at once
+const CHUNK_RECORDS: usize = 1000;
+
+/// a chunk of records together with its position in the output
+type Job = (u64, Vec<Vec<String>>);
+
+///
+/// Serializes records on several worker threads; a sequencer
+/// thread writes the serialized chunks to the sink in order
+pub struct WriterPool<W: Write + Send + 'static> {
+    /// records collected for the next chunk
+    chunk: Vec<Vec<String>>,
+    /// position of the next chunk in the output
+    sequence: u64,
+    jobs: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    sequencer: JoinHandle<Result<W>>,
+}
+
+///
+/// Serializes records with the dialect and encoding of `profile`
+fn serialize(profile: &OutputProfile, records: Vec<Vec<String>>) -> Result<Vec<u8>> {
+    let mut writer = profile
+        .writer_builder()
+        .from_writer(Transcoder::new(Vec::new(), profile.encoding));
+    for record in records {
+        writer.write_record(record)?;
+    }
+
+    match writer.into_inner() {
+        Ok(transcoder) => Ok(transcoder.into_inner()),
+        Err(e) => Err(Error::new(e.error().kind(), e.error().to_string())),
+    }
+}
+
+///
+/// Takes jobs until the pool is finished or the sequencer stopped
+fn work(
+    profile: &OutputProfile,
+    jobs: &Mutex<Receiver<Job>>,
+    done: &SyncSender<(u64, Result<Vec<u8>>)>,
+) {
+    loop {
+        let job = match jobs.lock() {
+            Ok(jobs) => jobs.recv(),
+            Err(_) => return,
+        };
+        let (sequence, records) = match job {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        if done.send((sequence, serialize(profile, records))).is_err() {
+            return;
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> WriterPool<W> {
+    ///
+    /// Starts `threads` workers serializing into `sink`
+    pub fn new(threads: usize, profile: &OutputProfile, mut sink: W) -> WriterPool<W> {
+        // bounded queues keep a slow sink from piling up records
+        let (jobs, job_queue) = sync_channel::<Job>(threads * 2);
+        let (done, results) = sync_channel(threads * 2);
+        let job_queue = Arc::new(Mutex::new(job_queue));
+        let profile = Arc::new(profile.clone());
+
+        let workers = (0..threads)
+            .map(|_| {
+                let job_queue = Arc::clone(&job_queue);
+                let done = done.clone();
+                let profile = Arc::clone(&profile);
+                std::thread::spawn(move || work(&profile, &job_queue, &done))
+            })
+            .collect();
+
+        let sequencer = std::thread::spawn(move || {
+            let mut pending: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+            let mut next: u64 = 0;
+            for (sequence, bytes) in results {
+                pending.insert(sequence, bytes?);
+                while let Some(bytes) = pending.remove(&next) {
+                    sink.write_all(&bytes)?;
+                    next += 1;
+                }
+            }
+
+            Ok(sink)
+        });
+
+        WriterPool {
+            chunk: Vec::with_capacity(CHUNK_RECORDS),
+            sequence: 0,
+            jobs: Some(jobs),
+            workers,
+            sequencer,
+        }
+    }
+
+    ///
+    /// Queues a record; it is written once its chunk is complete
+    pub fn write_record(&mut self, record: Vec<String>) -> Result<()> {
+        self.chunk.push(record);
+        if self.chunk.len() == CHUNK_RECORDS {
+            self.dispatch()?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Hands the collected records to the workers
+    fn dispatch(&mut self) -> Result<()> {
+        let records = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_RECORDS));
+        let sent = match self.jobs.as_ref() {
+            Some(jobs) => jobs.send((self.sequence, records)).is_ok(),
+            None => false,
+        };
+        self.sequence += 1;
+
+        if sent {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::BrokenPipe, "writer pool stopped"))
+        }
+    }
+
+    ///
+    /// Writes the remaining records, stops all threads and returns the sink
+    pub fn finish(mut self) -> Result<W> {
+        let dispatched = if self.chunk.is_empty() {
+            Ok(())
+        } else {
+            self.dispatch()
+        };
+        // workers stop once the queue is closed and drained
+        self.jobs = None;
+        for worker in self.workers {
+            if worker.join().is_err() {
+                return Err(Error::other("writer thread panicked"));
+            }
+        }
+
+        // an error of the sequencer explains a failed dispatch
+        match self.sequencer.join() {
+            Ok(result) => result.and_then(|sink| dispatched.map(|_| sink)),
+            Err(_) => Err(Error::other("sequencer thread panicked")),
+        }
+    }
+}
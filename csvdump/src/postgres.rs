@@ -0,0 +1,107 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Table definitions for loading exports into PostgreSQL
+//!
+
+use lib_oradb::definition::{ColumnDefinition, DataType};
+
+///
+/// Quotes an identifier, doubling embedded quotation marks
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+///
+/// Translates an Oracle column type into the closest PostgreSQL type
+pub fn pg_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::VarChar(length) if *length > 0 => format!("varchar({})", length),
+        DataType::Number(Some(precision), Some(0)) if *precision <= 4 => String::from("smallint"),
+        DataType::Number(Some(precision), Some(0)) if *precision <= 9 => String::from("integer"),
+        DataType::Number(Some(precision), Some(0)) if *precision <= 18 => String::from("bigint"),
+        DataType::Number(Some(precision), Some(scale))
+            if *scale > 0 && i64::from(*scale) <= i64::from(*precision) =>
+        {
+            format!("numeric({},{})", precision, scale)
+        }
+        DataType::Number(Some(precision), Some(0)) => format!("numeric({})", precision),
+        // negative scales and scales beyond the precision have no equivalent
+        DataType::Number(_, _) => String::from("numeric"),
+        DataType::BinaryFloat => String::from("real"),
+        DataType::Float(_) | DataType::BinaryDouble => String::from("double precision"),
+        DataType::Boolean => String::from("boolean"),
+        DataType::Date => String::from("date"),
+        DataType::DateTime => String::from("timestamp"),
+        DataType::BLob => String::from("bytea"),
+        DataType::VarChar(_) | DataType::CLob | DataType::Other(_) => String::from("text"),
+    }
+}
+
+///
+/// Builds a `CREATE TABLE` statement for the exported columns. `header`
+/// holds the exported column names in the order of `columns`; names
+/// beyond the table columns, like a row hash, become text columns.
+pub fn create_table(
+    table_name: &str,
+    header: &[String],
+    columns: &[ColumnDefinition],
+    primary_key: Option<&[String]>,
+) -> String {
+    let mut lines: Vec<String> = header
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| match columns.get(idx) {
+            Some(col) if !col.nullable() => {
+                format!("    {} {} NOT NULL", quote(name), pg_type(col.data_type()))
+            }
+            Some(col) => format!("    {} {}", quote(name), pg_type(col.data_type())),
+            None => format!("    {} text", quote(name)),
+        })
+        .collect();
+
+    // key columns are named as exported
+    let key_names: Option<Vec<String>> = primary_key.and_then(|pk| {
+        pk.iter()
+            .map(|key| {
+                columns
+                    .iter()
+                    .position(|col| col.column_name() == key)
+                    .map(|idx| quote(&header[idx]))
+            })
+            .collect()
+    });
+    if let Some(keys) = key_names {
+        lines.push(format!("    PRIMARY KEY ({})", keys.join(", ")));
+    }
+
+    format!(
+        "CREATE TABLE {} (\n{}\n);\n",
+        quote(table_name),
+        lines.join(",\n")
+    )
+}
@@ -0,0 +1,219 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Output profiles matching the load expectations of data warehouses
+//!
+
+use crate::output::{Compression, Encoding};
+use csv::{QuoteStyle, Terminator, WriterBuilder};
+use lib_oradb::definition::ValueFormat;
+
+///
+/// Collection of csv dialect and value format settings
+#[derive(Clone)]
+pub struct OutputProfile {
+    /// field delimiter
+    pub delimiter: u8,
+    /// when to put quotation marks around values
+    pub quote_style: QuoteStyle,
+    /// how to render values
+    pub value_format: ValueFormat,
+    /// compression applied to the output file
+    pub compression: Compression,
+    /// character encoding of the output file
+    pub encoding: Encoding,
+    /// whether a UTF-8 output file starts with a byte order mark
+    pub bom: bool,
+    /// record terminator
+    pub terminator: Terminator,
+}
+
+///
+/// Gets the line ending native to the platform
+fn platform_terminator() -> Terminator {
+    if cfg!(windows) {
+        Terminator::CRLF
+    } else {
+        Terminator::Any(b'\n')
+    }
+}
+
+impl Default for OutputProfile {
+    fn default() -> Self {
+        OutputProfile {
+            delimiter: b',',
+            quote_style: QuoteStyle::Necessary,
+            value_format: ValueFormat::new(),
+            compression: Compression::None,
+            encoding: Encoding::Utf8,
+            bom: false,
+            terminator: platform_terminator(),
+        }
+    }
+}
+
+impl OutputProfile {
+    ///
+    /// Gets the profile for a named target warehouse; all of them
+    /// expect gzip compressed files
+    ///
+    /// * `snowflake` loads with `FIELD_DELIMITER = ','`, `NULL_IF = ('\\N')`,
+    ///   `ESCAPE_UNENCLOSED_FIELD = '\\'` and `COMPRESSION = GZIP`
+    /// * `bigquery` loads with `bq load --source_format=CSV --null_marker='\N'`,
+    ///   so that empty strings stay apart from NULL
+    /// * `redshift` loads with `DELIMITER '|' NULL AS '\\N' ESCAPE GZIP`
+    ///   and `DATEFORMAT 'YYYY-MM-DD' TIMEFORMAT 'YYYY-MM-DD HH:MI:SS'`
+    ///
+    /// Snowflake and Redshift read unenclosed fields with backslash
+    /// escapes, so text reading `\N` is written as `\\N`. BigQuery has
+    /// no escapes and loads such text as NULL.
+    pub fn for_target(target: &str) -> Option<OutputProfile> {
+        let value_format = ValueFormat::new()
+            .date_format("%Y-%m-%d")
+            .datetime_format("%Y-%m-%d %H:%M:%S")
+            .null_token("\\N");
+
+        match target {
+            "snowflake" => Some(OutputProfile {
+                delimiter: b',',
+                quote_style: QuoteStyle::Never,
+                value_format: value_format.prefix_escapes(','),
+                compression: Compression::Gzip,
+                ..OutputProfile::default()
+            }),
+            "bigquery" => Some(OutputProfile {
+                delimiter: b',',
+                quote_style: QuoteStyle::Necessary,
+                value_format,
+                compression: Compression::Gzip,
+                ..OutputProfile::default()
+            }),
+            "redshift" => Some(OutputProfile {
+                delimiter: b'|',
+                quote_style: QuoteStyle::Never,
+                value_format: value_format.prefix_escapes('|'),
+                compression: Compression::Gzip,
+                ..OutputProfile::default()
+            }),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the name of an output file with the extension matching the
+    /// compression of the profile, e.g. `orders.csv.gz` for `orders.csv`
+    pub fn file_name(&self, file_name: &str) -> String {
+        match self.compression {
+            Compression::Gzip if !file_name.ends_with(".gz") => format!("{}.gz", file_name),
+            _ => String::from(file_name),
+        }
+    }
+
+    ///
+    /// Gets the profile for the text format of PostgreSQL's
+    /// `COPY ... FROM STDIN`: tab separated and unquoted, with `\N`
+    /// for NULL and backslash escapes in text values
+    pub fn pg_copy() -> OutputProfile {
+        OutputProfile {
+            delimiter: b'\t',
+            quote_style: QuoteStyle::Never,
+            value_format: ValueFormat::new()
+                .date_format("%Y-%m-%d")
+                .datetime_format("%Y-%m-%d %H:%M:%S")
+                .null_token("\\N")
+                .backslash_escapes(true),
+            terminator: Terminator::Any(b'\n'),
+            ..OutputProfile::default()
+        }
+    }
+
+    ///
+    /// Creates a csv writer builder with the profile's dialect
+    pub fn writer_builder(&self) -> WriterBuilder {
+        let mut builder = WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote_style(self.quote_style)
+            .terminator(self.terminator)
+            .double_quote(true);
+
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputProfile;
+    use lib_oradb::definition::ColumnValue;
+
+    ///
+    /// Writes NULL, an empty string, text reading `\N` and text with
+    /// the delimiter as one record of the profile
+    fn render(profile: &OutputProfile) -> String {
+        let row = vec![
+            None,
+            Some(ColumnValue::Varchar(String::new())),
+            Some(ColumnValue::Varchar(String::from("\\N"))),
+            Some(ColumnValue::Varchar(String::from("a,b|c"))),
+        ];
+        let mut writer = profile.writer_builder().from_writer(Vec::new());
+        writer
+            .write_record(profile.value_format.format_row(&row))
+            .unwrap();
+
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_snowflake() {
+        let profile = OutputProfile::for_target("snowflake").unwrap();
+        assert_eq!("\\N,,\\\\N,a\\,b|c\n", render(&profile));
+        assert_eq!("orders.csv.gz", profile.file_name("orders.csv"));
+        assert_eq!("orders.csv.gz", profile.file_name("orders.csv.gz"));
+    }
+
+    #[test]
+    fn test_bigquery() {
+        let profile = OutputProfile::for_target("bigquery").unwrap();
+        assert_eq!("\\N,,\\N,\"a,b|c\"\n", render(&profile));
+        assert_eq!("orders.csv.gz", profile.file_name("orders.csv"));
+    }
+
+    #[test]
+    fn test_redshift() {
+        let profile = OutputProfile::for_target("redshift").unwrap();
+        assert_eq!("\\N||\\\\N|a,b\\|c\n", render(&profile));
+        assert_eq!("orders.csv.gz", profile.file_name("orders.csv"));
+    }
+
+    #[test]
+    fn test_plain_csv() {
+        assert!(OutputProfile::for_target("teradata").is_none());
+        let profile = OutputProfile::default();
+        assert_eq!("orders.csv", profile.file_name("orders.csv"));
+    }
+}
@@ -0,0 +1,242 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Pruning of older exports from the output directory
+//!
+
+use crate::control::ControlFile;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// seconds per day
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// control files are small; larger files are not even read
+const MAX_CONTROL_FILE_SIZE: u64 = 1024 * 1024;
+
+///
+/// Decides which earlier exports of a job are kept; a file is
+/// kept only while it satisfies every configured limit
+pub struct RetentionPolicy {
+    /// file names belonging to the job
+    pattern: Regex,
+    /// number of most recent files to keep
+    keep_last: Option<usize>,
+    /// age in days up to which files are kept
+    keep_days: Option<u64>,
+}
+
+impl RetentionPolicy {
+    ///
+    /// Compiles the file name pattern of a policy; the pattern has
+    /// to match whole file names, not just a part of them
+    pub fn new(
+        pattern: &str,
+        keep_last: Option<usize>,
+        keep_days: Option<u64>,
+    ) -> Result<RetentionPolicy, String> {
+        let pattern = Regex::new(&format!("^(?:{})$", pattern))
+            .map_err(|e| format!("Invalid retention pattern {}: {}", pattern, e))?;
+
+        Ok(RetentionPolicy {
+            pattern,
+            keep_last,
+            keep_days,
+        })
+    }
+
+    ///
+    /// Lists files in `directory` matching the pattern that fall
+    /// outside the limits, newest first
+    pub fn expired(&self, directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files: Vec<(SystemTime, PathBuf)> = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && self.pattern.is_match(&entry.file_name().to_string_lossy()) {
+                files.push((metadata.modified()?, entry.path()));
+            }
+        }
+        files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+        // a cutoff before the earliest representable time keeps every file
+        let cutoff = self
+            .keep_days
+            .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+            .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)));
+
+        Ok(files
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, (modified, _))| {
+                self.keep_last.is_some_and(|n| *idx >= n) || cutoff.is_some_and(|c| *modified < c)
+            })
+            .map(|(_, (_, path))| path)
+            .collect())
+    }
+}
+
+///
+/// Finds control files in `directory` describing one of the given output files
+pub fn control_files_for(directory: &Path, outputs: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let outputs: Vec<PathBuf> = outputs
+        .iter()
+        .filter_map(|p| p.canonicalize().ok())
+        .collect();
+    let mut result = Vec::new();
+
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || metadata.len() > MAX_CONTROL_FILE_SIZE {
+            continue;
+        }
+        let path = entry.path();
+        // anything that does not parse is not a control file
+        if let Ok(control_file) = ControlFile::load(&path) {
+            let described = Path::new(control_file.output_file()).canonicalize();
+            if described.is_ok_and(|d| outputs.contains(&d)) {
+                result.push(path);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{control_files_for, RetentionPolicy, SECONDS_PER_DAY};
+    use crate::control::ControlFile;
+    use chrono::Utc;
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    ///
+    /// Creates an empty directory for one test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("csvdump-retention-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    ///
+    /// Creates a file last modified `age_days` days ago
+    fn touch(dir: &Path, name: &str, age_days: u64) -> PathBuf {
+        let path = dir.join(name);
+        let modified = SystemTime::now() - Duration::from_secs(age_days * SECONDS_PER_DAY);
+        File::create(&path).unwrap().set_modified(modified).unwrap();
+
+        path
+    }
+
+    fn file_names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_last() {
+        let dir = test_dir("keep-last");
+        touch(&dir, "orders_1.csv", 3);
+        touch(&dir, "orders_2.csv", 2);
+        touch(&dir, "orders_3.csv", 1);
+
+        let policy = RetentionPolicy::new(r"orders_\d+\.csv", Some(1), None).unwrap();
+        assert_eq!(
+            vec!["orders_2.csv", "orders_1.csv"],
+            file_names(&policy.expired(&dir).unwrap())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keep_days() {
+        let dir = test_dir("keep-days");
+        touch(&dir, "orders_1.csv", 10);
+        touch(&dir, "orders_2.csv", 1);
+
+        let policy = RetentionPolicy::new(r"orders_\d+\.csv", None, Some(5)).unwrap();
+        assert_eq!(
+            vec!["orders_1.csv"],
+            file_names(&policy.expired(&dir).unwrap())
+        );
+
+        // a limit reaching before the earliest time keeps every file
+        let policy = RetentionPolicy::new(r"orders_\d+\.csv", None, Some(u64::MAX)).unwrap();
+        assert!(policy.expired(&dir).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pattern_matches_whole_names() {
+        let dir = test_dir("pattern");
+        touch(&dir, "orders.csv", 2);
+        touch(&dir, "orders.state", 2);
+        touch(&dir, "orders_rejects.csv", 2);
+        touch(&dir, "customers.csv", 2);
+
+        let policy = RetentionPolicy::new(r"orders\.csv", None, Some(1)).unwrap();
+        assert_eq!(
+            vec!["orders.csv"],
+            file_names(&policy.expired(&dir).unwrap())
+        );
+        assert!(RetentionPolicy::new("orders(", None, None).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_control_files_for() {
+        let dir = test_dir("control");
+        let expired = touch(&dir, "orders_1.csv", 2);
+        let kept = touch(&dir, "orders_2.csv", 1);
+        for (output, control) in [(&expired, "orders_1.ctl"), (&kept, "orders_2.ctl")] {
+            ControlFile::new("ORDERS", output, 0, true, Utc::now(), None, String::new())
+                .unwrap()
+                .write(&dir.join(control))
+                .unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a control file").unwrap();
+
+        assert_eq!(
+            vec!["orders_1.ctl"],
+            file_names(&control_files_for(&dir, &[expired]).unwrap())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Row hashing for change detection
+//!
+
+use lib_oradb::definition::{ColumnValue, ValueFormat};
+use sha2::{Digest, Sha256, Sha512};
+
+///
+/// Digest algorithms available for row hashes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    ///
+    /// Parses an algorithm name as accepted on the command line
+    pub fn parse(name: &str) -> Option<HashAlgorithm> {
+        match name.to_lowercase().as_str() {
+            "sha256" | "sha-256" => Some(HashAlgorithm::Sha256),
+            "sha512" | "sha-512" => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Calculates deterministic hashes over all values of a row
+#[derive(Default)]
+pub struct RowHasher {
+    /// digest algorithm
+    algorithm: HashAlgorithm,
+    /// canonical text format, independent of output settings
+    value_format: ValueFormat,
+}
+
+impl RowHasher {
+    ///
+    /// Creates a new `RowHasher` using the given digest algorithm
+    pub fn new(algorithm: HashAlgorithm) -> RowHasher {
+        RowHasher {
+            algorithm,
+            ..RowHasher::default()
+        }
+    }
+
+    ///
+    /// Hashes a row and returns the hex encoded digest
+    pub fn hash(&self, row: &[Option<ColumnValue>]) -> String {
+        match self.algorithm {
+            HashAlgorithm::Sha256 => self.digest::<Sha256>(row),
+            HashAlgorithm::Sha512 => self.digest::<Sha512>(row),
+        }
+    }
+
+    ///
+    /// Feeds all values of a row into a digest and hex encodes the result
+    fn digest<D: Digest>(&self, row: &[Option<ColumnValue>]) -> String {
+        let mut hasher = D::new();
+
+        for value in row {
+            match value {
+                // keep NULL distinguishable from an empty string
+                None => hasher.update([0u8]),
+                Some(_) => {
+                    let text = self.value_format.format(value);
+                    hasher.update([1u8]);
+                    hasher.update((text.len() as u64).to_be_bytes());
+                    hasher.update(text.as_bytes());
+                }
+            }
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
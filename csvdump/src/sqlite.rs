@@ -0,0 +1,158 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Output into a SQLite database file
+//!
+
+use lib_oradb::definition::{ColumnDefinition, ColumnValue, DataType};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use std::path::Path;
+
+/// rows inserted per transaction
+const ROWS_PER_TRANSACTION: u64 = 10_000;
+
+///
+/// Quotes an identifier, doubling embedded quotation marks
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+///
+/// Gets the SQLite column type whose affinity fits an Oracle type
+fn sqlite_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Number(_, _) if data_type.is_integer() => "INTEGER",
+        DataType::Number(_, _) => "NUMERIC",
+        DataType::Float(_) | DataType::BinaryFloat | DataType::BinaryDouble => "REAL",
+        DataType::Boolean => "INTEGER",
+        DataType::BLob => "BLOB",
+        DataType::VarChar(_)
+        | DataType::CLob
+        | DataType::Date
+        | DataType::DateTime
+        | DataType::Other(_) => "TEXT",
+    }
+}
+
+///
+/// Writes rows into a table of a new SQLite database, committing
+/// every `ROWS_PER_TRANSACTION` rows
+pub struct SqliteWriter {
+    conn: Connection,
+    /// insert statement with one placeholder per column
+    insert_sql: String,
+    /// rows inserted in the open transaction
+    pending: u64,
+}
+
+impl SqliteWriter {
+    ///
+    /// Creates the database file and a table with the exported columns.
+    /// `header` holds the exported column names in the order of
+    /// `columns`; names beyond the table columns become text columns.
+    /// An existing file is replaced.
+    pub fn create(
+        path: &Path,
+        table_name: &str,
+        header: &[String],
+        columns: &[ColumnDefinition],
+    ) -> Result<SqliteWriter, String> {
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+
+        let column_list: Vec<String> = header
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let data_type = columns
+                    .get(idx)
+                    .map_or("TEXT", |c| sqlite_type(c.data_type()));
+                format!("{} {}", quote(name), data_type)
+            })
+            .collect();
+        conn.execute_batch(&format!(
+            "CREATE TABLE {} ({}); BEGIN;",
+            quote(table_name),
+            column_list.join(", ")
+        ))
+        .map_err(|e| e.to_string())?;
+
+        let placeholders = vec!["?"; header.len()].join(", ");
+        Ok(SqliteWriter {
+            conn,
+            insert_sql: format!(
+                "INSERT INTO {} VALUES ({})",
+                quote(table_name),
+                placeholders
+            ),
+            pending: 0,
+        })
+    }
+
+    ///
+    /// Inserts a row. Text is taken from the rendered `record`, so that
+    /// formatting and truncation apply, while NULL values, numbers,
+    /// booleans and binary values are stored with their native type.
+    pub fn write(
+        &mut self,
+        row: &[Option<ColumnValue>],
+        record: &[String],
+    ) -> rusqlite::Result<()> {
+        let values = record
+            .iter()
+            .enumerate()
+            .map(|(idx, text)| match row.get(idx) {
+                Some(None) => Value::Null,
+                Some(Some(ColumnValue::Number(v))) => Value::Integer(*v),
+                Some(Some(ColumnValue::Float(v))) => Value::Real(*v),
+                Some(Some(ColumnValue::Boolean(v))) => Value::Integer(i64::from(*v)),
+                Some(Some(ColumnValue::Binary(v))) => Value::Blob(v.clone()),
+                _ => Value::Text(text.clone()),
+            });
+        self.conn
+            .prepare_cached(&self.insert_sql)?
+            .execute(params_from_iter(values))?;
+
+        self.pending += 1;
+        if self.pending == ROWS_PER_TRANSACTION {
+            self.conn.execute_batch("COMMIT; BEGIN;")?;
+            self.pending = 0;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Commits the remaining rows and closes the database
+    pub fn finish(self) -> rusqlite::Result<()> {
+        self.conn.execute_batch("COMMIT;")?;
+        self.conn.close().map_err(|(_, e)| e)
+    }
+}
@@ -0,0 +1,262 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Persisted state of incremental exports
+//!
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use lib_oradb::definition::ColumnValue;
+use std::cmp::Ordering;
+use std::fs::read_to_string;
+use std::path::Path;
+
+///
+/// Highest exported value of the incremental column, tagged by kind
+/// so it can be bound with the correct type on the next run
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum StateValue {
+    Number(i64),
+    Decimal(String),
+    Float(f64),
+    Text(String),
+    Date(DateTime<Utc>),
+    DateTime(DateTime<Utc>),
+}
+
+///
+/// State of an incremental export between runs
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IncrementalState {
+    /// exported table
+    table: String,
+    /// column used to detect new rows
+    column: Option<String>,
+    /// completion of the last run
+    updated: String,
+    /// system change number recorded before the last run
+    last_scn: Option<u64>,
+    /// highest value exported so far
+    last_value: Option<StateValue>,
+}
+
+impl StateValue {
+    ///
+    /// Converts an exported column value; booleans and binary values cannot be tracked
+    fn from_column_value(value: &ColumnValue) -> Option<StateValue> {
+        match value {
+            ColumnValue::Number(n) => Some(StateValue::Number(*n)),
+            ColumnValue::Decimal(d) => Some(StateValue::Decimal(d.clone())),
+            ColumnValue::Float(f) => Some(StateValue::Float(*f)),
+            ColumnValue::Varchar(s) => Some(StateValue::Text(s.clone())),
+            ColumnValue::Date(d) => Some(StateValue::Date(*d)),
+            ColumnValue::DateTime(d) => Some(StateValue::DateTime(*d)),
+            ColumnValue::Boolean(_) | ColumnValue::Binary(_) => None,
+        }
+    }
+
+    ///
+    /// Converts into a column value to bind as lower bound
+    pub fn to_column_value(&self) -> ColumnValue {
+        match self {
+            StateValue::Number(n) => ColumnValue::Number(*n),
+            StateValue::Decimal(d) => ColumnValue::Decimal(d.clone()),
+            StateValue::Float(f) => ColumnValue::Float(*f),
+            StateValue::Text(s) => ColumnValue::Varchar(s.clone()),
+            StateValue::Date(d) => ColumnValue::Date(*d),
+            StateValue::DateTime(d) => ColumnValue::DateTime(*d),
+        }
+    }
+
+    ///
+    /// Compares two values of the same kind
+    fn compare(&self, other: &StateValue) -> Option<Ordering> {
+        match (self, other) {
+            (StateValue::Number(a), StateValue::Number(b)) => Some(a.cmp(b)),
+            (StateValue::Decimal(a), StateValue::Decimal(b)) => {
+                a.parse::<f64>().ok()?.partial_cmp(&b.parse::<f64>().ok()?)
+            }
+            (StateValue::Float(a), StateValue::Float(b)) => a.partial_cmp(b),
+            (StateValue::Text(a), StateValue::Text(b)) => Some(a.cmp(b)),
+            (StateValue::Date(a), StateValue::Date(b))
+            | (StateValue::DateTime(a), StateValue::DateTime(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl IncrementalState {
+    ///
+    /// Creates empty state for a table and optional column
+    pub fn new(table: &str, column: Option<&str>) -> IncrementalState {
+        IncrementalState {
+            table: String::from(table),
+            column: column.map(String::from),
+            updated: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            last_scn: None,
+            last_value: None,
+        }
+    }
+
+    ///
+    /// Loads state file; a missing file yields `None`
+    pub fn load(filename: &Path) -> Result<Option<IncrementalState>, Box<dyn std::error::Error>> {
+        if !filename.exists() {
+            return Ok(None);
+        }
+
+        let contents = read_to_string(filename)?;
+
+        Ok(Some(toml::from_str(&contents)?))
+    }
+
+    ///
+    /// Checks whether state was recorded for the given table and column
+    pub fn matches(&self, table: &str, column: Option<&str>) -> bool {
+        self.table == table && self.column.as_deref() == column
+    }
+
+    ///
+    /// Gets the system change number recorded before the last run
+    pub fn last_scn(&self) -> Option<u64> {
+        self.last_scn
+    }
+
+    ///
+    /// Records the system change number the next run continues from
+    pub fn set_scn(&mut self, scn: u64) {
+        self.last_scn = Some(scn);
+    }
+
+    ///
+    /// Gets the highest value exported so far
+    pub fn last_value(&self) -> Option<&StateValue> {
+        self.last_value.as_ref()
+    }
+
+    ///
+    /// Records a newly exported value if it exceeds the current maximum
+    pub fn observe(&mut self, value: &Option<ColumnValue>) {
+        let candidate = match value.as_ref().and_then(StateValue::from_column_value) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let is_greater = match &self.last_value {
+            Some(current) => candidate.compare(current) == Some(Ordering::Greater),
+            None => true,
+        };
+        if is_greater {
+            self.last_value = Some(candidate);
+        }
+    }
+
+    ///
+    /// Writes state file as toml
+    pub fn write(&mut self, filename: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.updated = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        std::fs::write(filename, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IncrementalState, StateValue};
+    use chrono::{TimeZone, Utc};
+    use lib_oradb::definition::ColumnValue;
+
+    #[test]
+    fn test_observe_keeps_maximum() {
+        let mut state = IncrementalState::new("ORDERS", Some("ID"));
+        assert_eq!(None, state.last_value());
+
+        state.observe(&Some(ColumnValue::Number(7)));
+        state.observe(&Some(ColumnValue::Number(3)));
+        state.observe(&None);
+        assert_eq!(Some(&StateValue::Number(7)), state.last_value());
+
+        // decimals compare by value, not as text
+        let mut state = IncrementalState::new("ORDERS", Some("AMOUNT"));
+        state.observe(&Some(ColumnValue::Decimal(String::from("9.5"))));
+        state.observe(&Some(ColumnValue::Decimal(String::from("10.25"))));
+        assert_eq!(
+            Some(&StateValue::Decimal(String::from("10.25"))),
+            state.last_value()
+        );
+    }
+
+    #[test]
+    fn test_untracked_values_are_ignored() {
+        let mut state = IncrementalState::new("ORDERS", Some("FLAG"));
+        state.observe(&Some(ColumnValue::Boolean(true)));
+        state.observe(&Some(ColumnValue::Binary(vec![1, 2])));
+        assert_eq!(None, state.last_value());
+
+        // values of another kind do not replace the current maximum
+        state.observe(&Some(ColumnValue::Number(1)));
+        state.observe(&Some(ColumnValue::Varchar(String::from("Z"))));
+        assert_eq!(Some(&StateValue::Number(1)), state.last_value());
+    }
+
+    #[test]
+    fn test_matches() {
+        let state = IncrementalState::new("ORDERS", Some("ID"));
+        assert!(state.matches("ORDERS", Some("ID")));
+        assert!(!state.matches("ORDERS", None));
+        assert!(!state.matches("CUSTOMERS", Some("ID")));
+        assert!(IncrementalState::new("ORDERS", None).matches("ORDERS", None));
+    }
+
+    #[test]
+    fn test_write_and_load() {
+        let path = std::env::temp_dir().join(format!("csvdump-state-{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(IncrementalState::load(&path).unwrap().is_none());
+
+        let changed = Utc.ymd(2023, 4, 1).and_hms(12, 30, 0);
+        let mut state = IncrementalState::new("ORDERS", Some("CHANGED"));
+        state.set_scn(4711);
+        state.observe(&Some(ColumnValue::DateTime(changed)));
+        state.write(&path).unwrap();
+
+        let loaded = IncrementalState::load(&path).unwrap().unwrap();
+        assert!(loaded.matches("ORDERS", Some("CHANGED")));
+        assert_eq!(Some(4711), loaded.last_scn());
+        assert_eq!(Some(&StateValue::DateTime(changed)), loaded.last_value());
+        assert_eq!(
+            ColumnValue::DateTime(changed),
+            loaded.last_value().unwrap().to_column_value()
+        );
+
+        std::fs::write(&path, "table = ").unwrap();
+        assert!(IncrementalState::load(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Per-column statistics gathered while scanning a table
+//!
+
+use lib_oradb::definition::{ColumnDefinition, ColumnValue, ValueFormat};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
+
+/// hashes kept for estimating distinct values; counts up to
+/// this many are exact
+const SKETCH_SIZE: usize = 1024;
+
+///
+/// Estimates the number of distinct values from the smallest
+/// hashes seen (k minimum values)
+#[derive(Default)]
+struct DistinctSketch {
+    smallest: BTreeSet<u64>,
+}
+
+impl DistinctSketch {
+    fn add(&mut self, value: &str) {
+        // the default hasher uses fixed keys, so estimates are repeatable
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if self.smallest.len() < SKETCH_SIZE {
+            self.smallest.insert(hash);
+        } else if hash < *self.smallest.iter().next_back().unwrap() && self.smallest.insert(hash) {
+            self.smallest.pop_last();
+        }
+    }
+
+    fn estimate(&self) -> u64 {
+        match self.smallest.iter().next_back() {
+            Some(largest) if self.smallest.len() == SKETCH_SIZE => {
+                let fraction = (*largest as f64 + 1.0) / (u64::MAX as f64 + 1.0);
+                ((SKETCH_SIZE - 1) as f64 / fraction).round() as u64
+            }
+            _ => self.smallest.len() as u64,
+        }
+    }
+}
+
+///
+/// Orders two values of a column; binary values have no order
+fn compare(a: &ColumnValue, b: &ColumnValue) -> Option<Ordering> {
+    match (a, b) {
+        (ColumnValue::Varchar(a), ColumnValue::Varchar(b)) => Some(a.cmp(b)),
+        (ColumnValue::Float(a), ColumnValue::Float(b)) => a.partial_cmp(b),
+        (ColumnValue::Number(a), ColumnValue::Number(b)) => Some(a.cmp(b)),
+        (ColumnValue::Decimal(a), ColumnValue::Decimal(b)) => {
+            a.parse::<f64>().ok()?.partial_cmp(&b.parse::<f64>().ok()?)
+        }
+        (ColumnValue::Boolean(a), ColumnValue::Boolean(b)) => Some(a.cmp(b)),
+        (ColumnValue::Date(a), ColumnValue::Date(b)) => Some(a.cmp(b)),
+        (ColumnValue::DateTime(a), ColumnValue::DateTime(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+///
+/// Statistics of one column, updated row by row
+struct ColumnStats {
+    column: String,
+    data_type: String,
+    nulls: u64,
+    min: Option<ColumnValue>,
+    max: Option<ColumnValue>,
+    /// characters of all formatted values, bytes for binary values
+    total_length: u64,
+    distinct: DistinctSketch,
+}
+
+///
+/// Statistics of one column as reported
+#[derive(Serialize)]
+pub struct ColumnReport {
+    column: String,
+    data_type: String,
+    rows: u64,
+    nulls: u64,
+    distinct_estimate: u64,
+    min: Option<String>,
+    max: Option<String>,
+    avg_length: Option<f64>,
+}
+
+///
+/// Gathers statistics of all columns of a table
+pub struct TableProfiler {
+    columns: Vec<ColumnStats>,
+    rows: u64,
+    format: ValueFormat,
+}
+
+impl TableProfiler {
+    pub fn new(columns: &[ColumnDefinition]) -> TableProfiler {
+        TableProfiler {
+            columns: columns
+                .iter()
+                .map(|c| ColumnStats {
+                    column: String::from(c.column_name()),
+                    data_type: c.data_type().to_string(),
+                    nulls: 0,
+                    min: None,
+                    max: None,
+                    total_length: 0,
+                    distinct: DistinctSketch::default(),
+                })
+                .collect(),
+            rows: 0,
+            format: ValueFormat::new(),
+        }
+    }
+
+    ///
+    /// Adds a row with values in column order
+    pub fn add(&mut self, row: &[Option<ColumnValue>]) {
+        self.rows += 1;
+        for (stats, value) in self.columns.iter_mut().zip(row.iter()) {
+            let v = match value {
+                Some(v) => v,
+                None => {
+                    stats.nulls += 1;
+                    continue;
+                }
+            };
+            let formatted = self.format.format(value);
+            stats.total_length += match v {
+                ColumnValue::Binary(b) => b.len() as u64,
+                _ => formatted.chars().count() as u64,
+            };
+            stats.distinct.add(&formatted);
+            if stats
+                .min
+                .as_ref()
+                .is_none_or(|m| compare(v, m) == Some(Ordering::Less))
+            {
+                stats.min = Some(v.clone());
+            }
+            if stats
+                .max
+                .as_ref()
+                .is_none_or(|m| compare(v, m) == Some(Ordering::Greater))
+            {
+                stats.max = Some(v.clone());
+            }
+        }
+    }
+
+    ///
+    /// Gets the statistics of all columns
+    pub fn report(&self) -> Vec<ColumnReport> {
+        self.columns
+            .iter()
+            .map(|stats| {
+                let values = self.rows - stats.nulls;
+                // binary values are only counted
+                let ordered = |v: &Option<ColumnValue>| match v {
+                    Some(ColumnValue::Binary(_)) | None => None,
+                    v => Some(self.format.format(v)),
+                };
+                ColumnReport {
+                    column: stats.column.clone(),
+                    data_type: stats.data_type.clone(),
+                    rows: self.rows,
+                    nulls: stats.nulls,
+                    distinct_estimate: stats.distinct.estimate(),
+                    min: ordered(&stats.min),
+                    max: ordered(&stats.max),
+                    avg_length: if values > 0 {
+                        Some(stats.total_length as f64 / values as f64)
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,101 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Truncation of oversized text values
+//!
+
+use lib_oradb::definition::{ColumnDefinition, DataType};
+
+/// marker ending a truncated value if requested
+const ELLIPSIS: &str = "...";
+
+///
+/// Cuts text fields down to a maximum length in bytes of UTF-8 text,
+/// e.g. for loaders rejecting fields over 32k
+pub struct Truncator {
+    /// maximum length of a field
+    max_length: usize,
+    /// whether truncated values end with an ellipsis
+    ellipsis: bool,
+    /// positions and names of the text columns
+    columns: Vec<(usize, String)>,
+}
+
+///
+/// Shortens text to at most `length` bytes without splitting a character
+fn cut(text: &mut String, length: usize) {
+    let mut end = length;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+impl Truncator {
+    ///
+    /// Creates a `Truncator` for the text columns among the exported columns
+    pub fn new(max_length: usize, ellipsis: bool, columns: &[ColumnDefinition]) -> Truncator {
+        Truncator {
+            max_length,
+            ellipsis,
+            columns: columns
+                .iter()
+                .enumerate()
+                .filter(|(_, col)| {
+                    matches!(
+                        col.data_type(),
+                        DataType::VarChar(_) | DataType::CLob | DataType::Other(_)
+                    )
+                })
+                .map(|(idx, col)| (idx, String::from(col.column_name())))
+                .collect(),
+        }
+    }
+
+    ///
+    /// Truncates the oversized text fields of a formatted record;
+    /// returns the names and original lengths of the truncated columns
+    pub fn apply(&self, record: &mut [String]) -> Vec<(&str, usize)> {
+        let mut truncated = Vec::new();
+        for (idx, name) in &self.columns {
+            let value = &mut record[*idx];
+            if value.len() <= self.max_length {
+                continue;
+            }
+
+            truncated.push((name.as_str(), value.len()));
+            if self.ellipsis && self.max_length >= ELLIPSIS.len() {
+                cut(value, self.max_length - ELLIPSIS.len());
+                value.push_str(ELLIPSIS);
+            } else {
+                cut(value, self.max_length);
+            }
+        }
+
+        truncated
+    }
+}
@@ -0,0 +1,142 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Machine-readable description of the command line
+//!
+
+use clap::{App, ArgSettings};
+use serde::Serialize;
+
+///
+/// Option, flag or positional argument of a command
+#[derive(Serialize)]
+pub struct ArgUsage<'a> {
+    name: &'a str,
+    short: Option<char>,
+    long: Option<&'a str>,
+    /// position of a positional argument, counted from 1
+    index: Option<u64>,
+    value_name: Option<&'a str>,
+    help: Option<&'a str>,
+    takes_value: bool,
+    required: bool,
+    multiple: bool,
+    global: bool,
+    possible_values: Vec<&'a str>,
+    default_value: Option<String>,
+}
+
+///
+/// Command with its arguments and subcommands
+#[derive(Serialize)]
+pub struct CommandUsage<'a> {
+    name: &'a str,
+    aliases: Vec<&'a str>,
+    version: Option<&'a str>,
+    about: Option<&'a str>,
+    args: Vec<ArgUsage<'a>>,
+    subcommands: Vec<CommandUsage<'a>>,
+}
+
+impl<'a> CommandUsage<'a> {
+    ///
+    /// Describes a clap application and all of its subcommands
+    pub fn new(app: &'a App) -> Self {
+        // clap 2 has no public accessors for defined arguments, only the
+        // parser fields it uses for generating help and completions
+        let parser = &app.p;
+        let mut args = Vec::new();
+        for flag in &parser.flags {
+            args.push(ArgUsage {
+                name: flag.b.name,
+                short: flag.s.short,
+                long: flag.s.long,
+                index: None,
+                value_name: None,
+                help: flag.b.help,
+                takes_value: false,
+                required: flag.b.is_set(ArgSettings::Required),
+                multiple: flag.b.is_set(ArgSettings::Multiple),
+                global: flag.b.is_set(ArgSettings::Global),
+                possible_values: Vec::new(),
+                default_value: None,
+            });
+        }
+        for opt in &parser.opts {
+            args.push(ArgUsage {
+                name: opt.b.name,
+                short: opt.s.short,
+                long: opt.s.long,
+                index: None,
+                value_name: opt
+                    .v
+                    .val_names
+                    .as_ref()
+                    .and_then(|names| names.values().next().copied()),
+                help: opt.b.help,
+                takes_value: true,
+                required: opt.b.is_set(ArgSettings::Required),
+                multiple: opt.b.is_set(ArgSettings::Multiple),
+                global: opt.b.is_set(ArgSettings::Global),
+                possible_values: opt.v.possible_vals.clone().unwrap_or_default(),
+                default_value: opt.v.default_val.map(|v| v.to_string_lossy().into_owned()),
+            });
+        }
+        for pos in parser.positionals.values() {
+            args.push(ArgUsage {
+                name: pos.b.name,
+                short: None,
+                long: None,
+                index: Some(pos.index),
+                value_name: None,
+                help: pos.b.help,
+                takes_value: true,
+                required: pos.b.is_set(ArgSettings::Required),
+                multiple: pos.b.is_set(ArgSettings::Multiple),
+                global: pos.b.is_set(ArgSettings::Global),
+                possible_values: pos.v.possible_vals.clone().unwrap_or_default(),
+                default_value: pos.v.default_val.map(|v| v.to_string_lossy().into_owned()),
+            });
+        }
+
+        CommandUsage {
+            name: &parser.meta.name,
+            aliases: parser
+                .meta
+                .aliases
+                .iter()
+                .flatten()
+                .filter(|(_, visible)| *visible)
+                .map(|(alias, _)| *alias)
+                .collect(),
+            version: parser.meta.version,
+            about: parser.meta.about,
+            args,
+            subcommands: parser.subcommands.iter().map(CommandUsage::new).collect(),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Validation of exported rows against per-column rules
+//!
+
+use lib_oradb::definition::{ColumnValue, ValueFormat};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+///
+/// Rule a column value has to satisfy
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ValidationRule {
+    /// value must not be NULL
+    NotNull,
+    /// value must match a regular expression
+    Regex { pattern: String },
+    /// value must be a number within inclusive bounds
+    Range {
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// value must not be longer than the given number of characters
+    MaxLength { length: usize },
+}
+
+///
+/// Rule prepared for checking values
+enum Check {
+    NotNull,
+    /// compiled pattern anchored at both ends and the pattern as configured
+    Regex(Regex, String),
+    Range(Option<f64>, Option<f64>),
+    MaxLength(usize),
+}
+
+///
+/// Checks exported rows against configured validation rules
+pub struct Validator {
+    /// checks for each exported column, by position
+    checks: Vec<(String, Vec<Check>)>,
+    /// format used to turn non-text values into text before checking
+    value_format: ValueFormat,
+}
+
+impl Check {
+    ///
+    /// Checks a single value; returns why it fails
+    fn apply(&self, text: Option<&str>) -> Option<String> {
+        match (self, text) {
+            (Check::NotNull, None) => Some(String::from("is NULL")),
+            // rules other than not_null accept NULL values
+            (_, None) | (Check::NotNull, _) => None,
+            (Check::Regex(re, pattern), Some(t)) if !re.is_match(t) => {
+                Some(format!("does not match {}", pattern))
+            }
+            (Check::Regex(_, _), _) => None,
+            (Check::Range(min, max), Some(t)) => match t.parse::<f64>() {
+                Err(_) => Some(String::from("is not numeric")),
+                Ok(n) if min.is_some_and(|m| n < m) => {
+                    Some(format!("{} is below {}", t, min.unwrap()))
+                }
+                Ok(n) if max.is_some_and(|m| n > m) => {
+                    Some(format!("{} is above {}", t, max.unwrap()))
+                }
+                Ok(_) => None,
+            },
+            (Check::MaxLength(length), Some(t)) if t.chars().count() > *length => {
+                Some(format!("is longer than {} characters", length))
+            }
+            (Check::MaxLength(_), _) => None,
+        }
+    }
+}
+
+impl Validator {
+    ///
+    /// Creates a validator for the given header; fails if a validated
+    /// column is not part of the export or a pattern is invalid
+    pub fn new(
+        rules: &BTreeMap<String, Vec<ValidationRule>>,
+        header: &[String],
+    ) -> Result<Validator, String> {
+        if let Some(unknown) = rules.keys().find(|name| !header.contains(name)) {
+            return Err(format!("Validated column {} is not exported", unknown));
+        }
+
+        let mut checks = Vec::new();
+        for name in header {
+            let mut column_checks = Vec::new();
+            for rule in rules.get(name).into_iter().flatten() {
+                column_checks.push(match rule {
+                    ValidationRule::NotNull => Check::NotNull,
+                    ValidationRule::Regex { pattern } => Check::Regex(
+                        // anchored so the whole value has to match
+                        Regex::new(&format!("^(?:{})$", pattern))
+                            .map_err(|e| format!("Invalid pattern for {}: {}", name, e))?,
+                        pattern.clone(),
+                    ),
+                    ValidationRule::Range { min, max } => Check::Range(*min, *max),
+                    ValidationRule::MaxLength { length } => Check::MaxLength(*length),
+                });
+            }
+            checks.push((name.clone(), column_checks));
+        }
+
+        Ok(Validator {
+            checks,
+            value_format: ValueFormat::new(),
+        })
+    }
+
+    ///
+    /// Checks a row; returns the reasons it is rejected, if any
+    pub fn check(&self, row: &[Option<ColumnValue>]) -> Option<String> {
+        let reasons: Vec<String> = self
+            .checks
+            .iter()
+            .zip(row.iter())
+            .filter(|((_, checks), _)| !checks.is_empty())
+            .flat_map(|((name, checks), value)| {
+                let text = value.as_ref().map(|_| self.value_format.format(value));
+                checks
+                    .iter()
+                    .filter_map(move |c| c.apply(text.as_deref()))
+                    .map(move |reason| format!("{} {}", name, reason))
+            })
+            .collect();
+
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(reasons.join("; "))
+        }
+    }
+}
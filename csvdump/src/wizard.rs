@@ -0,0 +1,167 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Prompts of the interactive setup wizard
+//!
+
+use std::io::{BufRead, Error, ErrorKind, Result, Write};
+
+///
+/// Asks questions on an output and reads the answers line by line
+pub struct Prompter<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Prompter { input, output }
+    }
+
+    ///
+    /// Reads one trimmed line; running out of input is an error
+    /// because no answer would ever be complete
+    fn read_answer(&mut self) -> Result<String> {
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "no more answers"));
+        }
+
+        Ok(String::from(line.trim()))
+    }
+
+    ///
+    /// Prints a message between questions
+    pub fn say(&mut self, message: &str) -> Result<()> {
+        writeln!(self.output, "{}", message)
+    }
+
+    ///
+    /// Asks until an answer is given; an empty answer takes the default
+    pub fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        loop {
+            match default {
+                Some(d) => write!(self.output, "{} [{}]: ", question, d)?,
+                None => write!(self.output, "{}: ", question)?,
+            }
+            let answer = self.read_answer()?;
+            match (answer.is_empty(), default) {
+                (false, _) => return Ok(answer),
+                (true, Some(d)) => return Ok(String::from(d)),
+                (true, None) => self.say("An answer is required.")?,
+            }
+        }
+    }
+
+    ///
+    /// Asks a question that may be left unanswered
+    pub fn ask_optional(&mut self, question: &str) -> Result<Option<String>> {
+        write!(self.output, "{} (optional): ", question)?;
+        let answer = self.read_answer()?;
+
+        Ok(Some(answer).filter(|a| !a.is_empty()))
+    }
+
+    ///
+    /// Asks a yes or no question
+    pub fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        loop {
+            write!(
+                self.output,
+                "{} [{}]: ",
+                question,
+                if default { "Y/n" } else { "y/N" }
+            )?;
+            match self.read_answer()?.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => self.say("Please answer yes or no.")?,
+            }
+        }
+    }
+
+    ///
+    /// Asks for a number between 1 and `count`, returning its index
+    pub fn choose(&mut self, question: &str, count: usize) -> Result<usize> {
+        loop {
+            write!(self.output, "{} (1-{}): ", question, count)?;
+            match self.read_answer()?.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= count => return Ok(n - 1),
+                _ => self.say(&format!("Please enter a number from 1 to {}.", count))?,
+            }
+        }
+    }
+
+    ///
+    /// Asks for a selection like `1,3-5` among `count` entries,
+    /// returning their indexes; an empty answer selects all
+    pub fn choose_many(&mut self, question: &str, count: usize) -> Result<Vec<usize>> {
+        loop {
+            write!(self.output, "{} (e.g. 1,3-5) [all]: ", question)?;
+            match parse_selection(&self.read_answer()?, count) {
+                Ok(selection) => return Ok(selection),
+                Err(e) => self.say(&e)?,
+            }
+        }
+    }
+}
+
+///
+/// Parses a selection of comma separated numbers and ranges counted
+/// from 1 into indexes in the order given, without duplicates
+pub fn parse_selection(text: &str, count: usize) -> std::result::Result<Vec<usize>, String> {
+    if text.is_empty() || text.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+
+    let number = |n: &str| match n.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= count => Ok(n),
+        _ => Err(format!("{} is not a number from 1 to {}.", n.trim(), count)),
+    };
+    let mut selection = Vec::new();
+    for part in text.split(',').filter(|p| !p.trim().is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (number(first)?, number(last)?),
+            None => (number(part)?, number(part)?),
+        };
+        if first > last {
+            return Err(format!("Range {} is reversed.", part.trim()));
+        }
+        for n in first..=last {
+            if !selection.contains(&(n - 1)) {
+                selection.push(n - 1);
+            }
+        }
+    }
+    if selection.is_empty() {
+        return Err(String::from("Select at least one entry."));
+    }
+
+    Ok(selection)
+}
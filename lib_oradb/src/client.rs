@@ -0,0 +1,124 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Thread-safe client for running several exports concurrently
+//!
+
+use crate::definition::{
+    initialize_session, SessionOptions, TableData, TableDefinition, TableSelectionBuilder,
+};
+use crate::Result;
+use oracle::pool::{Pool, PoolBuilder};
+use oracle::Connection;
+use std::sync::Arc;
+
+///
+/// Shares an Oracle session pool between threads; cloning is cheap
+/// and every clone hands out connections from the same pool
+#[derive(Clone)]
+pub struct Client {
+    pool: Arc<Pool>,
+    /// options applied to every connection taken from the pool
+    session: Arc<SessionOptions>,
+}
+
+impl Client {
+    ///
+    /// Creates a client with a session pool of up to `max_connections`
+    pub fn new<U, P, C>(
+        username: U,
+        password: P,
+        connect_string: C,
+        max_connections: u32,
+    ) -> Result<Client>
+    where
+        U: Into<String>,
+        P: Into<String>,
+        C: Into<String>,
+    {
+        let pool = PoolBuilder::new(username, password, connect_string)
+            .max_connections(max_connections)
+            .build()?;
+
+        Ok(Client {
+            pool: Arc::new(pool),
+            session: Arc::new(SessionOptions::default()),
+        })
+    }
+
+    ///
+    /// Sets options applied to each connection before it is handed out
+    pub fn session_options(mut self, options: SessionOptions) -> Self {
+        self.session = Arc::new(options);
+
+        self
+    }
+
+    ///
+    /// Takes a connection from the pool; it returns to the pool when dropped
+    pub fn connection(&self) -> Result<Connection> {
+        let conn = self.pool.get()?;
+        initialize_session(&conn, &self.session)?;
+
+        Ok(conn)
+    }
+
+    ///
+    /// Runs `f` with a pooled connection
+    pub fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T>,
+    {
+        let conn = self.connection()?;
+
+        f(&conn)
+    }
+
+    ///
+    /// Reads the table definition selected by `builder`
+    pub fn build(&self, builder: TableSelectionBuilder) -> Result<TableDefinition> {
+        self.with_connection(|conn| builder.build(conn))
+    }
+
+    ///
+    /// Reads the table definition and all data selected by `builder`
+    pub fn load(&self, builder: TableSelectionBuilder) -> Result<TableData> {
+        self.with_connection(|conn| builder.build(conn)?.load(conn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Client;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_client_is_shareable() {
+        assert_send_sync::<Client>();
+    }
+}
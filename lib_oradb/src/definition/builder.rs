@@ -28,49 +28,518 @@
 //! Builder for table selection
 //!
 
+use super::identifier::{normalize_identifier, validate_identifier, TableName};
 use super::meta::ColumnDataProvider;
-use super::{ColumnDefinition, TableDefinition};
+use super::{
+    BindValue, ColumnDefinition, ColumnValue, DataType, QueryOptions, TableDefinition,
+    TablePartition,
+};
 use crate::Error;
 use crate::Result;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeSet;
+
+///
+/// How to handle selected columns with data types
+/// lib_oradb does not support natively
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnknownTypePolicy {
+    /// abort with `Error::UnknownDataType`
+    #[default]
+    Fail,
+    /// leave the column out and log a warning
+    SkipColumn,
+    /// fetch the column as text via `TO_CHAR`
+    AsString,
+}
+
+///
+/// Order of the columns in the data query and its results
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColumnOrder {
+    /// order in which columns were added to the builder;
+    /// columns selected by `all_columns` follow in table order
+    #[default]
+    Input,
+    /// order of the columns in the table definition
+    Table,
+}
 
 ///
 /// Builds `TableDefinition` from a few simple inputs.
 ///
 pub struct TableSelectionBuilder {
     /// table name
-    table_name: String,
-    /// selection of columns to query
-    column_names: BTreeSet<String>,
+    table_name: TableName,
+    /// selection of columns to query in input order
+    column_names: Vec<String>,
+    /// whether all columns of the table are queried
+    all_columns: bool,
+    /// columns left out of the selection
+    excluded_names: BTreeSet<String>,
+    /// query options
+    options: QueryOptions,
+    /// handling of unsupported data types
+    unknown_types: UnknownTypePolicy,
+    /// owner to pick when the table name exists for several owners
+    preferred_owner: Option<String>,
+    /// owner the table is read from, qualifying an unqualified table name
+    owner: Option<String>,
+    /// order of the selected columns
+    column_order: ColumnOrder,
+}
+
+///
+/// Counts `:name` placeholders outside of string literals
+fn count_placeholders(condition: &str) -> usize {
+    let mut in_literal = false;
+    let mut count = 0;
+    let mut chars = condition.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => in_literal = !in_literal,
+            ':' if !in_literal && chars.peek().is_some_and(|n| n.is_alphanumeric()) => count += 1,
+            _ => (),
+        }
+    }
+
+    count
 }
 
 impl TableSelectionBuilder {
     ///
-    /// Constructs a new `TableSelectionBuilder`
+    /// Constructs a new `TableSelectionBuilder`; unquoted names are
+    /// case-insensitive, double-quoted names are matched verbatim
     pub fn new<S: AsRef<str>>(table_name: S) -> TableSelectionBuilder {
         TableSelectionBuilder {
-            table_name: String::from(table_name.as_ref()),
-            column_names: BTreeSet::new(),
+            table_name: TableName::parse(table_name.as_ref()),
+            column_names: Vec::new(),
+            all_columns: false,
+            excluded_names: BTreeSet::new(),
+            options: QueryOptions::default(),
+            unknown_types: UnknownTypePolicy::default(),
+            preferred_owner: None,
+            owner: None,
+            column_order: ColumnOrder::default(),
         }
     }
 
     /// Adds a column name
     pub fn with<S: AsRef<str>>(mut self, column_name: S) -> Self {
-        self.column_names.insert(String::from(column_name.as_ref()));
+        let column_name = normalize_identifier(column_name.as_ref());
+        if !self.column_names.contains(&column_name) {
+            self.column_names.push(column_name);
+        }
+
+        self
+    }
+
+    ///
+    /// Selects all columns of the table, except those passed to `without`
+    pub fn all_columns(mut self) -> Self {
+        self.all_columns = true;
+
+        self
+    }
+
+    ///
+    /// Leaves a column out of the selection
+    pub fn without<S: AsRef<str>>(mut self, column_name: S) -> Self {
+        self.excluded_names
+            .insert(normalize_identifier(column_name.as_ref()));
+
+        self
+    }
+
+    ///
+    /// Reads decimal NUMBER columns as f64 instead of exact decimal text
+    pub fn float_numbers(mut self, enabled: bool) -> Self {
+        self.options.float_numbers = enabled;
+
+        self
+    }
+
+    ///
+    /// Restricts the query to rows where the given column is greater than `value`
+    pub fn newer_than<S: AsRef<str>>(mut self, column_name: S, value: ColumnValue) -> Self {
+        self.options.newer_than = Some((normalize_identifier(column_name.as_ref()), value));
+
+        self
+    }
+
+    ///
+    /// Restricts the query to rows changed after the given system change number.
+    /// Uses `ORA_ROWSCN`, which is tracked per block unless the table was
+    /// created with `ROWDEPENDENCIES`, so unchanged rows may be included.
+    pub fn changed_since_scn(mut self, scn: u64) -> Self {
+        self.options.changed_since_scn = Some(scn);
+
+        self
+    }
+
+    ///
+    /// Restricts the query to rows where the given column is at least
+    /// `start` and less than `end`, e.g. to export one month at a time
+    pub fn range<S: AsRef<str>, V: Into<BindValue>>(
+        mut self,
+        column_name: S,
+        start: V,
+        end: V,
+    ) -> Self {
+        self.options.range = Some((
+            normalize_identifier(column_name.as_ref()),
+            start.into(),
+            end.into(),
+        ));
+
+        self
+    }
+
+    ///
+    /// Reads rows as they were at the given system change number (flashback query),
+    /// so that several tables exported with the same number are consistent
+    pub fn as_of_scn(mut self, scn: u64) -> Self {
+        self.options.as_of_scn = Some(scn);
+
+        self
+    }
+
+    ///
+    /// Sets the number of rows fetched per database round trip
+    pub fn fetch_size(mut self, rows: u32) -> Self {
+        self.options.fetch_size = Some(rows);
+
+        self
+    }
+
+    ///
+    /// Sets the number of rows pushed into the pipe at once in threaded
+    /// mode; larger batches mean less locking but more rows in flight
+    pub fn batch_size(mut self, rows: u32) -> Self {
+        self.options.batch_size = Some(rows.max(1));
+
+        self
+    }
+
+    ///
+    /// Pauses reading in threaded mode while the process uses more than
+    /// `bytes` of resident memory, until the writer has drained the queue;
+    /// the fetch size is not changed. Has no effect where `resident_bytes`
+    /// cannot read the memory use.
+    pub fn memory_limit(mut self, bytes: u64) -> Self {
+        self.options.memory_limit = Some(bytes);
+
+        self
+    }
+
+    ///
+    /// Restricts the query by an additional WHERE condition; values must
+    /// be passed with `bind` and are bound to the placeholders of the
+    /// condition in order of appearance, never concatenated into the SQL
+    pub fn filter<S: AsRef<str>>(mut self, condition: S) -> Self {
+        self.options.filter = Some(String::from(condition.as_ref()));
+
+        self
+    }
+
+    ///
+    /// Restricts the query to rows where the given column matches one
+    /// of the key values, e.g. read from a list of ids
+    pub fn keys<S, I, V>(mut self, column_name: S, values: I) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = V>,
+        V: Into<BindValue>,
+    {
+        self.options.keys = Some((
+            normalize_identifier(column_name.as_ref()),
+            values.into_iter().map(|v| v.into()).collect(),
+        ));
+
+        self
+    }
+
+    ///
+    /// Adds the value for the next placeholder of the filter
+    pub fn bind<V: Into<BindValue>>(mut self, value: V) -> Self {
+        self.options.binds.push(value.into());
+
+        self
+    }
+
+    ///
+    /// Reads only the rows of a single partition
+    pub fn partition<S: AsRef<str>>(mut self, partition_name: S) -> Self {
+        self.options.partition = Some(TablePartition::Partition(normalize_identifier(
+            partition_name.as_ref(),
+        )));
+
+        self
+    }
+
+    ///
+    /// Reads only the rows of a single subpartition
+    pub fn subpartition<S: AsRef<str>>(mut self, subpartition_name: S) -> Self {
+        self.options.partition = Some(TablePartition::Subpartition(normalize_identifier(
+            subpartition_name.as_ref(),
+        )));
+
+        self
+    }
+
+    ///
+    /// Removes duplicate rows with `SELECT DISTINCT`; Oracle rejects this
+    /// for selections containing LOB columns
+    pub fn distinct(mut self, enabled: bool) -> Self {
+        self.options.distinct = enabled;
+
+        self
+    }
+
+    ///
+    /// Stages the selected rows in a temporary table before they are
+    /// fetched, using a `WITH` clause with the `MATERIALIZE` hint; this
+    /// can be faster for expensive filters and needs no DDL privileges
+    pub fn materialize(mut self, enabled: bool) -> Self {
+        self.options.materialize = enabled;
+
+        self
+    }
+
+    ///
+    /// Adds an aggregate expression such as `SUM(AMOUNT)` selected as the
+    /// given column name; its data type is derived from the statement.
+    /// The expression becomes part of the SQL and must not contain user input.
+    pub fn with_aggregate<E: AsRef<str>, S: AsRef<str>>(
+        mut self,
+        expression: E,
+        column_name: S,
+    ) -> Self {
+        self.options.aggregates.push((
+            String::from(expression.as_ref()),
+            normalize_identifier(column_name.as_ref()),
+        ));
+
+        self
+    }
+
+    ///
+    /// Adds a computed column such as `SYSDATE` or `'PROD'` selected as the
+    /// given column name after the table columns; its data type is derived
+    /// from the statement. The expression becomes part of the SQL and must
+    /// not contain user input.
+    pub fn with_computed<E: AsRef<str>, S: AsRef<str>>(
+        mut self,
+        expression: E,
+        column_name: S,
+    ) -> Self {
+        self.options.computed.push((
+            String::from(expression.as_ref()),
+            normalize_identifier(column_name.as_ref()),
+        ));
+
+        self
+    }
+
+    ///
+    /// Adds an expression such as `TRUNC(AU_KAUFDAT,'MM')` or `NVL(STATUS,'N')`
+    /// selected as the given column name after the table columns; the database
+    /// casts its value to the given data type. The expression becomes part of
+    /// the SQL and must not contain user input.
+    pub fn with_expression<E: AsRef<str>, S: AsRef<str>>(
+        self,
+        expression: E,
+        column_name: S,
+        data_type: DataType,
+    ) -> Self {
+        let column_name = column_name.as_ref();
+
+        self.with_computed(expression, column_name)
+            .with_type(column_name, data_type)
+    }
+
+    ///
+    /// Groups rows by a column; all selected columns except aggregates
+    /// have to be grouped
+    pub fn group_by<S: AsRef<str>>(mut self, column_name: S) -> Self {
+        let column_name = normalize_identifier(column_name.as_ref());
+        if !self.options.group_by.contains(&column_name) {
+            self.options.group_by.push(column_name);
+        }
+
+        self
+    }
+
+    ///
+    /// Reads a column as another data type, e.g. a NUMBER as text; the
+    /// column is cast by the database and the definition reports the new type
+    pub fn with_type<S: AsRef<str>>(mut self, column_name: S, data_type: DataType) -> Self {
+        let column_name = normalize_identifier(column_name.as_ref());
+        self.options
+            .type_overrides
+            .retain(|(name, _)| *name != column_name);
+        self.options.type_overrides.push((column_name, data_type));
+
+        self
+    }
+
+    ///
+    /// Reads at most the given number of rows
+    pub fn limit(mut self, rows: u64) -> Self {
+        self.options.row_limit = Some(rows);
+
+        self
+    }
+
+    ///
+    /// Reads a random sample of about the given percentage of rows; the same
+    /// seed selects the same rows as long as the table does not change
+    pub fn sample(mut self, percent: f64, seed: Option<u32>) -> Self {
+        self.options.sample = Some((percent, seed));
+
+        self
+    }
+
+    ///
+    /// Sets how columns with unsupported data types are handled
+    pub fn unknown_types(mut self, policy: UnknownTypePolicy) -> Self {
+        self.unknown_types = policy;
+
+        self
+    }
+
+    ///
+    /// Sets the order of the selected columns
+    pub fn column_order(mut self, order: ColumnOrder) -> Self {
+        self.column_order = order;
+
+        self
+    }
+
+    ///
+    /// Sets the owner to use if the table name exists for several owners
+    pub fn prefer_owner<S: AsRef<str>>(mut self, owner: S) -> Self {
+        self.preferred_owner = Some(normalize_identifier(owner.as_ref()));
+
+        self
+    }
+
+    ///
+    /// Reads the table from the schema of the given owner in both the
+    /// metadata and data queries; same as qualifying the table name
+    pub fn owner<S: AsRef<str>>(mut self, owner: S) -> Self {
+        self.owner = Some(String::from(owner.as_ref()));
 
         self
     }
 
     ///
     /// Constructs a `TableDefinition` from given column and table data
-    pub fn build(self, conn: &dyn ColumnDataProvider) -> Result<TableDefinition> {
+    pub fn build(mut self, conn: &dyn ColumnDataProvider) -> Result<TableDefinition> {
+        if let Some(owner) = self.owner.take() {
+            self.table_name = self.table_name.with_owner(owner)?;
+        }
+        // names end up quoted in the data query
+        self.table_name.validate()?;
+        for column_name in self
+            .column_names
+            .iter()
+            .chain(&self.excluded_names)
+            .chain(self.options.group_by())
+            .chain(self.options.aggregates().iter().map(|(_, alias)| alias))
+            .chain(self.options.computed().iter().map(|(_, alias)| alias))
+            .chain(self.options.type_overrides().iter().map(|(name, _)| name))
+        {
+            validate_identifier(column_name)?;
+        }
+        if let Some((column_name, data_type)) = self
+            .options
+            .type_overrides()
+            .iter()
+            .find(|(_, data_type)| matches!(data_type, DataType::Other(_)))
+        {
+            // the type name would end up unchecked in the data query
+            return Err(Error::InvalidValue(format!(
+                "column {} cannot be cast to unsupported data type {}",
+                column_name, data_type
+            )));
+        }
+        if let Some((_, alias)) = self
+            .options
+            .aggregates()
+            .iter()
+            .find(|(_, alias)| self.column_names.contains(alias))
+        {
+            return Err(Error::InvalidValue(format!(
+                "aggregate column {} is also selected from the table",
+                alias
+            )));
+        }
+        if let Some((column_name, _)) = self.options.newer_than() {
+            validate_identifier(column_name)?;
+        }
+        if let Some((column_name, _, _)) = self.options.range() {
+            validate_identifier(column_name)?;
+        }
+        if let Some((column_name, values)) = self.options.keys() {
+            validate_identifier(column_name)?;
+            if values.is_empty() {
+                return Err(Error::InvalidValue(format!(
+                    "no key values given for {}",
+                    column_name
+                )));
+            }
+        }
+        if let Some(TablePartition::Partition(name) | TablePartition::Subpartition(name)) =
+            self.options.partition()
+        {
+            validate_identifier(name)?;
+        }
+        if self.options.partition().is_some() && self.table_name.link().is_some() {
+            // Oracle allows either a partition or a database link on a table
+            return Err(Error::InvalidValue(format!(
+                "partitions of remote table {} cannot be queried",
+                self.table_name
+            )));
+        }
+        if let Some((percent, _)) = self.options.sample() {
+            // Oracle accepts sample percentages in [0.000001, 100)
+            if !(0.000001..100.0).contains(&percent) {
+                return Err(Error::InvalidValue(format!(
+                    "sample percentage {} is outside of [0.000001, 100)",
+                    percent
+                )));
+            }
+        }
+        let placeholders = self.options.filter().map_or(0, count_placeholders);
+        if placeholders != self.options.binds().len() {
+            return Err(Error::InvalidValue(format!(
+                "filter has {} placeholders but {} bind values",
+                placeholders,
+                self.options.binds().len()
+            )));
+        }
+
         info!("Querying table column data.");
         // get the columns
-        let columns = conn.query_column_data(&self.table_name)?;
+        let columns = match conn.query_column_data(&self.table_name) {
+            Err(Error::AmbiguousTable(table, owners)) => match &self.preferred_owner {
+                Some(owner) if owners.contains(owner) => {
+                    info!("Resolving ambiguous table {} to owner {}.", table, owner);
+                    self.table_name.set_owner(owner.clone());
+                    conn.query_column_data(&self.table_name)?
+                }
+                _ => return Err(Error::AmbiguousTable(table, owners)),
+            },
+            result => result?,
+        };
 
         info!("Checking whether we have unknown columns.");
 
         if columns.is_empty() {
+            if !conn.table_exists(&self.table_name)? {
+                return Err(Error::TableNotFound(self.table_name.to_string()));
+            }
             warn!("Column query returned no data.");
         } else {
             debug!("Query returned {} columns.", columns.len());
@@ -79,8 +548,22 @@ impl TableSelectionBuilder {
         // check whether there are columns being queried that are not in that table?
         let known_columns: BTreeSet<&str> =
             columns.iter().map(|col| col.column_name.as_str()).collect();
-        let queried_names: BTreeSet<&str> =
-            self.column_names.iter().map(|col| col.as_str()).collect();
+        let mut queried_names: BTreeSet<&str> = self
+            .column_names
+            .iter()
+            .chain(&self.excluded_names)
+            .chain(self.options.group_by())
+            .map(|col| col.as_str())
+            .collect();
+        if let Some((column_name, _)) = self.options.newer_than() {
+            queried_names.insert(column_name);
+        }
+        if let Some((column_name, _, _)) = self.options.range() {
+            queried_names.insert(column_name);
+        }
+        if let Some((column_name, _)) = self.options.keys() {
+            queried_names.insert(column_name);
+        }
         let unknown_columns: BTreeSet<&str> =
             queried_names.difference(&known_columns).cloned().collect();
 
@@ -93,18 +576,328 @@ impl TableSelectionBuilder {
 
         info!("Filtering to queried columns.");
 
-        // filter to the columns we want
-        let filtered: BTreeMap<String, ColumnDefinition> = columns
+        // filter to the columns we want, which come in table order
+        let mut filtered: Vec<ColumnDefinition> = columns
             .into_iter()
-            .filter(|col| self.column_names.contains(&col.column_name))
-            .map(|col| (col.column_name.clone(), col))
+            .filter(|col| self.all_columns || self.column_names.contains(&col.column_name))
+            .filter(|col| !self.excluded_names.contains(&col.column_name))
+            .collect();
+        if self.column_order == ColumnOrder::Input {
+            // stable sort keeps unnamed columns in table order
+            filtered.sort_by_key(|col| {
+                self.column_names
+                    .iter()
+                    .position(|cn| *cn == col.column_name)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        // aggregated queries can only return grouped columns
+        let aggregated =
+            !self.options.aggregates().is_empty() || !self.options.group_by().is_empty();
+        if let Some(col) = filtered
+            .iter()
+            .find(|col| aggregated && !self.options.group_by().contains(&col.column_name))
+        {
+            return Err(Error::InvalidValue(format!(
+                "column {} is neither grouped nor aggregated",
+                col.column_name
+            )));
+        }
+        if !self.options.aggregates().is_empty() {
+            info!("Querying aggregate column data.");
+            filtered
+                .extend(conn.query_expression_data(&self.table_name, self.options.aggregates())?);
+        }
+        if !self.options.computed().is_empty() {
+            let names: BTreeSet<&str> = filtered
+                .iter()
+                .map(|col| col.column_name.as_str())
+                .collect();
+            let mut aliases: BTreeSet<&str> = BTreeSet::new();
+            if let Some((_, alias)) = self
+                .options
+                .computed()
+                .iter()
+                .find(|(_, alias)| names.contains(alias.as_str()) || !aliases.insert(alias))
+            {
+                return Err(Error::InvalidValue(format!(
+                    "computed column {} is already selected",
+                    alias
+                )));
+            }
+            info!("Querying computed column data.");
+            // described on their own, they need not be grouped like aggregates
+            filtered.extend(conn.query_expression_data(&self.table_name, self.options.computed())?);
+        }
+
+        // overridden columns are cast by the database
+        for (column_name, data_type) in self.options.type_overrides() {
+            match filtered
+                .iter_mut()
+                .find(|col| col.column_name == *column_name)
+            {
+                Some(col) => col.data_type = data_type.clone(),
+                None => {
+                    return Err(Error::InvalidValue(format!(
+                        "column {} has a type override but is not selected",
+                        column_name
+                    )))
+                }
+            }
+        }
+
+        // apply policy for unsupported data types
+        let unsupported: Vec<(String, String)> = filtered
+            .iter()
+            .filter_map(|col| match &col.data_type {
+                DataType::Other(type_name) => Some((col.column_name.clone(), type_name.clone())),
+                _ => None,
+            })
             .collect();
+        for (column_name, type_name) in unsupported {
+            match self.unknown_types {
+                UnknownTypePolicy::Fail => return Err(Error::UnknownDataType(type_name)),
+                UnknownTypePolicy::SkipColumn => {
+                    warn!(
+                        "Skipping column {} with unsupported data type {}.",
+                        column_name, type_name
+                    );
+                    filtered.retain(|col| col.column_name != column_name);
+                }
+                UnknownTypePolicy::AsString => warn!(
+                    "Reading column {} with unsupported data type {} as text.",
+                    column_name, type_name
+                ),
+            }
+        }
+
+        info!("Querying key constraints.");
+        let constraints = conn.query_constraints(&self.table_name)?;
 
         info!("Returning table definition.");
 
         Ok(TableDefinition {
             table_name: self.table_name,
             columns: filtered,
+            options: self.options,
+            constraints,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{count_placeholders, ColumnOrder, TableSelectionBuilder};
+    use crate::definition::meta::ColumnDataProvider;
+    use crate::definition::{ColumnDefinition, Constraint, ConstraintKind, DataType, TableName};
+    use crate::Result;
+
+    /// table with columns `C`, `A`, `B` in definition order
+    struct Columns;
+
+    impl ColumnDataProvider for Columns {
+        fn query_column_data(&self, _table_name: &TableName) -> Result<Vec<ColumnDefinition>> {
+            Ok(["C", "A", "B"]
+                .iter()
+                .map(|name| ColumnDefinition {
+                    column_name: String::from(*name),
+                    nullable: true,
+                    data_type: DataType::VarChar(10),
+                })
+                .collect())
+        }
+
+        fn table_exists(&self, _table_name: &TableName) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn query_constraints(&self, _table_name: &TableName) -> Result<Vec<Constraint>> {
+            Ok(vec![Constraint {
+                name: String::from("T_PK"),
+                kind: ConstraintKind::PrimaryKey,
+                columns: vec![String::from("A")],
+            }])
+        }
+
+        fn query_expression_data(
+            &self,
+            _table_name: &TableName,
+            expressions: &[(String, String)],
+        ) -> Result<Vec<ColumnDefinition>> {
+            Ok(expressions
+                .iter()
+                .map(|(_, alias)| ColumnDefinition {
+                    column_name: alias.clone(),
+                    nullable: true,
+                    data_type: DataType::Number(None, None),
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_column_order() {
+        let builder = || TableSelectionBuilder::new("T").with("b").with("c");
+
+        let input = builder().build(&Columns).unwrap();
+        assert_eq!(vec!["B", "C"], input.header());
+
+        let table = builder()
+            .column_order(ColumnOrder::Table)
+            .build(&Columns)
+            .unwrap();
+        assert_eq!(vec!["C", "B"], table.header());
+
+        let all = builder().all_columns().build(&Columns).unwrap();
+        assert_eq!(vec!["B", "C", "A"], all.header());
+        assert_eq!(Some(&[String::from("A")][..]), all.primary_key());
+        assert!(all.unique_keys().is_empty());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let grouped = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_aggregate("SUM(B)", "total")
+            .group_by("a")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!(vec!["A", "TOTAL"], grouped.header());
+
+        let ungrouped = TableSelectionBuilder::new("T")
+            .with("a")
+            .with("c")
+            .with_aggregate("COUNT(*)", "n")
+            .group_by("a")
+            .build(&Columns);
+        assert!(ungrouped.is_err());
+
+        let unknown = TableSelectionBuilder::new("T")
+            .with_aggregate("COUNT(*)", "n")
+            .group_by("x")
+            .build(&Columns);
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_computed() {
+        let computed = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_computed("SYSDATE", "export_date")
+            .with_computed("'PROD'", "source")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!(vec!["A", "EXPORT_DATE", "SOURCE"], computed.header());
+
+        let selected = TableSelectionBuilder::new("T")
+            .all_columns()
+            .with_computed("'PROD'", "b")
+            .build(&Columns);
+        assert!(selected.is_err());
+
+        let duplicate = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_computed("'PROD'", "source")
+            .with_computed("'TEST'", "source")
+            .build(&Columns);
+        assert!(duplicate.is_err());
+    }
+
+    #[test]
+    fn test_expression() {
+        let monthly = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_expression("TRUNC(C,'MM')", "month", DataType::Date)
+            .build(&Columns)
+            .unwrap();
+        assert_eq!(vec!["A", "MONTH"], monthly.header());
+        assert_eq!(&DataType::Date, monthly.columns()[1].data_type());
+    }
+
+    #[test]
+    fn test_owner() {
+        let owned = TableSelectionBuilder::new("t")
+            .owner("scott")
+            .with("a")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!("SCOTT.T", owned.table_name.to_string());
+
+        let qualified = TableSelectionBuilder::new("scott.t")
+            .owner("SCOTT")
+            .with("a")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!("SCOTT.T", qualified.table_name.to_string());
+
+        let other = TableSelectionBuilder::new("hr.t")
+            .owner("scott")
+            .with("a")
+            .build(&Columns);
+        assert!(other.is_err());
+
+        // a dot inside quotes is part of the table name
+        let dotted = TableSelectionBuilder::new("\"My.Table\"")
+            .owner("scott")
+            .with("a")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!(Some("SCOTT"), dotted.table_name.owner());
+        assert_eq!("My.Table", dotted.table_name.table());
+    }
+
+    #[test]
+    fn test_db_link() {
+        let remote = TableSelectionBuilder::new("t@remote.example.com")
+            .owner("scott")
+            .with("a")
+            .build(&Columns)
+            .unwrap();
+        assert_eq!("SCOTT.T@REMOTE.EXAMPLE.COM", remote.table_name.to_string());
+
+        let partitioned = TableSelectionBuilder::new("t@remote")
+            .with("a")
+            .partition("p1")
+            .build(&Columns);
+        assert!(partitioned.is_err());
+    }
+
+    #[test]
+    fn test_type_override() {
+        let overridden = TableSelectionBuilder::new("T")
+            .with("a")
+            .with("b")
+            .with_type("b", DataType::Number(None, None))
+            .build(&Columns)
+            .unwrap();
+        let types: Vec<&DataType> = overridden.columns().iter().map(|c| c.data_type()).collect();
+        assert_eq!(
+            vec![&DataType::VarChar(10), &DataType::Number(None, None)],
+            types
+        );
+
+        let unselected = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_type("b", DataType::Date)
+            .build(&Columns);
+        assert!(unselected.is_err());
+
+        let unsupported = TableSelectionBuilder::new("T")
+            .with("a")
+            .with_type("a", DataType::Other(String::from("XMLTYPE")))
+            .build(&Columns);
+        assert!(unsupported.is_err());
+    }
+
+    #[test]
+    fn test_count_placeholders() {
+        assert_eq!(0, count_placeholders("STATUS = 'A'"));
+        assert_eq!(
+            2,
+            count_placeholders("AU_KAUFDAT > :1 AND STATUS = :status")
+        );
+        // colons inside literals are not placeholders
+        assert_eq!(1, count_placeholders("NOTE <> 'x:y' AND ID = :1"));
+    }
+}
@@ -0,0 +1,446 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Reading exported data back into rows of a table definition
+//!
+
+use super::{
+    ColumnDefinition, ColumnValue, DataRow, DataType, TableData, TableDefinition, ValueFormat,
+    DEFAULT_DATETIME_FORMAT, DEFAULT_DATE_FORMAT,
+};
+use crate::{Error, Result};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+///
+/// Reads a single value of a column, accepting the representation
+/// `ColumnValue` is serialized to; null yields `None`
+pub struct ValueSeed<'a> {
+    data_type: &'a DataType,
+}
+
+impl<'a> ValueSeed<'a> {
+    pub fn new(data_type: &'a DataType) -> ValueSeed<'a> {
+        ValueSeed { data_type }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+    type Value = Option<ColumnValue>;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueSeed<'a> {
+    type Value = Option<ColumnValue>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a {} value or null", self.data_type)
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = ValueVisitor {
+            data_type: self.data_type,
+        };
+        match self.data_type {
+            DataType::BLob => deserializer.deserialize_byte_buf(visitor).map(Some),
+            _ => deserializer.deserialize_any(visitor).map(Some),
+        }
+    }
+}
+
+///
+/// Converts a present value according to the column data type
+struct ValueVisitor<'a> {
+    data_type: &'a DataType,
+}
+
+impl<'a> ValueVisitor<'a> {
+    ///
+    /// Parses a date or timestamp in the default serialization format
+    fn parse_date<E: de::Error>(&self, text: &str) -> Option<std::result::Result<ColumnValue, E>> {
+        let invalid = || E::invalid_value(de::Unexpected::Str(text), self);
+        match self.data_type {
+            DataType::Date => Some(
+                NaiveDate::parse_from_str(text, DEFAULT_DATE_FORMAT)
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .map(|midnight| ColumnValue::Date(Utc.from_utc_datetime(&midnight)))
+                    .ok_or_else(invalid),
+            ),
+            DataType::DateTime => Some(
+                NaiveDateTime::parse_from_str(text, DEFAULT_DATETIME_FORMAT)
+                    .map(|stamp| ColumnValue::DateTime(Utc.from_utc_datetime(&stamp)))
+                    .map_err(|_| invalid()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueVisitor<'a> {
+    type Value = ColumnValue;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a {} value", self.data_type)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<ColumnValue, E> {
+        match self.data_type {
+            DataType::Boolean => Ok(ColumnValue::Boolean(v)),
+            _ => Err(E::invalid_type(de::Unexpected::Bool(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<ColumnValue, E> {
+        match self.data_type {
+            DataType::Number(_, _) if self.data_type.is_integer() => Ok(ColumnValue::Number(v)),
+            DataType::Number(_, _) => Ok(ColumnValue::Decimal(v.to_string())),
+            DataType::Float(_) | DataType::BinaryFloat | DataType::BinaryDouble => {
+                Ok(ColumnValue::Float(v as f64))
+            }
+            _ => Err(E::invalid_type(de::Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<ColumnValue, E> {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<ColumnValue, E> {
+        match self.data_type {
+            // decimal NUMBER columns read with float_numbers
+            DataType::Number(_, _) if !self.data_type.is_integer() => Ok(ColumnValue::Float(v)),
+            DataType::Float(_) | DataType::BinaryFloat | DataType::BinaryDouble => {
+                Ok(ColumnValue::Float(v))
+            }
+            _ => Err(E::invalid_type(de::Unexpected::Float(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<ColumnValue, E> {
+        if let Some(date) = self.parse_date(v) {
+            return date;
+        }
+        match self.data_type {
+            DataType::VarChar(_) | DataType::CLob | DataType::Other(_) => {
+                Ok(ColumnValue::Varchar(String::from(v)))
+            }
+            DataType::Number(_, _) if !self.data_type.is_integer() => match v.parse::<f64>() {
+                Ok(_) => Ok(ColumnValue::Decimal(String::from(v))),
+                Err(_) => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            },
+            _ => Err(E::invalid_type(de::Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<ColumnValue, E> {
+        self.visit_byte_buf(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<ColumnValue, E> {
+        match self.data_type {
+            DataType::BLob => Ok(ColumnValue::Binary(v)),
+            _ => Err(E::invalid_type(de::Unexpected::Bytes(&v), &self)),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<ColumnValue, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        // formats without a byte type write bytes as a list of numbers
+        if !matches!(self.data_type, DataType::BLob) {
+            return Err(de::Error::invalid_type(de::Unexpected::Seq, &self));
+        }
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(ColumnValue::Binary(bytes))
+    }
+}
+
+///
+/// Reads rows of a table definition, either from a serialized `DataRow`
+/// or from text fields rendered by a `ValueFormat`
+#[derive(Clone)]
+pub struct RowSeed {
+    column_defs: Arc<Vec<ColumnDefinition>>,
+}
+
+impl RowSeed {
+    pub fn new(table_def: &TableDefinition) -> RowSeed {
+        RowSeed {
+            column_defs: Arc::new(table_def.columns.clone()),
+        }
+    }
+
+    ///
+    /// Parses the text fields of an exported record, e.g. a CSV line
+    pub fn parse<S: AsRef<str>>(&self, fields: &[S], format: &ValueFormat) -> Result<DataRow> {
+        if fields.len() != self.column_defs.len() {
+            return Err(Error::InvalidValue(format!(
+                "expected {} fields, found {}",
+                self.column_defs.len(),
+                fields.len()
+            )));
+        }
+
+        let column_values = self
+            .column_defs
+            .iter()
+            .zip(fields)
+            .map(|(col, field)| format.parse(field.as_ref(), &col.data_type))
+            .collect::<Result<Vec<Option<ColumnValue>>>>()?;
+
+        Ok(DataRow {
+            column_defs: self.column_defs.clone(),
+            column_values,
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for RowSeed {
+    type Value = DataRow;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for RowSeed {
+    type Value = DataRow;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a row of {} values", self.column_defs.len())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<DataRow, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut column_values = Vec::with_capacity(self.column_defs.len());
+        for (idx, col) in self.column_defs.iter().enumerate() {
+            match seq.next_element_seed(ValueSeed::new(&col.data_type))? {
+                Some(value) => column_values.push(value),
+                None => return Err(de::Error::invalid_length(idx, &self)),
+            }
+        }
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(self.column_defs.len() + 1, &self));
+        }
+
+        Ok(DataRow {
+            column_defs: self.column_defs,
+            column_values,
+        })
+    }
+}
+
+///
+/// Reads serialized `TableData`, i.e. a header followed by rows,
+/// checking the header against the table definition
+pub struct TableDataSeed {
+    table_def: TableDefinition,
+}
+
+impl TableDataSeed {
+    pub fn new(table_def: TableDefinition) -> TableDataSeed {
+        TableDataSeed { table_def }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for TableDataSeed {
+    type Value = TableData;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TableDataSeed {
+    type Value = TableData;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "a header followed by rows of {}",
+            self.table_def.table_name
+        )
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<TableData, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let header: Vec<String> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if header != self.table_def.header() {
+            return Err(de::Error::custom(format!(
+                "header {} does not match columns {}",
+                header.join(","),
+                self.table_def.header().join(",")
+            )));
+        }
+
+        let row_seed = RowSeed::new(&self.table_def);
+        let mut data = Vec::new();
+        while let Some(row) = seq.next_element_seed(row_seed.clone())? {
+            data.push(row);
+        }
+
+        Ok(TableData {
+            table_name: self.table_def.table_name,
+            column_defs: row_seed.column_defs,
+            options: self.table_def.options,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RowSeed, TableDataSeed};
+    use crate::definition::{
+        ColumnDefinition, ColumnValue, DataType, QueryOptions, TableDefinition, TableName,
+        ValueFormat,
+    };
+    use chrono::{TimeZone, Utc};
+    use serde::de::DeserializeSeed;
+
+    fn table_def() -> TableDefinition {
+        let column = |name: &str, data_type| ColumnDefinition {
+            column_name: String::from(name),
+            nullable: true,
+            data_type,
+        };
+        TableDefinition {
+            table_name: TableName::parse("T"),
+            columns: vec![
+                column("ID", DataType::Number(Some(10), Some(0))),
+                column("AMOUNT", DataType::Number(Some(10), Some(2))),
+                column("RATIO", DataType::BinaryDouble),
+                column("NAME", DataType::VarChar(20)),
+                column("ACTIVE", DataType::Boolean),
+                column("CREATED", DataType::DateTime),
+                column("PAYLOAD", DataType::BLob),
+            ],
+            options: QueryOptions::default(),
+            constraints: Vec::new(),
+        }
+    }
+
+    fn values() -> Vec<Option<ColumnValue>> {
+        vec![
+            Some(ColumnValue::Number(7)),
+            Some(ColumnValue::Decimal(String::from("12.50"))),
+            Some(ColumnValue::Float(0.25)),
+            None,
+            Some(ColumnValue::Boolean(true)),
+            Some(ColumnValue::DateTime(
+                Utc.with_ymd_and_hms(2023, 4, 1, 12, 30, 0).unwrap(),
+            )),
+            Some(ColumnValue::Binary(vec![0, 255])),
+        ]
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut json = serde_json::Deserializer::from_str(
+            r#"[["ID","AMOUNT","RATIO","NAME","ACTIVE","CREATED","PAYLOAD"],
+                [7,"12.50",0.25,null,true,"2023-04-01 12:30:00",[0,255]]]"#,
+        );
+        let data = TableDataSeed::new(table_def())
+            .deserialize(&mut json)
+            .unwrap();
+        assert_eq!(1, data.rows().len());
+        assert_eq!(values(), data.rows()[0].values());
+
+        let text = serde_json::to_string(&data).unwrap();
+        let mut json = serde_json::Deserializer::from_str(&text);
+        let again = TableDataSeed::new(table_def())
+            .deserialize(&mut json)
+            .unwrap();
+        assert_eq!(data.rows(), again.rows());
+    }
+
+    #[test]
+    fn test_json_mismatch() {
+        let mut json = serde_json::Deserializer::from_str(r#"[["ID"]]"#);
+        assert!(TableDataSeed::new(table_def())
+            .deserialize(&mut json)
+            .is_err());
+
+        let mut json = serde_json::Deserializer::from_str(r#"[7,"x",0.25,null,true,null,null]"#);
+        assert!(RowSeed::new(&table_def()).deserialize(&mut json).is_err());
+
+        let mut json = serde_json::Deserializer::from_str(r#"[7,"1",0.25,null,true,null]"#);
+        assert!(RowSeed::new(&table_def()).deserialize(&mut json).is_err());
+
+        let fields = ["7", "12.50"];
+        assert!(RowSeed::new(&table_def())
+            .parse(&fields, &ValueFormat::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let format = ValueFormat::new();
+        let fields = format.format_row(&values());
+        let row = RowSeed::new(&table_def()).parse(&fields, &format).unwrap();
+        assert_eq!(values(), row.values());
+    }
+}
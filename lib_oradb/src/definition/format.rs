@@ -0,0 +1,705 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Text formatting of column values
+//!
+
+use super::{ColumnValue, DataType};
+use crate::Error;
+use crate::Result;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Default format for `DATE` columns
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Default format for `TIMESTAMP` columns
+pub const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+///
+/// Text representation of boolean values
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoolFormat {
+    /// `true` and `false`
+    #[default]
+    TrueFalse,
+    /// `1` and `0`
+    OneZero,
+    /// `Y` and `N`
+    YesNo,
+}
+
+impl BoolFormat {
+    ///
+    /// Parses a format name as accepted on the command line
+    pub fn parse(name: &str) -> Option<BoolFormat> {
+        match name.to_lowercase().as_str() {
+            "true-false" => Some(BoolFormat::TrueFalse),
+            "1-0" => Some(BoolFormat::OneZero),
+            "y-n" => Some(BoolFormat::YesNo),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the texts for `true` and `false`
+    fn tokens(self) -> (&'static str, &'static str) {
+        match self {
+            BoolFormat::TrueFalse => ("true", "false"),
+            BoolFormat::OneZero => ("1", "0"),
+            BoolFormat::YesNo => ("Y", "N"),
+        }
+    }
+}
+
+///
+/// Handling of control characters in text values
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ControlChars {
+    /// write control characters unchanged
+    #[default]
+    Keep,
+    /// leave control characters out
+    Strip,
+    /// write the given character instead
+    Replace(char),
+}
+
+///
+/// Converts decimal text in scientific notation such as `1.5E+3`
+/// into plain notation; other text is returned unchanged
+fn plain_notation(text: &str) -> String {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((m, e)) => match e.parse::<i32>() {
+            Ok(exp) => (m, exp),
+            Err(_) => return String::from(text),
+        },
+        None => return String::from(text),
+    };
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(m) => ("-", m),
+        None => ("", mantissa),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int, frac);
+    // position of the decimal point within the digits
+    let point = int.len() as i64 + i64::from(exponent);
+
+    let (int, frac) = if point <= 0 {
+        (
+            String::from("0"),
+            format!("{}{}", "0".repeat(point.unsigned_abs() as usize), digits),
+        )
+    } else if point as usize >= digits.len() {
+        (
+            format!("{}{}", digits, "0".repeat(point as usize - digits.len())),
+            String::new(),
+        )
+    } else {
+        let (i, f) = digits.split_at(point as usize);
+        (String::from(i), String::from(f))
+    };
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+    let frac = frac.trim_end_matches('0');
+
+    if frac.is_empty() {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}.{}", sign, int, frac)
+    }
+}
+
+///
+/// Rounds decimal text in plain notation half away from zero to a
+/// fixed number of fraction digits, padding with zeros if needed
+fn round_decimal(text: &str, scale: usize) -> String {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(u) => ("-", u),
+        None => ("", text),
+    };
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    let mut digits: Vec<u8> = int.bytes().chain(frac.bytes().take(scale)).collect();
+    digits.resize(int.len() + scale, b'0');
+    if frac.as_bytes().get(scale).is_some_and(|d| *d >= b'5') {
+        // carry through trailing nines
+        let mut carry = true;
+        for d in digits.iter_mut().rev() {
+            if *d == b'9' {
+                *d = b'0';
+            } else {
+                *d += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            digits.insert(0, b'1');
+        }
+    }
+
+    let digits = String::from_utf8(digits).unwrap();
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    // rounding may leave a negative zero
+    let sign = if digits.bytes().all(|d| d == b'0') {
+        ""
+    } else {
+        sign
+    };
+
+    if scale == 0 {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}.{}", sign, int, frac)
+    }
+}
+
+///
+/// Inserts a separator between groups of three digits
+fn group_digits(int: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(int.len() + int.len() / 3);
+    for (idx, c) in int.chars().enumerate() {
+        if idx > 0 && (int.len() - idx).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+///
+/// Describes how column values are rendered as text
+#[derive(Debug, Clone)]
+pub struct ValueFormat {
+    /// text written for NULL values
+    null_token: String,
+    /// chrono format string for dates
+    date_format: String,
+    /// chrono format string for timestamps
+    datetime_format: String,
+    /// representation of boolean values
+    bool_format: BoolFormat,
+    /// separator between integer and fraction digits of non-integer numbers
+    decimal_separator: char,
+    /// separator between groups of thousands of non-integer numbers
+    thousands_separator: Option<char>,
+    /// number of fraction digits non-integer numbers are rounded or padded to
+    decimal_scale: Option<usize>,
+    /// handling of control characters in text values
+    control_chars: ControlChars,
+    /// whether line breaks in text values are written as `\n`
+    escape_newlines: bool,
+    /// whether trailing whitespace of text values is removed
+    trim_trailing: bool,
+    /// whether backslashes, tabs and line breaks are escaped with a backslash
+    backslash_escapes: bool,
+    /// delimiter that, like backslashes and line breaks, gets a backslash prefix
+    prefix_escapes: Option<char>,
+}
+
+impl Default for ValueFormat {
+    fn default() -> Self {
+        ValueFormat {
+            null_token: String::new(),
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            datetime_format: String::from(DEFAULT_DATETIME_FORMAT),
+            bool_format: BoolFormat::default(),
+            decimal_separator: '.',
+            thousands_separator: None,
+            decimal_scale: None,
+            control_chars: ControlChars::default(),
+            escape_newlines: false,
+            trim_trailing: false,
+            backslash_escapes: false,
+            prefix_escapes: None,
+        }
+    }
+}
+
+impl ValueFormat {
+    ///
+    /// Constructs a new `ValueFormat` with default settings
+    pub fn new() -> ValueFormat {
+        ValueFormat::default()
+    }
+
+    ///
+    /// Sets the text written for NULL values
+    pub fn null_token<S: AsRef<str>>(mut self, token: S) -> Self {
+        self.null_token = String::from(token.as_ref());
+
+        self
+    }
+
+    ///
+    /// Sets the chrono format string for dates
+    pub fn date_format<S: AsRef<str>>(mut self, format: S) -> Self {
+        self.date_format = String::from(format.as_ref());
+
+        self
+    }
+
+    ///
+    /// Sets the chrono format string for timestamps
+    pub fn datetime_format<S: AsRef<str>>(mut self, format: S) -> Self {
+        self.datetime_format = String::from(format.as_ref());
+
+        self
+    }
+
+    ///
+    /// Sets the representation of boolean values
+    pub fn bool_format(mut self, format: BoolFormat) -> Self {
+        self.bool_format = format;
+
+        self
+    }
+
+    ///
+    /// Sets the decimal separator of non-integer numbers, e.g. `,`
+    pub fn decimal_separator(mut self, separator: char) -> Self {
+        self.decimal_separator = separator;
+
+        self
+    }
+
+    ///
+    /// Sets a separator between groups of thousands of non-integer numbers
+    pub fn thousands_separator(mut self, separator: char) -> Self {
+        self.thousands_separator = Some(separator);
+
+        self
+    }
+
+    ///
+    /// Rounds or pads non-integer numbers to a fixed number of fraction digits
+    pub fn decimal_scale(mut self, scale: usize) -> Self {
+        self.decimal_scale = Some(scale);
+
+        self
+    }
+
+    ///
+    /// Sets how control characters in text values are handled; line
+    /// breaks are only affected unless they are escaped
+    pub fn control_chars(mut self, handling: ControlChars) -> Self {
+        self.control_chars = handling;
+
+        self
+    }
+
+    ///
+    /// Writes line breaks (`\r\n`, `\n` or `\r`) in text values as the
+    /// two characters `\n`
+    pub fn escape_newlines(mut self, enabled: bool) -> Self {
+        self.escape_newlines = enabled;
+
+        self
+    }
+
+    ///
+    /// Removes trailing whitespace from text values
+    pub fn trim_trailing(mut self, enabled: bool) -> Self {
+        self.trim_trailing = enabled;
+
+        self
+    }
+
+    ///
+    /// Escapes backslashes, tabs and line breaks in text values as
+    /// `\\`, `\t`, `\r` and `\n` and prefixes binary values with `\\x`,
+    /// as the text format of PostgreSQL's `COPY` expects. Takes
+    /// precedence over `escape_newlines` and `control_chars`.
+    pub fn backslash_escapes(mut self, enabled: bool) -> Self {
+        self.backslash_escapes = enabled;
+
+        self
+    }
+
+    ///
+    /// Prefixes backslashes, `delimiter` and line breaks in text values
+    /// with a backslash, as loaders with an `ESCAPE` option expect for
+    /// unenclosed fields; text reading `\N` is written as `\\N` and thus
+    /// differs from a `\N` null token. `backslash_escapes` takes precedence.
+    pub fn prefix_escapes(mut self, delimiter: char) -> Self {
+        self.prefix_escapes = Some(delimiter);
+
+        self
+    }
+
+    ///
+    /// Reverts the escapes written with `backslash_escapes` or `prefix_escapes`
+    fn unescape_text(&self, text: &str) -> String {
+        if !self.backslash_escapes && self.prefix_escapes.is_none() {
+            return String::from(text);
+        }
+
+        let mut unescaped = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('t') if self.backslash_escapes => unescaped.push('\t'),
+                Some('r') if self.backslash_escapes => unescaped.push('\r'),
+                Some('n') if self.backslash_escapes => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push(c),
+            }
+        }
+
+        unescaped
+    }
+
+    ///
+    /// Renders a text value with the configured sanitization
+    fn format_text(&self, text: &str) -> String {
+        let text = if self.trim_trailing {
+            text.trim_end()
+        } else {
+            text
+        };
+
+        let mut sanitized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if self.backslash_escapes => sanitized.push_str("\\\\"),
+                '\t' if self.backslash_escapes => sanitized.push_str("\\t"),
+                '\r' if self.backslash_escapes => sanitized.push_str("\\r"),
+                '\n' if self.backslash_escapes => sanitized.push_str("\\n"),
+                c if self
+                    .prefix_escapes
+                    .is_some_and(|d| ['\\', d, '\r', '\n'].contains(&c)) =>
+                {
+                    sanitized.push('\\');
+                    sanitized.push(c);
+                }
+                '\r' | '\n' if self.escape_newlines => {
+                    // a CRLF pair is a single line break
+                    if c == '\r' && chars.peek() == Some(&'\n') {
+                        chars.next();
+                    }
+                    sanitized.push_str("\\n");
+                }
+                c if c.is_control() => match self.control_chars {
+                    ControlChars::Keep => sanitized.push(c),
+                    ControlChars::Strip => (),
+                    ControlChars::Replace(r) => sanitized.push(r),
+                },
+                c => sanitized.push(c),
+            }
+        }
+
+        sanitized
+    }
+
+    ///
+    /// Renders decimal text in plain notation with the configured
+    /// scale and separators
+    fn format_decimal(&self, text: &str) -> String {
+        let plain = plain_notation(text);
+        let plain = match self.decimal_scale {
+            Some(scale) => round_decimal(&plain, scale),
+            None => plain,
+        };
+        if self.decimal_separator == '.' && self.thousands_separator.is_none() {
+            return plain;
+        }
+
+        let (sign, unsigned) = match plain.strip_prefix('-') {
+            Some(u) => ("-", u),
+            None => ("", plain.as_str()),
+        };
+        let (int, frac) = match unsigned.split_once('.') {
+            Some((i, f)) => (i, Some(f)),
+            None => (unsigned, None),
+        };
+        let int = match self.thousands_separator {
+            Some(separator) => group_digits(int, separator),
+            None => String::from(int),
+        };
+
+        match frac {
+            Some(f) => format!("{}{}{}{}", sign, int, self.decimal_separator, f),
+            None => format!("{}{}", sign, int),
+        }
+    }
+
+    ///
+    /// Reverts the separators of a non-integer number written by `format`
+    fn normalize_decimal(&self, text: &str) -> String {
+        text.chars()
+            .filter(|c| Some(*c) != self.thousands_separator)
+            .map(|c| if c == self.decimal_separator { '.' } else { c })
+            .collect()
+    }
+
+    ///
+    /// Renders a single, possibly NULL, column value
+    pub fn format(&self, value: &Option<ColumnValue>) -> String {
+        match value {
+            None => self.null_token.clone(),
+            Some(ColumnValue::Varchar(v)) => self.format_text(v),
+            // infinity and NaN have no decimal representation
+            Some(ColumnValue::Float(v)) if !v.is_finite() => v.to_string(),
+            Some(ColumnValue::Float(v)) => self.format_decimal(&v.to_string()),
+            Some(ColumnValue::Number(v)) => v.to_string(),
+            Some(ColumnValue::Decimal(v)) => self.format_decimal(v),
+            Some(ColumnValue::Boolean(v)) => {
+                let (yes, no) = self.bool_format.tokens();
+                String::from(if *v { yes } else { no })
+            }
+            Some(ColumnValue::Date(v)) => v.format(&self.date_format).to_string(),
+            Some(ColumnValue::DateTime(v)) => v.format(&self.datetime_format).to_string(),
+            Some(ColumnValue::Binary(v)) => {
+                let hex: String = v.iter().map(|b| format!("{:02X}", b)).collect();
+                if self.backslash_escapes {
+                    format!("\\\\x{}", hex)
+                } else {
+                    hex
+                }
+            }
+        }
+    }
+
+    ///
+    /// Renders all values of a row
+    pub fn format_row(&self, row: &[Option<ColumnValue>]) -> Vec<String> {
+        row.iter().map(|value| self.format(value)).collect()
+    }
+
+    ///
+    /// Parses text produced by `format` back into a column value
+    /// of the given data type. The null token yields `None`.
+    pub fn parse(&self, text: &str, data_type: &DataType) -> Result<Option<ColumnValue>> {
+        if text == self.null_token {
+            return Ok(None);
+        }
+
+        let invalid = || Error::InvalidValue(format!("{} is not a valid {:?}", text, data_type));
+
+        Ok(Some(match data_type {
+            DataType::VarChar(_) | DataType::CLob | DataType::Other(_) => {
+                ColumnValue::Varchar(self.unescape_text(text))
+            }
+            DataType::Number(_, _) if data_type.is_integer() => {
+                ColumnValue::Number(text.parse().map_err(|_| invalid())?)
+            }
+            DataType::Number(_, _) => {
+                let text = self.normalize_decimal(text);
+                // validate without losing precision
+                text.parse::<f64>().map_err(|_| invalid())?;
+                ColumnValue::Decimal(text)
+            }
+            DataType::Float(_) | DataType::BinaryFloat | DataType::BinaryDouble => {
+                ColumnValue::Float(
+                    self.normalize_decimal(text)
+                        .parse()
+                        .map_err(|_| invalid())?,
+                )
+            }
+            DataType::Boolean => match self.bool_format.tokens() {
+                (yes, _) if text == yes => ColumnValue::Boolean(true),
+                (_, no) if text == no => ColumnValue::Boolean(false),
+                _ => return Err(invalid()),
+            },
+            DataType::Date => {
+                let date =
+                    NaiveDate::parse_from_str(text, &self.date_format).map_err(|_| invalid())?;
+                let midnight = date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?;
+                ColumnValue::Date(Utc.from_utc_datetime(&midnight))
+            }
+            DataType::DateTime => {
+                let stamp = NaiveDateTime::parse_from_str(text, &self.datetime_format)
+                    .map_err(|_| invalid())?;
+                ColumnValue::DateTime(Utc.from_utc_datetime(&stamp))
+            }
+            DataType::BLob => {
+                let text = if self.backslash_escapes {
+                    text.strip_prefix("\\\\x").ok_or_else(invalid)?
+                } else {
+                    text
+                };
+                if !text.len().is_multiple_of(2) {
+                    return Err(invalid());
+                }
+                let bytes: std::result::Result<Vec<u8>, _> = (0..text.len())
+                    .step_by(2)
+                    .map(|idx| {
+                        text.get(idx..idx + 2)
+                            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                            .ok_or_else(invalid)
+                    })
+                    .collect();
+                ColumnValue::Binary(bytes?)
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plain_notation, round_decimal, BoolFormat, ControlChars, ValueFormat};
+    use crate::definition::{ColumnValue, DataType};
+
+    #[test]
+    fn test_bool_format() {
+        let format = ValueFormat::new().bool_format(BoolFormat::YesNo);
+        assert_eq!("Y", format.format(&Some(ColumnValue::Boolean(true))));
+        assert_eq!("N", format.format(&Some(ColumnValue::Boolean(false))));
+        assert!(matches!(
+            format.parse("N", &DataType::Boolean),
+            Ok(Some(ColumnValue::Boolean(false)))
+        ));
+        assert!(format.parse("false", &DataType::Boolean).is_err());
+
+        let format = ValueFormat::new().bool_format(BoolFormat::parse("1-0").unwrap());
+        assert_eq!("1", format.format(&Some(ColumnValue::Boolean(true))));
+        assert_eq!(
+            "true",
+            ValueFormat::new().format(&Some(ColumnValue::Boolean(true)))
+        );
+    }
+
+    #[test]
+    fn test_plain_notation() {
+        assert_eq!("1500", plain_notation("1.5E+3"));
+        assert_eq!("-0.00012", plain_notation("-1.2e-4"));
+        assert_eq!("12.5", plain_notation("1.25E1"));
+        assert_eq!("1234.56", plain_notation("1234.56"));
+    }
+
+    #[test]
+    fn test_round_decimal() {
+        assert_eq!("1234.57", round_decimal("1234.565", 2));
+        assert_eq!("1.50", round_decimal("1.5", 2));
+        assert_eq!("100", round_decimal("99.5", 0));
+        assert_eq!("-10.0", round_decimal("-9.96", 1));
+        assert_eq!("0.00", round_decimal("-0.001", 2));
+    }
+
+    #[test]
+    fn test_decimal_format() {
+        let format = ValueFormat::new()
+            .decimal_separator(',')
+            .thousands_separator('.')
+            .decimal_scale(2);
+        assert_eq!(
+            "1.234,56",
+            format.format(&Some(ColumnValue::Decimal(String::from("1234.555"))))
+        );
+        assert_eq!("-0,50", format.format(&Some(ColumnValue::Float(-0.5))));
+        assert_eq!("12", format.format(&Some(ColumnValue::Number(12))));
+        assert!(matches!(
+            format.parse("1.234,56", &DataType::Number(Some(10), Some(2))),
+            Ok(Some(ColumnValue::Decimal(v))) if v == "1234.56"
+        ));
+
+        let format = ValueFormat::new();
+        assert_eq!(
+            "0.1",
+            format.format(&Some(ColumnValue::Decimal(String::from("0.1"))))
+        );
+        assert_eq!(
+            "1000000000000000000000",
+            format.format(&Some(ColumnValue::Float(1e21)))
+        );
+    }
+
+    #[test]
+    fn test_text_sanitization() {
+        let text = Some(ColumnValue::Varchar(String::from("a\tb\r\nc\0 \n  ")));
+
+        assert_eq!("a\tb\r\nc\0 \n  ", ValueFormat::new().format(&text));
+        assert_eq!(
+            "a b\\nc ",
+            ValueFormat::new()
+                .control_chars(ControlChars::Replace(' '))
+                .escape_newlines(true)
+                .trim_trailing(true)
+                .format(&text)
+        );
+        assert_eq!(
+            "ab\\nc \\n",
+            ValueFormat::new()
+                .control_chars(ControlChars::Strip)
+                .escape_newlines(true)
+                .format(&Some(ColumnValue::Varchar(String::from("a\tb\rc\0 \n"))))
+        );
+    }
+
+    #[test]
+    fn test_backslash_escapes() {
+        let format = ValueFormat::new()
+            .backslash_escapes(true)
+            .escape_newlines(true)
+            .control_chars(ControlChars::Strip);
+        let text = Some(ColumnValue::Varchar(String::from("C:\\tmp\tx\r\ny\0")));
+        assert_eq!("C:\\\\tmp\\tx\\r\\ny", format.format(&text));
+        assert!(matches!(
+            format.parse("C:\\\\tmp\\tx\\r\\ny", &DataType::VarChar(20)),
+            Ok(Some(ColumnValue::Varchar(v))) if v == "C:\\tmp\tx\r\ny"
+        ));
+
+        let value = Some(ColumnValue::Binary(vec![0x0A, 0xFF]));
+        assert_eq!("\\\\x0AFF", format.format(&value));
+        assert_eq!(value, format.parse("\\\\x0AFF", &DataType::BLob).unwrap());
+    }
+
+    #[test]
+    fn test_prefix_escapes() {
+        let format = ValueFormat::new().null_token("\\N").prefix_escapes('|');
+        let text = Some(ColumnValue::Varchar(String::from("\\N|a\tb\n")));
+        assert_eq!("\\\\N\\|a\tb\\\n", format.format(&text));
+        assert_eq!("\\N", format.format(&None));
+        assert_eq!(
+            text,
+            format
+                .parse("\\\\N\\|a\tb\\\n", &DataType::VarChar(20))
+                .unwrap()
+        );
+        assert_eq!(None, format.parse("\\N", &DataType::VarChar(20)).unwrap());
+    }
+
+    #[test]
+    fn test_binary_format() {
+        let format = ValueFormat::new();
+        let value = Some(ColumnValue::Binary(vec![0x00, 0xAB, 0x7F]));
+        assert_eq!("00AB7F", format.format(&value));
+        assert!(matches!(
+            format.parse("00AB7F", &DataType::BLob),
+            Ok(Some(ColumnValue::Binary(v))) if v == vec![0x00, 0xAB, 0x7F]
+        ));
+        assert!(format.parse("ABC", &DataType::BLob).is_err());
+        assert!(format.parse("ZZ", &DataType::BLob).is_err());
+    }
+}
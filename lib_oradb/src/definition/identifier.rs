@@ -0,0 +1,325 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Normalization and quoting of Oracle identifiers
+//!
+
+use crate::{Error, Result};
+
+///
+/// Normalizes an identifier to its data dictionary form: unquoted
+/// names are case-insensitive and stored in upper case, double-quoted
+/// names are kept verbatim without the quotes
+pub fn normalize_identifier(identifier: &str) -> String {
+    let identifier = identifier.trim();
+
+    match identifier
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(quoted) => String::from(quoted),
+        None => identifier.to_uppercase(),
+    }
+}
+
+/// maximum identifier length in bytes since Oracle 12.2
+const MAX_IDENTIFIER_LENGTH: usize = 128;
+
+///
+/// Checks that a normalized identifier can be quoted safely;
+/// quoted identifiers must not contain double quotes or NUL
+pub(crate) fn validate_identifier(identifier: &str) -> Result<()> {
+    if identifier.is_empty()
+        || identifier.len() > MAX_IDENTIFIER_LENGTH
+        || identifier.contains(['"', '\0'])
+    {
+        return Err(Error::InvalidIdentifier(String::from(identifier)));
+    }
+
+    Ok(())
+}
+
+///
+/// Checks that a normalized database link name can be written unquoted;
+/// link names may contain dots and an `@` connection qualifier
+fn validate_db_link(link: &str) -> Result<()> {
+    let valid = link.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && link.len() <= MAX_IDENTIFIER_LENGTH
+        && link
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['_', '$', '#', '.', '@'].contains(&c));
+    if !valid {
+        return Err(Error::InvalidIdentifier(String::from(link)));
+    }
+
+    Ok(())
+}
+
+///
+/// Optionally owner qualified table name with an optional database
+/// link, normalized part by part; dots and `@` inside double quotes
+/// belong to the name and never separate owner, table or link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableName {
+    owner: Option<String>,
+    table: String,
+    link: Option<String>,
+}
+
+impl TableName {
+    ///
+    /// Parses `[owner.]table[@dblink]`; database link names are
+    /// case-insensitive
+    pub fn parse(table_name: &str) -> TableName {
+        let (local_name, link) = match find_unquoted(table_name, '@') {
+            Some(idx) => (
+                &table_name[..idx],
+                Some(table_name[idx + 1..].trim().to_uppercase()),
+            ),
+            None => (table_name, None),
+        };
+
+        let (owner, table) = match find_unquoted(local_name, '.') {
+            Some(idx) => (
+                Some(normalize_identifier(&local_name[..idx])),
+                normalize_identifier(&local_name[idx + 1..]),
+            ),
+            None => (None, normalize_identifier(local_name)),
+        };
+
+        TableName { owner, table, link }
+    }
+
+    /// normalized owner, if the name is qualified
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// normalized table name without owner and database link
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// normalized database link name without the leading `@`
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
+    ///
+    /// Qualifies the table name with an owner; fails if the name is
+    /// already qualified with a different owner
+    pub fn with_owner<S: AsRef<str>>(mut self, owner: S) -> Result<TableName> {
+        let owner = normalize_identifier(owner.as_ref());
+        match &self.owner {
+            Some(named) if *named != owner => Err(Error::InvalidValue(format!(
+                "table {} does not belong to owner {}",
+                self, owner
+            ))),
+            Some(_) => Ok(self),
+            None => {
+                self.owner = Some(owner);
+                Ok(self)
+            }
+        }
+    }
+
+    ///
+    /// Qualifies the table name with a normalized owner
+    pub(crate) fn set_owner(&mut self, owner: String) {
+        self.owner = Some(owner);
+    }
+
+    ///
+    /// Checks owner, table and database link part
+    pub(crate) fn validate(&self) -> Result<()> {
+        let valid = self
+            .owner
+            .as_deref()
+            .map_or(Ok(()), validate_identifier)
+            .and_then(|_| validate_identifier(&self.table))
+            .and_then(|_| self.link.as_deref().map_or(Ok(()), validate_db_link));
+
+        valid.map_err(|_| Error::InvalidIdentifier(self.to_string()))
+    }
+
+    ///
+    /// Quoted SQL reference to a validated table name
+    pub(crate) fn reference(&self) -> String {
+        let mut reference = match &self.owner {
+            Some(owner) => format!(
+                "{}.{}",
+                quote_identifier(owner),
+                quote_identifier(&self.table)
+            ),
+            None => quote_identifier(&self.table),
+        };
+        if let Some(link) = &self.link {
+            reference.push('@');
+            reference.push_str(link);
+        }
+        reference
+    }
+
+    ///
+    /// Suffix that routes a data dictionary view over the database link
+    pub(crate) fn link_suffix(&self) -> String {
+        self.link
+            .as_ref()
+            .map_or_else(String::new, |link| format!("@{}", link))
+    }
+}
+
+impl std::fmt::Display for TableName {
+    ///
+    /// Writes the name so that parsing it again yields the same parts;
+    /// parts that are not plain upper case identifiers are quoted
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(owner) = &self.owner {
+            write!(f, "{}.", display_identifier(owner))?;
+        }
+        write!(f, "{}", display_identifier(&self.table))?;
+        if let Some(link) = &self.link {
+            write!(f, "@{}", link)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Byte offset of the first `separator` outside double quotes
+fn find_unquoted(text: &str, separator: char) -> Option<usize> {
+    let mut in_quotes = false;
+    text.char_indices().find_map(|(idx, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        c if c == separator && !in_quotes => Some(idx),
+        _ => None,
+    })
+}
+
+///
+/// Writes a normalized identifier unquoted when it reads back unchanged
+fn display_identifier(identifier: &str) -> String {
+    let plain = identifier
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_uppercase())
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || ['_', '$', '#'].contains(&c));
+    if plain {
+        String::from(identifier)
+    } else {
+        quote_identifier(identifier)
+    }
+}
+
+///
+/// Quotes a validated identifier so that reserved words and names
+/// with special characters are used verbatim in SQL
+pub(crate) fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_identifier, quote_identifier, TableName};
+
+    #[test]
+    fn test_unquoted_identifiers_are_upper_case() {
+        assert_eq!("AU_KAUFDAT", normalize_identifier("au_KaufDat"));
+
+        let table_name = TableName::parse("scott.emp");
+        assert_eq!(Some("SCOTT"), table_name.owner());
+        assert_eq!("EMP", table_name.table());
+        assert_eq!("SCOTT.EMP", table_name.to_string());
+    }
+
+    #[test]
+    fn test_quoted_identifiers_are_verbatim() {
+        assert_eq!("lower name", normalize_identifier("\"lower name\""));
+
+        let table_name = TableName::parse("scott.\"My.Table\"");
+        assert_eq!(Some("SCOTT"), table_name.owner());
+        assert_eq!("My.Table", table_name.table());
+        assert_eq!(r#""SCOTT"."My.Table""#, table_name.reference());
+    }
+
+    #[test]
+    fn test_quoted_dot_without_owner() {
+        let table_name = TableName::parse("\"My.Table\"");
+        assert_eq!(None, table_name.owner());
+        assert_eq!("My.Table", table_name.table());
+        assert_eq!(r#""My.Table""#, table_name.reference());
+        assert_eq!(table_name, TableName::parse(&table_name.to_string()));
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(r#""AU_KAUFDAT""#, quote_identifier("AU_KAUFDAT"));
+        assert_eq!(r#""au_kaufdat""#, quote_identifier("au_kaufdat"));
+        assert_eq!(r#""ORDER""#, quote_identifier("ORDER"));
+    }
+
+    #[test]
+    fn test_reject_injected_table_name() {
+        assert!(TableName::parse("SCOTT.EMP").validate().is_ok());
+        assert!(TableName::parse(r#""EMP"" WHERE 1=1 --""#)
+            .validate()
+            .is_err());
+        assert!(TableName::parse("").validate().is_err());
+        assert!(TableName::parse(&"X".repeat(129)).validate().is_err());
+    }
+
+    #[test]
+    fn test_db_link() {
+        let table_name = TableName::parse("scott.emp@remote.example.com");
+        assert_eq!(Some("SCOTT"), table_name.owner());
+        assert_eq!("EMP", table_name.table());
+        assert_eq!(Some("REMOTE.EXAMPLE.COM"), table_name.link());
+        assert_eq!(
+            r#""SCOTT"."EMP"@REMOTE.EXAMPLE.COM"#,
+            table_name.reference()
+        );
+        assert_eq!("SCOTT.EMP@REMOTE.EXAMPLE.COM", table_name.to_string());
+        assert_eq!(None, TableName::parse("EMP").link());
+
+        // an `@` inside quotes belongs to the table name
+        let quoted = TableName::parse("\"Mail@Box\"@hq");
+        assert_eq!(None, quoted.owner());
+        assert_eq!("Mail@Box", quoted.table());
+        assert_eq!(Some("HQ"), quoted.link());
+        assert_eq!(r#""Mail@Box"@HQ"#, quoted.reference());
+        assert_eq!(quoted, TableName::parse(&quoted.to_string()));
+
+        assert!(TableName::parse("EMP@REMOTE@QUALIFIER").validate().is_ok());
+        assert!(TableName::parse("EMP@").validate().is_err());
+        assert!(TableName::parse("EMP@REMOTE WHERE 1=1").validate().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Process memory monitoring for throttling the reader
+//!
+
+use super::DataPipe;
+
+/// number of rows read between memory checks
+pub(crate) const MEMORY_CHECK_INTERVAL: u64 = 1000;
+
+/// pause between memory checks while throttled
+const THROTTLE_PAUSE: std::time::Duration = std::time::Duration::from_millis(100);
+
+///
+/// Reads the resident set size of the current process in bytes;
+/// `None` where `/proc/self/status` is not available, e.g. on Windows
+/// and macOS, where a memory limit has no effect
+pub fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(|kb| kb * 1024)
+}
+
+///
+/// Pauses the reader while the process exceeds `limit` bytes and
+/// the writer still has queued rows to drain
+pub(crate) fn wait_for_memory(pipe: &DataPipe, limit: u64) {
+    let mut throttled = false;
+
+    while pipe.depth() > 0 && resident_bytes().is_some_and(|rss| rss > limit) {
+        if !throttled {
+            warn!(
+                "Memory limit of {} bytes exceeded; pausing reader with {} queued batches.",
+                limit,
+                pipe.depth()
+            );
+            throttled = true;
+        }
+        std::thread::sleep(THROTTLE_PAUSE);
+    }
+
+    if throttled {
+        info!("Memory below limit again; resuming reader.");
+    }
+}
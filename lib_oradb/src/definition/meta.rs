@@ -28,18 +28,36 @@
 //! Meta definitions for querying meta data
 //!
 
-use super::{ColumnDefinition, DataRow, RowIndicator};
+use super::{
+    ColumnDefinition, ColumnValue, Constraint, DataPipe, DataRow, PartitionInfo, QueryOptions,
+    SessionOptions, TableInfo, TableName,
+};
 use crate::Result;
-use std::collections::{BTreeMap, VecDeque};
-use std::rc::Rc;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 ///
 /// Provides column data from a database
 pub trait ColumnDataProvider {
     ///
     /// queries column data
-    fn query_column_data(&self, table_name: &str) -> Result<Vec<ColumnDefinition>>;
+    fn query_column_data(&self, table_name: &TableName) -> Result<Vec<ColumnDefinition>>;
+
+    ///
+    /// checks whether a table or view exists and is accessible
+    fn table_exists(&self, table_name: &TableName) -> Result<bool>;
+
+    ///
+    /// derives column data of expressions selected from a table as the
+    /// given column names from the statement metadata
+    fn query_expression_data(
+        &self,
+        table_name: &TableName,
+        expressions: &[(String, String)],
+    ) -> Result<Vec<ColumnDefinition>>;
+
+    ///
+    /// queries the primary key and unique constraints of a table
+    fn query_constraints(&self, table_name: &TableName) -> Result<Vec<Constraint>>;
 }
 
 pub trait DataRowProvider {
@@ -47,8 +65,9 @@ pub trait DataRowProvider {
     /// queries data rows
     fn query_data(
         &self,
-        table_name: &str,
-        column_names: Rc<BTreeMap<String, ColumnDefinition>>,
+        table_name: &TableName,
+        column_names: Arc<Vec<ColumnDefinition>>,
+        options: &QueryOptions,
     ) -> Result<Vec<DataRow>>;
 }
 
@@ -57,11 +76,84 @@ pub trait DataRowProvider {
 /// of returning all items collectively.
 pub trait ThreadedDataRowProvider {
     ///
-    /// queries data rows in threaded fashion
+    /// queries data rows in threaded fashion and returns the
+    /// number of rows pushed into the queue
+    fn query_data_threaded(
+        &self,
+        table_name: &TableName,
+        column_names: Arc<Vec<ColumnDefinition>>,
+        options: &QueryOptions,
+        q: Arc<DataPipe>,
+    ) -> Result<u64>;
+}
+
+impl<T: ThreadedDataRowProvider + ?Sized> ThreadedDataRowProvider for Arc<T> {
     fn query_data_threaded(
         &self,
-        table_name: &str,
-        column_names: Rc<BTreeMap<String, ColumnDefinition>>,
-        q: Arc<RwLock<VecDeque<RowIndicator>>>,
-    ) -> Result<()>;
+        table_name: &TableName,
+        column_names: Arc<Vec<ColumnDefinition>>,
+        options: &QueryOptions,
+        q: Arc<DataPipe>,
+    ) -> Result<u64> {
+        self.as_ref()
+            .query_data_threaded(table_name, column_names, options, q)
+    }
+}
+
+///
+/// Writes rows into a table of a database
+pub trait DataRowSink {
+    ///
+    /// inserts rows with values in the order of the given columns
+    /// using a single array bound statement and returns the number
+    /// of rows inserted; committing is left to the caller
+    fn insert_rows(
+        &self,
+        table_name: &TableName,
+        columns: &[ColumnDefinition],
+        rows: &[Vec<Option<ColumnValue>>],
+    ) -> Result<u64>;
+}
+
+///
+/// Provides table statistics from a database
+pub trait TableStatisticsProvider {
+    ///
+    /// estimates the number of rows in a table;
+    /// `None` if no statistics are available
+    fn estimate_rows(&self, table_name: &TableName) -> Result<Option<u64>>;
+}
+
+///
+/// Provides the list of accessible tables
+pub trait TableListProvider {
+    ///
+    /// lists tables, optionally restricted to an owner and a
+    /// table name pattern in `LIKE` syntax
+    fn list_tables(&self, owner: Option<&str>, pattern: Option<&str>) -> Result<Vec<TableInfo>>;
+}
+
+///
+/// Provides the partitions of a table
+pub trait PartitionListProvider {
+    ///
+    /// lists the partitions of a table ordered by owner and position
+    fn list_partitions(&self, table_name: &TableName) -> Result<Vec<PartitionInfo>>;
+}
+
+///
+/// Provides the system change number of a database
+pub trait SystemChangeNumberProvider {
+    ///
+    /// queries the current system change number
+    fn current_scn(&self) -> Result<u64>;
+}
+
+///
+/// Prepares database sessions before any query runs
+pub trait SessionProvider {
+    ///
+    /// applies session options, executing setup statements such as
+    /// `ALTER SESSION` in order before starting a read-only transaction
+    fn initialize_session(&self, options: &SessionOptions) -> Result<()>;
 }
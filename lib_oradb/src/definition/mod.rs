@@ -28,60 +28,216 @@
 //! Table, column, data type definitions
 //!
 
-use std::collections::BTreeMap;
-
 mod builder;
+mod de;
+mod format;
+mod identifier;
+mod memory;
 mod meta;
 mod oracle;
-use crate::Result;
+mod sample;
+mod session;
+use crate::{Error, Result};
 use chrono::{DateTime, Utc};
 use serde::ser::SerializeSeq;
 use serde::{Serialize, Serializer};
 
-pub use self::builder::TableSelectionBuilder;
-use self::meta::{DataRowProvider, ThreadedDataRowProvider};
+pub use self::builder::{ColumnOrder, TableSelectionBuilder, UnknownTypePolicy};
+pub use self::de::{RowSeed, TableDataSeed, ValueSeed};
+pub use self::format::{
+    BoolFormat, ControlChars, ValueFormat, DEFAULT_DATETIME_FORMAT, DEFAULT_DATE_FORMAT,
+};
+pub use self::identifier::{normalize_identifier, TableName};
+pub use self::memory::resident_bytes;
+use self::meta::{
+    ColumnDataProvider, DataRowProvider, DataRowSink, PartitionListProvider, SessionProvider,
+    SystemChangeNumberProvider, TableListProvider, TableStatisticsProvider,
+    ThreadedDataRowProvider,
+};
+pub use self::sample::TypeDrift;
+pub use self::session::SessionOptions;
 use std::collections::VecDeque;
-use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
 
 ///
 /// Available column data type
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DataType {
     VarChar(u32),
-    Number(u32, u32),
+    /// NUMBER with optional precision and scale
+    Number(Option<u32>, Option<i32>),
+    /// ANSI FLOAT with binary precision
+    Float(u32),
+    BinaryFloat,
+    BinaryDouble,
     Boolean,
     Date,
     CLob,
+    /// binary LOB, rendered as hexadecimal text
+    BLob,
     DateTime,
+    /// data type without native support, named as in the data dictionary
+    Other(String),
 }
 
 ///
 /// Defines a table column
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ColumnDefinition {
     column_name: String,
     nullable: bool,
     data_type: DataType,
 }
 
+///
+/// Restricts a query to a single partition or subpartition of a table
+#[derive(Debug, Clone, PartialEq)]
+pub enum TablePartition {
+    /// queried with `PARTITION (name)`
+    Partition(String),
+    /// queried with `SUBPARTITION (name)`
+    Subpartition(String),
+}
+
+///
+/// Options controlling how table data is queried
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// only read distinct rows
+    distinct: bool,
+    /// stage the result in a temporary table before fetching it
+    materialize: bool,
+    /// only read rows of the given partition or subpartition
+    partition: Option<TablePartition>,
+    /// read decimal NUMBER columns as f64 instead of exact decimal text
+    float_numbers: bool,
+    /// only read rows whose column value is greater than the given value
+    newer_than: Option<(String, ColumnValue)>,
+    /// only read rows changed after the given system change number
+    changed_since_scn: Option<u64>,
+    /// only read rows whose column value lies in the half-open range
+    range: Option<(String, BindValue, BindValue)>,
+    /// read rows as of the given system change number
+    as_of_scn: Option<u64>,
+    /// number of rows fetched per database round trip
+    fetch_size: Option<u32>,
+    /// number of rows pushed into the pipe at once in threaded mode
+    batch_size: Option<u32>,
+    /// resident memory in bytes above which the reader pauses
+    memory_limit: Option<u64>,
+    /// only read rows whose column value is one of the given keys
+    keys: Option<(String, Vec<BindValue>)>,
+    /// additional WHERE condition with placeholders
+    filter: Option<String>,
+    /// values bound to the filter placeholders in order of appearance
+    binds: Vec<BindValue>,
+    /// maximum number of rows to read
+    row_limit: Option<u64>,
+    /// percentage of rows read at random and the seed repeating the sample
+    sample: Option<(f64, Option<u32>)>,
+    /// aggregate expressions and the column names they are selected as
+    aggregates: Vec<(String, String)>,
+    /// columns rows are grouped by
+    group_by: Vec<String>,
+    /// expressions selected as additional columns and their column names
+    computed: Vec<(String, String)>,
+    /// columns cast to another data type by the database
+    type_overrides: Vec<(String, DataType)>,
+}
+
+///
+/// Describes an accessible table
+#[derive(Debug)]
+pub struct TableInfo {
+    /// owning schema
+    owner: String,
+    /// table name
+    table_name: String,
+    /// row count from statistics
+    num_rows: Option<u64>,
+}
+
+///
+/// Describes a partition of a table
+#[derive(Debug)]
+pub struct PartitionInfo {
+    /// owning schema
+    owner: String,
+    /// partitioned table
+    table_name: String,
+    /// partition name
+    partition_name: String,
+    /// position of the partition within the table
+    position: u32,
+    /// upper bound of a range partition or values of a list partition
+    high_value: Option<String>,
+    /// row count from statistics
+    num_rows: Option<u64>,
+    /// number of subpartitions
+    subpartition_count: u32,
+}
+
+///
+/// Kind of a key constraint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+}
+
+///
+/// Describes a primary key or unique constraint of a table
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    /// constraint name
+    name: String,
+    /// primary key or unique constraint
+    kind: ConstraintKind,
+    /// constrained columns in key order
+    columns: Vec<String>,
+}
+
 ///
 /// Defines a table
 #[derive(Debug)]
 pub struct TableDefinition {
     /// table name
-    table_name: String,
+    table_name: TableName,
     /// maps column name to column definition
-    columns: BTreeMap<String, ColumnDefinition>,
+    columns: Vec<ColumnDefinition>,
+    /// query options
+    options: QueryOptions,
+    /// primary key and unique constraints of the table
+    constraints: Vec<Constraint>,
 }
 
 ///
-/// Defines a row's column value
-#[derive(Debug)]
+/// Defines a row's column value; not hashable as floats have no total equality
+#[derive(Debug, Clone, PartialEq)]
 pub enum ColumnValue {
     Varchar(String),
     Float(f64),
     Number(i64),
+    /// exact decimal number in plain text notation
+    Decimal(String),
+    Boolean(bool),
+    Date(DateTime<Utc>),
+    DateTime(DateTime<Utc>),
+    /// content of a BLOB
+    Binary(Vec<u8>),
+}
+
+///
+/// Defines a value bound to a filter placeholder
+#[derive(Debug, Clone)]
+pub enum BindValue {
+    Varchar(String),
+    Float(f64),
+    Number(i64),
+    /// exact decimal number in plain text notation
+    Decimal(String),
     Boolean(bool),
     Date(DateTime<Utc>),
     DateTime(DateTime<Utc>),
@@ -93,15 +249,30 @@ pub enum ColumnValue {
 /// data has been reached.
 pub enum RowIndicator {
     EndOfData,
-    MoreToCome(Vec<Option<ColumnValue>>),
+    /// a batch of rows in query order
+    MoreToCome(Vec<Vec<Option<ColumnValue>>>),
+}
+
+/// rows pushed into the pipe at once unless configured otherwise
+pub const DEFAULT_BATCH_SIZE: u32 = 256;
+
+///
+/// Queue carrying rows from a producer to consumers,
+/// keeping track of progress
+pub struct DataPipe {
+    queue: RwLock<VecDeque<RowIndicator>>,
+    /// number of rows pushed into the queue
+    delivered: AtomicU64,
+    /// set once `EndOfData` has been pushed
+    finished: AtomicBool,
 }
 
 ///
 /// Describes a data row in a table
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DataRow {
     /// back link to column definitions
-    column_defs: Rc<BTreeMap<String, ColumnDefinition>>,
+    column_defs: Arc<Vec<ColumnDefinition>>,
     column_values: Vec<Option<ColumnValue>>,
 }
 
@@ -110,9 +281,11 @@ pub struct DataRow {
 #[derive(Debug)]
 pub struct TableData {
     /// table name
-    table_name: String,
-    /// maps column names to definitions
-    column_defs: Rc<BTreeMap<String, ColumnDefinition>>,
+    table_name: TableName,
+    /// column definitions in output order
+    column_defs: Arc<Vec<ColumnDefinition>>,
+    /// query options
+    options: QueryOptions,
     /// row data
     data: Vec<DataRow>,
 }
@@ -123,72 +296,364 @@ pub struct TableData {
 /// This permits working with received data while
 /// it is still being loaded.
 pub struct ThreadedTableData {
-    table_name: String,
-    /// maps column names to definitions
-    column_defs: Rc<BTreeMap<String, ColumnDefinition>>,
-    pipe: Arc<RwLock<VecDeque<RowIndicator>>>,
+    table_name: TableName,
+    /// column definitions in output order
+    column_defs: Arc<Vec<ColumnDefinition>>,
+    /// query options
+    options: QueryOptions,
+    pipe: Arc<DataPipe>,
+}
+
+///
+/// Handle to a producer thread started by `ThreadedTableData::spawn`,
+/// receiving the rows it queries
+pub struct LoadHandle {
+    pipe: Arc<DataPipe>,
+    producer: JoinHandle<Result<u64>>,
+}
+
+/// pause between looking for new rows while the pipe is empty
+const RECV_PAUSE: std::time::Duration = std::time::Duration::from_millis(10);
+
+impl Default for DataPipe {
+    fn default() -> Self {
+        DataPipe {
+            queue: RwLock::new(VecDeque::new()),
+            delivered: AtomicU64::new(0),
+            finished: AtomicBool::new(false),
+        }
+    }
+}
+
+impl DataPipe {
+    ///
+    /// Constructs an empty `DataPipe`
+    pub fn new() -> DataPipe {
+        DataPipe::default()
+    }
+
+    ///
+    /// Gets access to the underlying queue
+    pub fn queue(&self) -> &RwLock<VecDeque<RowIndicator>> {
+        &self.queue
+    }
+
+    ///
+    /// Appends an indicator to the queue; returns false
+    /// if the queue could not be unlocked
+    pub fn push(&self, indicator: RowIndicator) -> bool {
+        let rows = match &indicator {
+            RowIndicator::MoreToCome(batch) => Some(batch.len() as u64),
+            RowIndicator::EndOfData => None,
+        };
+
+        match self.queue.write() {
+            Ok(mut queue_in) => queue_in.push_back(indicator),
+            Err(e) => {
+                error!(
+                    "Failed to push into queue because it could not be unlocked: {}",
+                    e
+                );
+                return false;
+            }
+        };
+
+        match rows {
+            Some(rows) => {
+                self.delivered.fetch_add(rows, Ordering::SeqCst);
+            }
+            None => self.finished.store(true, Ordering::SeqCst),
+        }
+
+        true
+    }
+
+    ///
+    /// Gets the number of rows delivered into the queue so far
+    pub fn rows_delivered(&self) -> u64 {
+        self.delivered.load(Ordering::SeqCst)
+    }
+
+    ///
+    /// Checks whether `EndOfData` has been sent
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    ///
+    /// Gets the number of entries, i.e. batches of rows, waiting in the queue
+    pub fn depth(&self) -> usize {
+        match self.queue.read() {
+            Ok(q) => q.len(),
+            Err(e) => e.into_inner().len(),
+        }
+    }
 }
 
 impl ThreadedTableData {
     ///
     /// Gets table name
-    pub fn table_name(&self) -> &str {
+    pub fn table_name(&self) -> &TableName {
         &self.table_name
     }
 
     /// Gets iterator over column definitions
-    pub fn column_defs(
-        &self,
-    ) -> std::collections::btree_map::Values<'_, std::string::String, ColumnDefinition> {
-        self.column_defs.values()
+    pub fn column_defs(&self) -> std::slice::Iter<'_, ColumnDefinition> {
+        self.column_defs.iter()
     }
     /// Get access to data pipe
-    pub fn pipe(&self) -> Arc<RwLock<VecDeque<RowIndicator>>> {
+    pub fn pipe(&self) -> Arc<DataPipe> {
         self.pipe.clone()
     }
 
-    pub fn execute(&self, conn: &dyn ThreadedDataRowProvider) -> Result<()> {
+    ///
+    /// Gets the number of rows delivered into the pipe so far
+    pub fn rows_delivered(&self) -> u64 {
+        self.pipe.rows_delivered()
+    }
+
+    ///
+    /// Checks whether all rows have been delivered
+    pub fn is_finished(&self) -> bool {
+        self.pipe.is_finished()
+    }
+
+    ///
+    /// Gets the number of entries waiting in the pipe
+    pub fn queue_depth(&self) -> usize {
+        self.pipe.depth()
+    }
+
+    ///
+    /// Gets the statement used to query the data
+    pub fn select_sql(&self) -> String {
+        self::oracle::select_statement(&self.table_name, &self.column_defs, &self.options)
+    }
+
+    ///
+    /// Queries data into the pipe and returns the number of rows produced
+    pub fn execute(&self, conn: &dyn ThreadedDataRowProvider) -> Result<u64> {
         // initiate querying data
         conn.query_data_threaded(
-            self.table_name.as_str(),
+            &self.table_name,
             self.column_defs.clone(),
+            &self.options,
             self.pipe.clone(),
-        )?;
+        )
+    }
+
+    ///
+    /// Queries data into the pipe on a thread of its own, reading
+    /// through the connection returned by `conn_factory`
+    pub fn spawn<C, F>(&self, conn_factory: F) -> LoadHandle
+    where
+        C: ThreadedDataRowProvider,
+        F: FnOnce() -> Result<C> + Send + 'static,
+    {
+        let table_name = self.table_name.clone();
+        let column_defs = self.column_defs.clone();
+        let options = self.options.clone();
+        let pipe = self.pipe.clone();
+        let producer = std::thread::spawn(move || {
+            let result = conn_factory().and_then(|conn| {
+                conn.query_data_threaded(&table_name, column_defs, &options, pipe.clone())
+            });
+            if result.is_err() {
+                // let the consumer finish with the rows delivered so far
+                pipe.push(RowIndicator::EndOfData);
+            }
+
+            result
+        });
+
+        LoadHandle {
+            pipe: self.pipe.clone(),
+            producer,
+        }
+    }
+}
+
+impl LoadHandle {
+    ///
+    /// Waits for the next batch of rows; `None` once all rows
+    /// have been received or the producer stopped
+    pub fn recv(&self) -> Option<Vec<Vec<Option<ColumnValue>>>> {
+        loop {
+            // nothing is pushed anymore once the producer is done
+            let stopped = self.producer.is_finished();
+            let next = match self.pipe.queue().write() {
+                Ok(mut q) => q.pop_front(),
+                Err(e) => e.into_inner().pop_front(),
+            };
+            match next {
+                Some(RowIndicator::MoreToCome(rows)) => return Some(rows),
+                Some(RowIndicator::EndOfData) => return None,
+                None if stopped => return None,
+                None => std::thread::sleep(RECV_PAUSE),
+            }
+        }
+    }
+
+    ///
+    /// Waits for the producer and returns the number of rows it
+    /// produced; a producer that panicked closed the pipe early
+    pub fn join(self) -> Result<u64> {
+        match self.producer.join() {
+            Ok(result) => result,
+            Err(_) => Err(Error::ChannelClosed),
+        }
+    }
+}
+
+///
+/// Gets the current system change number of the database, e.g. to
+/// export several tables as of the same point in time
+pub fn current_scn(conn: &dyn SystemChangeNumberProvider) -> Result<u64> {
+    conn.current_scn()
+}
+
+///
+/// Applies session options such as `ALTER SESSION SET
+/// NLS_DATE_FORMAT = ...` statements right after connecting
+pub fn initialize_session(conn: &dyn SessionProvider, options: &SessionOptions) -> Result<()> {
+    conn.initialize_session(options)
+}
+
+///
+/// Inserts rows into a table as one batch of array bound values; the
+/// values of each row follow the order of `columns`. Changes are not
+/// committed.
+pub fn insert_rows(
+    conn: &dyn DataRowSink,
+    table_name: &TableName,
+    columns: &[ColumnDefinition],
+    rows: &[Vec<Option<ColumnValue>>],
+) -> Result<u64> {
+    // the name ends up quoted in the insert statement
+    table_name.validate()?;
+    conn.insert_rows(table_name, columns, rows)
+}
+
+///
+/// Lists accessible tables, optionally restricted to an owner
+/// and a table name pattern in `LIKE` syntax
+pub fn list_tables(
+    conn: &dyn TableListProvider,
+    owner: Option<&str>,
+    pattern: Option<&str>,
+) -> Result<Vec<TableInfo>> {
+    conn.list_tables(owner, pattern)
+}
+
+///
+/// Lists the partitions of a table in partition order
+pub fn list_partitions(
+    conn: &dyn PartitionListProvider,
+    table_name: &TableName,
+) -> Result<Vec<PartitionInfo>> {
+    conn.list_partitions(table_name)
+}
 
-        Ok(())
+///
+/// Reads all column definitions of a table
+pub fn describe_table(
+    conn: &dyn ColumnDataProvider,
+    table_name: &TableName,
+) -> Result<Vec<ColumnDefinition>> {
+    let columns = conn.query_column_data(table_name)?;
+    if columns.is_empty() && !conn.table_exists(table_name)? {
+        return Err(crate::Error::TableNotFound(table_name.to_string()));
     }
+
+    Ok(columns)
 }
 
 impl TableDefinition {
     ///
     /// Get header definition
     pub fn header(&self) -> Vec<String> {
-        self.columns.keys().cloned().collect()
+        self.columns.iter().map(|c| c.column_name.clone()).collect()
+    }
+
+    ///
+    /// Gets the definitions of the selected columns in query order
+    pub fn columns(&self) -> &[ColumnDefinition] {
+        &self.columns
+    }
+
+    ///
+    /// Gets the columns of the primary key; `None` if the table has none
+    pub fn primary_key(&self) -> Option<&[String]> {
+        self.constraints
+            .iter()
+            .find(|c| c.kind == ConstraintKind::PrimaryKey)
+            .map(|c| c.columns.as_slice())
+    }
+
+    ///
+    /// Gets the columns of each unique constraint
+    pub fn unique_keys(&self) -> Vec<&[String]> {
+        self.constraints
+            .iter()
+            .filter(|c| c.kind == ConstraintKind::Unique)
+            .map(|c| c.columns.as_slice())
+            .collect()
+    }
+
+    ///
+    /// Gets the statement `load` and `load_threaded` will execute,
+    /// with placeholders in place of bound values
+    pub fn select_sql(&self) -> String {
+        self::oracle::select_statement(&self.table_name, &self.columns, &self.options)
+    }
+
+    ///
+    /// Estimates the number of rows from table statistics;
+    /// `None` if statistics have not been gathered
+    pub fn estimate_rows(&self, conn: &dyn TableStatisticsProvider) -> Result<Option<u64>> {
+        conn.estimate_rows(&self.table_name)
     }
     ///
     /// Loads table and returns `TableData`
     pub fn load(self, conn: &dyn DataRowProvider) -> Result<TableData> {
         let mut table_data = TableData {
             table_name: self.table_name,
-            column_defs: Rc::new(self.columns),
+            column_defs: Arc::new(self.columns),
+            options: self.options,
             data: Vec::new(),
         };
 
         let data = conn.query_data(
-            table_data.table_name.as_str(),
+            &table_data.table_name,
             table_data.column_defs.clone(),
+            &table_data.options,
         )?;
         table_data.data = data;
 
         Ok(table_data)
     }
 
+    ///
+    /// Reads the first `rows` rows and compares their values with the
+    /// declared column types, e.g. to find NUMBER columns carrying
+    /// more decimals than declared before starting a long export
+    pub fn sample(&self, conn: &dyn DataRowProvider, rows: u64) -> Result<Vec<TypeDrift>> {
+        let options = QueryOptions {
+            row_limit: Some(rows),
+            ..self.options.clone()
+        };
+        let data = conn.query_data(&self.table_name, Arc::new(self.columns.clone()), &options)?;
+
+        Ok(self::sample::check_rows(&data))
+    }
+
     pub fn load_threaded(self) -> Result<ThreadedTableData> {
         // Create threaded data structure
         let threaded_data = ThreadedTableData {
             table_name: self.table_name,
-            column_defs: Rc::new(self.columns),
-            pipe: Arc::new(RwLock::new(VecDeque::new())),
+            column_defs: Arc::new(self.columns),
+            options: self.options,
+            pipe: Arc::new(DataPipe::new()),
         };
         // return pipe
         Ok(threaded_data)
@@ -204,16 +669,17 @@ impl TableData {
 
     ///
     /// Gets iterator over column definitions
-    pub fn column_defs(
-        &self,
-    ) -> std::collections::btree_map::Values<'_, std::string::String, ColumnDefinition> {
-        self.column_defs.values()
+    pub fn column_defs(&self) -> std::slice::Iter<'_, ColumnDefinition> {
+        self.column_defs.iter()
     }
 
     ///
     /// Get header definition
     pub fn header(&self) -> Vec<String> {
-        self.column_defs.keys().cloned().collect()
+        self.column_defs
+            .iter()
+            .map(|c| c.column_name.clone())
+            .collect()
     }
 }
 
@@ -228,14 +694,16 @@ impl Serialize for ColumnValue {
         match self {
             ColumnValue::Boolean(v) => serializer.serialize_bool(*v),
             ColumnValue::Date(v) => {
-                serializer.serialize_str(v.format("%Y-%m-%d").to_string().as_str())
+                serializer.serialize_str(v.format(DEFAULT_DATE_FORMAT).to_string().as_str())
             }
             ColumnValue::DateTime(v) => {
-                serializer.serialize_str(v.format("%Y-%m-%d %H:%M:%S").to_string().as_str())
+                serializer.serialize_str(v.format(DEFAULT_DATETIME_FORMAT).to_string().as_str())
             }
             ColumnValue::Number(v) => serializer.serialize_i64(*v),
+            ColumnValue::Decimal(v) => serializer.serialize_str(v.as_str()),
             ColumnValue::Float(v) => serializer.serialize_f64(*v),
             ColumnValue::Varchar(v) => serializer.serialize_str(v.as_str()),
+            ColumnValue::Binary(v) => serializer.serialize_bytes(v),
         }
     }
 }
@@ -265,7 +733,7 @@ impl Serialize for TableData {
         // add header
         let column_names: Vec<&str> = self
             .column_defs
-            .values()
+            .iter()
             .map(|df| df.column_name.as_str())
             .collect();
         seq.serialize_element(&column_names)?;
@@ -281,15 +749,456 @@ impl Serialize for TableData {
 impl DataRow {
     ///
     /// Get column definitions for row
-    pub fn column_defs(&self) -> Rc<BTreeMap<String, ColumnDefinition>> {
+    pub fn column_defs(&self) -> Arc<Vec<ColumnDefinition>> {
         self.column_defs.clone()
     }
+
+    ///
+    /// Gets all values in column order
+    pub fn values(&self) -> &[Option<ColumnValue>] {
+        &self.column_values
+    }
+
+    ///
+    /// Gets the value of a column by name; `None` if the value is NULL
+    /// or the row has no such column
+    pub fn get(&self, column_name: &str) -> Option<&ColumnValue> {
+        self.column_defs
+            .iter()
+            .position(|col| col.column_name == column_name)
+            .and_then(|idx| self.column_values[idx].as_ref())
+    }
+
+    ///
+    /// Iterates over column names and values in column order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&ColumnValue>)> {
+        self.column_defs
+            .iter()
+            .map(|col| col.column_name.as_str())
+            .zip(self.column_values.iter().map(|value| value.as_ref()))
+    }
+}
+
+/// Largest number of decimal digits that always fits into an `i64`
+const MAX_INTEGER_DIGITS: i64 = 18;
+
+impl DataType {
+    ///
+    /// Checks whether values of this type are integers that fit into an `i64`.
+    /// This is decided by scale; NUMBER without precision may hold up to
+    /// 38 digits and is never considered an integer.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            DataType::Number(Some(precision), Some(scale)) => {
+                *scale <= 0 && i64::from(*precision) - i64::from(*scale) <= MAX_INTEGER_DIGITS
+            }
+            _ => false,
+        }
+    }
+}
+
+impl QueryOptions {
+    ///
+    /// Gets whether duplicate rows are removed by the database
+    pub fn distinct(&self) -> bool {
+        self.distinct
+    }
+
+    ///
+    /// Gets whether the result is staged before it is fetched
+    pub fn materialize(&self) -> bool {
+        self.materialize
+    }
+    ///
+    /// Gets whether decimal NUMBER columns are read as f64
+    pub fn float_numbers(&self) -> bool {
+        self.float_numbers
+    }
+
+    ///
+    /// Gets the column and lower bound rows are restricted to
+    pub fn newer_than(&self) -> Option<(&str, &ColumnValue)> {
+        self.newer_than
+            .as_ref()
+            .map(|(column, value)| (column.as_str(), value))
+    }
+
+    ///
+    /// Gets the system change number rows must have changed after
+    pub fn changed_since_scn(&self) -> Option<u64> {
+        self.changed_since_scn
+    }
+
+    ///
+    /// Gets the partition or subpartition rows are read from
+    pub fn partition(&self) -> Option<&TablePartition> {
+        self.partition.as_ref()
+    }
+
+    ///
+    /// Gets the column with inclusive start and exclusive end rows are restricted to
+    pub fn range(&self) -> Option<(&str, &BindValue, &BindValue)> {
+        self.range
+            .as_ref()
+            .map(|(column, start, end)| (column.as_str(), start, end))
+    }
+
+    ///
+    /// Gets the system change number rows are read as of
+    pub fn as_of_scn(&self) -> Option<u64> {
+        self.as_of_scn
+    }
+
+    ///
+    /// Gets the number of rows fetched per round trip
+    pub fn fetch_size(&self) -> Option<u32> {
+        self.fetch_size
+    }
+
+    ///
+    /// Gets the number of rows pushed into the pipe at once
+    pub fn batch_size(&self) -> usize {
+        self.batch_size.unwrap_or(DEFAULT_BATCH_SIZE) as usize
+    }
+
+    ///
+    /// Gets the resident memory limit of the reader in bytes
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    ///
+    /// Gets the column and the key values rows are restricted to
+    pub fn keys(&self) -> Option<(&str, &[BindValue])> {
+        self.keys
+            .as_ref()
+            .map(|(column, values)| (column.as_str(), values.as_slice()))
+    }
+
+    ///
+    /// Gets the additional WHERE condition
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    ///
+    /// Gets the values bound to the filter placeholders
+    pub fn binds(&self) -> &[BindValue] {
+        &self.binds
+    }
+
+    ///
+    /// Gets the maximum number of rows to read
+    pub fn row_limit(&self) -> Option<u64> {
+        self.row_limit
+    }
+
+    ///
+    /// Gets the percentage of rows sampled and the optional sample seed
+    pub fn sample(&self) -> Option<(f64, Option<u32>)> {
+        self.sample
+    }
+
+    ///
+    /// Gets the aggregate expression selected as the given column
+    pub fn aggregate(&self, column_name: &str) -> Option<&str> {
+        self.aggregates
+            .iter()
+            .find(|(_, alias)| alias == column_name)
+            .map(|(expression, _)| expression.as_str())
+    }
+
+    ///
+    /// Gets the aggregate or computed expression selected as the given column
+    pub fn expression(&self, column_name: &str) -> Option<&str> {
+        self.aggregates
+            .iter()
+            .chain(&self.computed)
+            .find(|(_, alias)| alias == column_name)
+            .map(|(expression, _)| expression.as_str())
+    }
+
+    ///
+    /// Gets the computed expressions and the column names they are selected as
+    pub fn computed(&self) -> &[(String, String)] {
+        &self.computed
+    }
+
+    ///
+    /// Gets the aggregate expressions and the column names they are selected as
+    pub fn aggregates(&self) -> &[(String, String)] {
+        &self.aggregates
+    }
+
+    ///
+    /// Gets the data type the given column is cast to
+    pub fn type_override(&self, column_name: &str) -> Option<&DataType> {
+        self.type_overrides
+            .iter()
+            .find(|(name, _)| name == column_name)
+            .map(|(_, data_type)| data_type)
+    }
+
+    ///
+    /// Gets the columns cast to another data type
+    pub fn type_overrides(&self) -> &[(String, DataType)] {
+        &self.type_overrides
+    }
+
+    ///
+    /// Gets the columns rows are grouped by
+    pub fn group_by(&self) -> &[String] {
+        &self.group_by
+    }
+}
+
+impl From<&str> for BindValue {
+    fn from(value: &str) -> BindValue {
+        BindValue::Varchar(String::from(value))
+    }
+}
+
+impl From<String> for BindValue {
+    fn from(value: String) -> BindValue {
+        BindValue::Varchar(value)
+    }
+}
+
+impl From<i64> for BindValue {
+    fn from(value: i64) -> BindValue {
+        BindValue::Number(value)
+    }
+}
+
+impl From<f64> for BindValue {
+    fn from(value: f64) -> BindValue {
+        BindValue::Float(value)
+    }
+}
+
+impl From<bool> for BindValue {
+    fn from(value: bool) -> BindValue {
+        BindValue::Boolean(value)
+    }
+}
+
+impl From<DateTime<Utc>> for BindValue {
+    fn from(value: DateTime<Utc>) -> BindValue {
+        BindValue::DateTime(value)
+    }
 }
 
 impl ColumnDefinition {
+    ///
+    /// Gets column name
+    pub fn column_name(&self) -> &str {
+        &self.column_name
+    }
+
     ///
     /// Gets nullable status for column
     pub fn nullable(&self) -> bool {
         self.nullable
     }
+
+    ///
+    /// Gets data type of column
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+}
+
+impl TableInfo {
+    ///
+    /// Gets owning schema
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    ///
+    /// Gets table name
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    ///
+    /// Gets row count from statistics; `None` if not gathered
+    pub fn num_rows(&self) -> Option<u64> {
+        self.num_rows
+    }
+}
+
+impl Constraint {
+    ///
+    /// Gets the constraint name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    ///
+    /// Gets whether this is a primary key or unique constraint
+    pub fn kind(&self) -> ConstraintKind {
+        self.kind
+    }
+
+    ///
+    /// Gets the constrained columns in key order
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+impl PartitionInfo {
+    ///
+    /// Gets owning schema
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    ///
+    /// Gets the name of the partitioned table
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    ///
+    /// Gets partition name
+    pub fn partition_name(&self) -> &str {
+        &self.partition_name
+    }
+
+    ///
+    /// Gets the position of the partition, starting at 1
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+
+    ///
+    /// Gets the partition bound as written in the table definition,
+    /// e.g. `TO_DATE(' 2024-02-01 00:00:00', ...)` for range partitions
+    pub fn high_value(&self) -> Option<&str> {
+        self.high_value.as_deref()
+    }
+
+    ///
+    /// Gets row count from statistics; `None` if not gathered
+    pub fn num_rows(&self) -> Option<u64> {
+        self.num_rows
+    }
+
+    ///
+    /// Gets the number of subpartitions
+    pub fn subpartition_count(&self) -> u32 {
+        self.subpartition_count
+    }
+}
+
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataType::VarChar(len) => write!(f, "VARCHAR2({})", len),
+            DataType::Number(Some(p), Some(s)) => write!(f, "NUMBER({},{})", p, s),
+            DataType::Number(Some(p), None) => write!(f, "NUMBER({})", p),
+            DataType::Number(None, Some(s)) => write!(f, "NUMBER(*,{})", s),
+            DataType::Number(None, None) => write!(f, "NUMBER"),
+            DataType::Float(p) => write!(f, "FLOAT({})", p),
+            DataType::BinaryFloat => write!(f, "BINARY_FLOAT"),
+            DataType::BinaryDouble => write!(f, "BINARY_DOUBLE"),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Date => write!(f, "DATE"),
+            DataType::CLob => write!(f, "CLOB"),
+            DataType::BLob => write!(f, "BLOB"),
+            DataType::DateTime => write!(f, "TIMESTAMP"),
+            DataType::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::meta::ThreadedDataRowProvider;
+    use super::{
+        ColumnDefinition, ColumnValue, DataPipe, DataRow, DataType, QueryOptions, RowIndicator,
+        TableData, TableDefinition, TableName, ThreadedTableData,
+    };
+    use crate::{Error, Result};
+    use std::sync::Arc;
+
+    /// pushes a fixed number of rows in batches of two
+    struct Producer(i64);
+
+    impl ThreadedDataRowProvider for Producer {
+        fn query_data_threaded(
+            &self,
+            _table_name: &TableName,
+            _column_names: Arc<Vec<ColumnDefinition>>,
+            _options: &QueryOptions,
+            q: Arc<DataPipe>,
+        ) -> Result<u64> {
+            let rows: Vec<Vec<Option<ColumnValue>>> = (0..self.0)
+                .map(|n| vec![Some(ColumnValue::Number(n))])
+                .collect();
+            for batch in rows.chunks(2) {
+                q.push(RowIndicator::MoreToCome(batch.to_vec()));
+            }
+            q.push(RowIndicator::EndOfData);
+
+            Ok(self.0 as u64)
+        }
+    }
+
+    fn table_def() -> TableDefinition {
+        TableDefinition {
+            table_name: TableName::parse("T"),
+            columns: vec![ColumnDefinition {
+                column_name: String::from("ID"),
+                nullable: false,
+                data_type: DataType::Number(Some(10), Some(0)),
+            }],
+            options: QueryOptions::default(),
+            constraints: Vec::new(),
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_data_is_shareable() {
+        assert_send_sync::<DataRow>();
+        assert_send_sync::<TableData>();
+        assert_send_sync::<ThreadedTableData>();
+    }
+
+    #[test]
+    fn test_spawn() {
+        let data = table_def().load_threaded().unwrap();
+        let handle = data.spawn(|| Ok(Producer(5)));
+
+        let mut received = Vec::new();
+        while let Some(rows) = handle.recv() {
+            received.extend(rows);
+        }
+        assert_eq!(5, handle.join().unwrap());
+        assert_eq!(Some(ColumnValue::Number(4)), received[4][0]);
+        assert_eq!(5, received.len());
+        assert!(data.is_finished());
+    }
+
+    #[test]
+    fn test_spawn_connect_failure() {
+        let data = table_def().load_threaded().unwrap();
+        let handle =
+            data.spawn(|| -> Result<Producer> { Err(Error::TableNotFound(String::from("T"))) });
+
+        assert!(handle.recv().is_none());
+        assert!(matches!(handle.join(), Err(Error::TableNotFound(_))));
+    }
+    #[test]
+    fn test_spawn_producer_panic() {
+        let data = table_def().load_threaded().unwrap();
+        let handle = data.spawn(|| -> Result<Producer> { panic!("connection lost") });
+
+        assert!(handle.recv().is_none());
+        assert!(matches!(handle.join(), Err(Error::ChannelClosed)));
+    }
 }
@@ -28,46 +28,459 @@
 //! Oracle implementation for meta
 //!
 
-use super::meta::{ColumnDataProvider, DataRowProvider, ThreadedDataRowProvider};
-use super::{ColumnDefinition, ColumnValue, DataRow, DataType, RowIndicator};
-use crate::Error;
-use crate::Result;
+use super::identifier::{quote_identifier, TableName};
+use super::memory::{wait_for_memory, MEMORY_CHECK_INTERVAL};
+use super::meta::{
+    ColumnDataProvider, DataRowProvider, DataRowSink, PartitionListProvider, SessionProvider,
+    SystemChangeNumberProvider, TableListProvider, TableStatisticsProvider,
+    ThreadedDataRowProvider,
+};
+use super::{
+    BindValue, ColumnDefinition, ColumnValue, Constraint, ConstraintKind, DataPipe, DataRow,
+    DataType, PartitionInfo, QueryOptions, RowIndicator, SessionOptions, TableInfo, TablePartition,
+};
+use crate::{Error, Result};
 use chrono::{DateTime, Utc};
-use std::collections::{BTreeMap, VecDeque};
-use std::rc::Rc;
-use std::sync::{Arc, RwLock};
+use oracle::sql_type::{OracleType, ToSql};
+use std::collections::BTreeSet;
+use std::sync::Arc;
 
-impl ColumnDataProvider for oracle::Connection {
-    fn query_column_data(&self, table_name: &str) -> Result<Vec<ColumnDefinition>> {
-        let mut owner: Option<String> = None;
+///
+/// Converts column information from `ALL_TAB_COLUMNS` into a `DataType`
+fn map_data_type(
+    data_type: &str,
+    data_length: Option<u32>,
+    char_length: Option<u32>,
+    data_precision: Option<u32>,
+    data_scale: Option<i32>,
+) -> DataType {
+    match data_type {
+        "NUMBER" => DataType::Number(data_precision, data_scale),
+        // FLOAT precision is given in binary digits
+        "FLOAT" => DataType::Float(data_precision.unwrap_or(126)),
+        "BINARY_FLOAT" => DataType::BinaryFloat,
+        "BINARY_DOUBLE" => DataType::BinaryDouble,
+        "VARCHAR2" => DataType::VarChar(data_length.unwrap_or(0)),
+        // national character types store DATA_LENGTH in bytes
+        "NVARCHAR2" | "NCHAR" => DataType::VarChar(char_length.unwrap_or(0)),
+        "DATE" => DataType::Date,
+        "TIMESTAMP(6)" => DataType::DateTime,
+        "BOOL" => DataType::Boolean,
+        "CLOB" | "NCLOB" => DataType::CLob,
+        "BLOB" => DataType::BLob,
+        x => DataType::Other(String::from(x)),
+    }
+}
 
-        // check whether owner is specified in front of table name
-        let t_name: String = if let Some(cut_index) = table_name.find('.') {
-            debug!("Owner included in table name. Separating.");
+/// Oracle accepts at most 1000 expressions in an `IN` list
+const KEY_BATCH_SIZE: usize = 1000;
 
-            let mut dupl: String = String::from(table_name);
+///
+/// Converts the type of a result column into a `DataType`
+fn map_oracle_type(oracle_type: &OracleType) -> DataType {
+    match oracle_type {
+        // aggregates return NUMBER without precision, reported as 0 and scale -127
+        OracleType::Number(precision, scale) => DataType::Number(
+            Some(u32::from(*precision)).filter(|p| *p > 0),
+            Some(i32::from(*scale)).filter(|s| *s != -127),
+        ),
+        OracleType::Float(precision) => DataType::Float(u32::from(*precision)),
+        OracleType::BinaryFloat => DataType::BinaryFloat,
+        OracleType::BinaryDouble => DataType::BinaryDouble,
+        OracleType::Varchar2(length)
+        | OracleType::NVarchar2(length)
+        | OracleType::Char(length)
+        | OracleType::NChar(length) => DataType::VarChar(*length),
+        OracleType::Date => DataType::Date,
+        OracleType::Timestamp(_) => DataType::DateTime,
+        OracleType::Boolean => DataType::Boolean,
+        OracleType::CLOB | OracleType::NCLOB => DataType::CLob,
+        OracleType::BLOB => DataType::BLob,
+        other => DataType::Other(other.to_string()),
+    }
+}
 
-            let new_name: String = dupl.split_off(cut_index + 1);
-            // split out point
-            let _ = dupl.split_off(cut_index);
+///
+/// Gets the select list expression for a column
+fn select_expression(col_item: &ColumnDefinition, options: &QueryOptions) -> String {
+    let column_name = quote_identifier(&col_item.column_name);
+    // aggregates and computed columns are selected under their column name
+    let source = options.expression(&col_item.column_name);
 
-            debug!("Identified owner [{}]", &dupl);
-            owner = Some(dupl);
+    if let Some(data_type) = options.type_override(&col_item.column_name) {
+        return format!(
+            "CAST({} AS {}) AS {}",
+            source.unwrap_or(&column_name),
+            data_type,
+            column_name
+        );
+    }
+    match (&col_item.data_type, source) {
+        // unsupported types are converted into text on the database side
+        (DataType::Other(_), Some(expression)) => {
+            format!("TO_CHAR({}) AS {}", expression, column_name)
+        }
+        (DataType::Other(_), None) => format!("TO_CHAR({}) AS {}", column_name, column_name),
+        (_, Some(expression)) => format!("{} AS {}", expression, column_name),
+        (_, None) => column_name,
+    }
+}
 
-            debug!("Identified table name [{}]", &new_name);
+///
+/// Builds the data query for a table and its columns
+pub(crate) fn select_statement(
+    table_name: &TableName,
+    column_names: &[ColumnDefinition],
+    options: &QueryOptions,
+) -> String {
+    // collect column expressions into comma separated string
+    let column_str: String = column_names
+        .iter()
+        .map(|col_item| select_expression(col_item, options))
+        .collect::<Vec<String>>()
+        .join(",");
 
-            new_name
-        } else {
-            String::from(table_name)
-        };
-        // construct query statement for getting column data
-        let query: &str = match &owner {
-            None => {
-                r#"SELECT COLUMN_NAME, NULLABLE, DATA_TYPE, DATA_LENGTH, DATA_PRECISION FROM ALL_TAB_COLUMNS WHERE TABLE_NAME=:1"#
-            }
-            Some(_) => {
-                r#"SELECT COLUMN_NAME, NULLABLE, DATA_TYPE, DATA_LENGTH, DATA_PRECISION FROM ALL_TAB_COLUMNS WHERE TABLE_NAME=:1 AND OWNER=:2"#
+    // placeholders are numbered in the order of `bind_params`
+    let mut placeholder: usize = 0;
+    let mut next_placeholder = || {
+        placeholder += 1;
+        placeholder
+    };
+
+    let table = table_name.reference();
+    // the partition belongs to the table, a flashback clause follows it
+    let table = match options.partition() {
+        Some(TablePartition::Partition(name)) => {
+            format!("{} PARTITION ({})", table, quote_identifier(name))
+        }
+        Some(TablePartition::Subpartition(name)) => {
+            format!("{} SUBPARTITION ({})", table, quote_identifier(name))
+        }
+        None => table,
+    };
+    // the sample clause is written as a literal, Oracle does not bind it
+    let table = match options.sample() {
+        Some((percent, Some(seed))) => format!("{} SAMPLE ({}) SEED ({})", table, percent, seed),
+        Some((percent, None)) => format!("{} SAMPLE ({})", table, percent),
+        None => table,
+    };
+    let source = match options.as_of_scn() {
+        Some(_) => format!("{} AS OF SCN :{}", table, next_placeholder()),
+        None => table,
+    };
+
+    let mut predicates: Vec<String> = Vec::new();
+    if let Some((column_name, _)) = options.newer_than() {
+        predicates.push(format!(
+            "{} > :{}",
+            quote_identifier(column_name),
+            next_placeholder()
+        ));
+    }
+    if options.changed_since_scn().is_some() {
+        predicates.push(format!("ORA_ROWSCN > :{}", next_placeholder()));
+    }
+    if let Some((column_name, _, _)) = options.range() {
+        let column_name = quote_identifier(column_name);
+        let start = next_placeholder();
+        predicates.push(format!(
+            "{} >= :{} AND {} < :{}",
+            column_name,
+            start,
+            column_name,
+            next_placeholder()
+        ));
+    }
+    if let Some((column_name, values)) = options.keys() {
+        let column_name = quote_identifier(column_name);
+        // longer key lists are split into several IN lists
+        let batches: Vec<String> = values
+            .chunks(KEY_BATCH_SIZE)
+            .map(|batch| {
+                let placeholders: Vec<String> = batch
+                    .iter()
+                    .map(|_| format!(":{}", next_placeholder()))
+                    .collect();
+                format!("{} IN ({})", column_name, placeholders.join(","))
+            })
+            .collect();
+        predicates.push(format!("({})", batches.join(" OR ")));
+    }
+    // filter placeholders are bound by position after the ones above
+    if let Some(filter) = options.filter() {
+        predicates.push(format!("({})", filter));
+    }
+
+    // hints have to follow the SELECT keyword
+    let select = match (options.materialize(), options.distinct()) {
+        (true, true) => "SELECT /*+ MATERIALIZE */ DISTINCT",
+        (true, false) => "SELECT /*+ MATERIALIZE */",
+        (false, true) => "SELECT DISTINCT",
+        (false, false) => "SELECT",
+    };
+    let mut statement = if predicates.is_empty() {
+        format!(r#"{} {} FROM {}"#, select, column_str, source)
+    } else {
+        format!(
+            r#"{} {} FROM {} WHERE {}"#,
+            select,
+            column_str,
+            source,
+            predicates.join(" AND ")
+        )
+    };
+    if !options.group_by().is_empty() {
+        let columns: Vec<String> = options
+            .group_by()
+            .iter()
+            .map(|cn| quote_identifier(cn))
+            .collect();
+        statement.push_str(&format!(" GROUP BY {}", columns.join(",")));
+    }
+    if options.row_limit().is_some() {
+        statement.push_str(&format!(" FETCH FIRST :{} ROWS ONLY", next_placeholder()));
+    }
+    if options.materialize() {
+        // the hinted query block is only materialized as a named subquery
+        statement = format!(r#"WITH "STAGED" AS ({}) SELECT * FROM "STAGED""#, statement);
+    }
+
+    statement
+}
+
+///
+/// Gets the bindable content of a `BindValue`
+fn bind_value(value: &BindValue) -> &dyn ToSql {
+    match value {
+        BindValue::Varchar(v) | BindValue::Decimal(v) => v,
+        BindValue::Float(v) => v,
+        BindValue::Number(v) => v,
+        BindValue::Boolean(v) => v,
+        BindValue::Date(v) | BindValue::DateTime(v) => v,
+    }
+}
+
+/// typed NULL values, so that a column keeps its bind type across rows
+static NULL_TEXT: Option<String> = None;
+static NULL_INTEGER: Option<i64> = None;
+static NULL_FLOAT: Option<f64> = None;
+static NULL_BOOLEAN: Option<bool> = None;
+static NULL_TIMESTAMP: Option<DateTime<Utc>> = None;
+static NULL_BYTES: Option<Vec<u8>> = None;
+
+///
+/// Builds an `INSERT` statement with one placeholder per column
+fn insert_statement(table_name: &TableName, columns: &[ColumnDefinition]) -> String {
+    let column_list: Vec<String> = columns
+        .iter()
+        .map(|col| quote_identifier(&col.column_name))
+        .collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|idx| format!(":{}", idx)).collect();
+
+    format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table_name.reference(),
+        column_list.join(","),
+        placeholders.join(",")
+    )
+}
+
+///
+/// Gets the bindable content of a value to insert into a column
+fn insert_value<'a>(value: &'a Option<ColumnValue>, data_type: &DataType) -> &'a dyn ToSql {
+    match (value, data_type) {
+        (Some(ColumnValue::Varchar(v)), _) | (Some(ColumnValue::Decimal(v)), _) => v,
+        (Some(ColumnValue::Float(v)), _) => v,
+        (Some(ColumnValue::Number(v)), _) => v,
+        (Some(ColumnValue::Boolean(v)), _) => v,
+        (Some(ColumnValue::Date(v)), _) | (Some(ColumnValue::DateTime(v)), _) => v,
+        (Some(ColumnValue::Binary(v)), _) => v,
+        (None, DataType::Number(_, _)) if data_type.is_integer() => &NULL_INTEGER,
+        (None, DataType::Float(_)) | (None, DataType::BinaryFloat) => &NULL_FLOAT,
+        (None, DataType::BinaryDouble) => &NULL_FLOAT,
+        (None, DataType::Boolean) => &NULL_BOOLEAN,
+        (None, DataType::Date) | (None, DataType::DateTime) => &NULL_TIMESTAMP,
+        (None, DataType::BLob) => &NULL_BYTES,
+        (None, _) => &NULL_TEXT,
+    }
+}
+
+///
+/// Collects bind parameters matching the placeholders of `select_statement`
+fn bind_params(options: &QueryOptions) -> Vec<&dyn ToSql> {
+    let mut params: Vec<&dyn ToSql> = Vec::new();
+    if let Some(scn) = &options.as_of_scn {
+        params.push(scn);
+    }
+    if let Some((_, value)) = options.newer_than() {
+        params.push(match value {
+            ColumnValue::Varchar(v) | ColumnValue::Decimal(v) => v,
+            ColumnValue::Float(v) => v,
+            ColumnValue::Number(v) => v,
+            ColumnValue::Boolean(v) => v,
+            ColumnValue::Date(v) | ColumnValue::DateTime(v) => v,
+            ColumnValue::Binary(v) => v,
+        });
+    }
+    if let Some(scn) = &options.changed_since_scn {
+        params.push(scn);
+    }
+    if let Some((_, start, end)) = options.range() {
+        params.push(bind_value(start));
+        params.push(bind_value(end));
+    }
+    if let Some((_, values)) = options.keys() {
+        for value in values {
+            params.push(bind_value(value));
+        }
+    }
+    for value in options.binds() {
+        params.push(bind_value(value));
+    }
+    if let Some(rows) = &options.row_limit {
+        params.push(rows);
+    }
+
+    params
+}
+
+///
+/// Prepares a data query with the configured fetch size
+fn prepare<'conn>(
+    conn: &'conn oracle::Connection,
+    query: &str,
+    options: &QueryOptions,
+) -> Result<oracle::Statement<'conn>> {
+    let mut builder = conn.statement(query);
+    if let Some(rows) = options.fetch_size() {
+        builder.fetch_array_size(rows);
+    }
+
+    Ok(builder.build()?)
+}
+
+///
+/// Adds the leading zero Oracle omits in numbers like `.5`
+fn normalize_decimal(value: String) -> String {
+    if let Some(fraction) = value.strip_prefix("-.") {
+        format!("-0.{}", fraction)
+    } else if value.starts_with('.') {
+        format!("0{}", value)
+    } else {
+        value
+    }
+}
+
+///
+/// Reads a single column value from a result row
+fn read_value(
+    row: &oracle::Row,
+    col_item: &ColumnDefinition,
+    options: &QueryOptions,
+) -> std::result::Result<Option<ColumnValue>, oracle::Error> {
+    let column_name = col_item.column_name.as_str();
+
+    Ok(match col_item.data_type {
+        DataType::VarChar(_) | DataType::CLob | DataType::Other(_) => {
+            let data: Option<String> = row.get(column_name)?;
+
+            data.map(ColumnValue::Varchar)
+        }
+        DataType::Number(_, _) => {
+            if col_item.data_type.is_integer() {
+                let data: Option<i64> = row.get(column_name)?;
+
+                data.map(ColumnValue::Number)
+            } else if options.float_numbers() {
+                let data: Option<f64> = row.get(column_name)?;
+
+                data.map(ColumnValue::Float)
+            } else {
+                // read as text to keep the exact database value
+                let data: Option<String> = row.get(column_name)?;
+
+                data.map(|v| ColumnValue::Decimal(normalize_decimal(v)))
             }
+        }
+        DataType::Float(_) | DataType::BinaryDouble => {
+            let data: Option<f64> = row.get(column_name)?;
+
+            data.map(ColumnValue::Float)
+        }
+        DataType::BinaryFloat => {
+            let data: Option<f32> = row.get(column_name)?;
+
+            data.map(|v| ColumnValue::Float(f64::from(v)))
+        }
+        DataType::Boolean => {
+            let data: Option<bool> = row.get(column_name)?;
+
+            data.map(ColumnValue::Boolean)
+        }
+        DataType::Date => {
+            let data: Option<DateTime<Utc>> = row.get(column_name)?;
+
+            data.map(ColumnValue::Date)
+        }
+        DataType::DateTime => {
+            let data: Option<DateTime<Utc>> = row.get(column_name)?;
+
+            data.map(ColumnValue::DateTime)
+        }
+        DataType::BLob => {
+            let data: Option<Vec<u8>> = row.get(column_name)?;
+
+            data.map(ColumnValue::Binary)
+        }
+    })
+}
+
+///
+/// Fetches the `row_number`th row of `table_name` and reads its values;
+/// errors name the table, row and column involved
+fn read_row(
+    row_result: std::result::Result<oracle::Row, oracle::Error>,
+    table_name: &TableName,
+    row_number: u64,
+    column_names: &[ColumnDefinition],
+    options: &QueryOptions,
+) -> Result<Vec<Option<ColumnValue>>> {
+    let row = row_result.map_err(|source| Error::RowFetch {
+        table: table_name.to_string(),
+        row: row_number,
+        source: Box::new(source),
+    })?;
+
+    column_names
+        .iter()
+        .map(|col_item| {
+            read_value(&row, col_item, options).map_err(|source| Error::ColumnRead {
+                table: table_name.to_string(),
+                column: col_item.column_name.clone(),
+                row: row_number,
+                source: Box::new(source),
+            })
+        })
+        .collect()
+}
+
+impl ColumnDataProvider for oracle::Connection {
+    fn query_column_data(&self, table_name: &TableName) -> Result<Vec<ColumnDefinition>> {
+        // remote tables are described by the dictionary of the remote database
+        let link = table_name.link_suffix();
+        let owner = table_name.owner().map(String::from);
+        let t_name = String::from(table_name.table());
+
+        // construct query statement for getting column data
+        let query = match &owner {
+            None => format!(
+                r#"SELECT OWNER, COLUMN_NAME, NULLABLE, DATA_TYPE, DATA_LENGTH, CHAR_LENGTH, DATA_PRECISION, DATA_SCALE FROM ALL_TAB_COLUMNS{} WHERE TABLE_NAME=:1 ORDER BY OWNER, COLUMN_ID"#,
+                link
+            ),
+            Some(_) => format!(
+                r#"SELECT COLUMN_NAME, NULLABLE, DATA_TYPE, DATA_LENGTH, CHAR_LENGTH, DATA_PRECISION, DATA_SCALE FROM ALL_TAB_COLUMNS{} WHERE TABLE_NAME=:1 AND OWNER=:2 ORDER BY COLUMN_ID"#,
+                link
+            ),
         };
 
         debug!("Attempting query: {}", query);
@@ -78,13 +491,14 @@ impl ColumnDataProvider for oracle::Connection {
 
         // query data from database
         let rows = match &owner {
-            None => self.query(query, &[&t_name])?,
-            Some(o) => self.query(query, &[&t_name.to_string(), &o.to_string()])?,
+            None => self.query(&query, &[&t_name])?,
+            Some(o) => self.query(&query, &[&t_name.to_string(), &o.to_string()])?,
         };
 
         debug!("Got rows in return.");
 
         let mut result_vec: Vec<ColumnDefinition> = Vec::new();
+        let mut owners: BTreeSet<String> = BTreeSet::new();
 
         debug!("Iterating {} rows...", result_vec.len());
 
@@ -92,6 +506,10 @@ impl ColumnDataProvider for oracle::Connection {
             debug!("Attempting to resolve result set.");
             let row = row_result?;
 
+            if owner.is_none() {
+                owners.insert(row.get("OWNER")?);
+            }
+
             debug!("Getting column name.");
             let column_name: String = row.get("COLUMN_NAME")?;
             let nullable_str: String = row.get("NULLABLE")?;
@@ -99,21 +517,24 @@ impl ColumnDataProvider for oracle::Connection {
             let data_type: String = row.get("DATA_TYPE")?;
             debug!("Getting data length.");
             let data_length: Option<u32> = row.get("DATA_LENGTH")?;
+            debug!("Getting character length.");
+            let char_length: Option<u32> = row.get("CHAR_LENGTH")?;
             debug!("Getting data precision.");
             let data_precision: Option<u32> = row.get("DATA_PRECISION")?;
             debug!("Getting nullable.");
             let nullable: bool = "Y" == nullable_str;
 
+            debug!("Getting data scale.");
+            let data_scale: Option<i32> = row.get("DATA_SCALE")?;
+
             debug!("Converting to internal data type.");
-            let data_type = match data_type.as_str() {
-                "NUMBER" => DataType::Number(data_length.unwrap_or(0), data_precision.unwrap_or(0)),
-                "VARCHAR2" => DataType::VarChar(data_length.unwrap_or(0)),
-                "DATE" => DataType::Date,
-                "TIMESTAMP(6)" => DataType::DateTime,
-                "BOOL" => DataType::Boolean,
-                "CLOB" => DataType::CLob,
-                x => return Err(Error::UnknownDataType(String::from(x))),
-            };
+            let data_type = map_data_type(
+                &data_type,
+                data_length,
+                char_length,
+                data_precision,
+                data_scale,
+            );
 
             debug!("Pushing result structure into return vector.");
             result_vec.push(ColumnDefinition {
@@ -124,8 +545,118 @@ impl ColumnDataProvider for oracle::Connection {
         }
 
         debug!("Row iteration completed.");
+
+        // without an owner the same table name may exist in several schemas
+        if owners.len() > 1 {
+            return Err(Error::AmbiguousTable(t_name, owners.into_iter().collect()));
+        }
+
         Ok(result_vec)
     }
+
+    fn table_exists(&self, table_name: &TableName) -> Result<bool> {
+        let link = table_name.link_suffix();
+        let owner = table_name.owner().map(String::from);
+        let t_name = String::from(table_name.table());
+
+        let count: u64 = match &owner {
+            None => self.query_row_as(
+                &format!(
+                    r#"SELECT COUNT(*) FROM ALL_OBJECTS{} WHERE OBJECT_NAME=:1 AND OBJECT_TYPE IN ('TABLE', 'VIEW')"#,
+                    link
+                ),
+                &[&t_name],
+            )?,
+            Some(o) => self.query_row_as(
+                &format!(
+                    r#"SELECT COUNT(*) FROM ALL_OBJECTS{} WHERE OBJECT_NAME=:1 AND OWNER=:2 AND OBJECT_TYPE IN ('TABLE', 'VIEW')"#,
+                    link
+                ),
+                &[&t_name, o],
+            )?,
+        };
+
+        Ok(count > 0)
+    }
+
+    fn query_constraints(&self, table_name: &TableName) -> Result<Vec<Constraint>> {
+        let link = table_name.link_suffix();
+        let owner = table_name.owner().map(String::from);
+        let t_name = String::from(table_name.table());
+
+        let query = match &owner {
+            None => format!(
+                r#"SELECT C.CONSTRAINT_NAME, C.CONSTRAINT_TYPE, CC.COLUMN_NAME FROM ALL_CONSTRAINTS{0} C JOIN ALL_CONS_COLUMNS{0} CC ON CC.OWNER=C.OWNER AND CC.CONSTRAINT_NAME=C.CONSTRAINT_NAME WHERE C.TABLE_NAME=:1 AND C.CONSTRAINT_TYPE IN ('P', 'U') ORDER BY C.CONSTRAINT_TYPE, C.CONSTRAINT_NAME, CC.POSITION"#,
+                link
+            ),
+            Some(_) => format!(
+                r#"SELECT C.CONSTRAINT_NAME, C.CONSTRAINT_TYPE, CC.COLUMN_NAME FROM ALL_CONSTRAINTS{0} C JOIN ALL_CONS_COLUMNS{0} CC ON CC.OWNER=C.OWNER AND CC.CONSTRAINT_NAME=C.CONSTRAINT_NAME WHERE C.TABLE_NAME=:1 AND C.OWNER=:2 AND C.CONSTRAINT_TYPE IN ('P', 'U') ORDER BY C.CONSTRAINT_TYPE, C.CONSTRAINT_NAME, CC.POSITION"#,
+                link
+            ),
+        };
+        debug!("Attempting query: {}", query);
+
+        let rows = match &owner {
+            None => self.query(&query, &[&t_name])?,
+            Some(o) => self.query(&query, &[&t_name, o])?,
+        };
+
+        let mut constraints: Vec<Constraint> = Vec::new();
+        for row_result in rows {
+            let row = row_result?;
+            let name: String = row.get("CONSTRAINT_NAME")?;
+            let constraint_type: String = row.get("CONSTRAINT_TYPE")?;
+            let column_name: String = row.get("COLUMN_NAME")?;
+
+            // rows of a constraint are adjacent and ordered by position
+            match constraints.last_mut() {
+                Some(c) if c.name == name => c.columns.push(column_name),
+                _ => constraints.push(Constraint {
+                    name,
+                    kind: if constraint_type == "P" {
+                        ConstraintKind::PrimaryKey
+                    } else {
+                        ConstraintKind::Unique
+                    },
+                    columns: vec![column_name],
+                }),
+            }
+        }
+
+        Ok(constraints)
+    }
+
+    fn query_expression_data(
+        &self,
+        table_name: &TableName,
+        expressions: &[(String, String)],
+    ) -> Result<Vec<ColumnDefinition>> {
+        let select_list: Vec<String> = expressions
+            .iter()
+            .map(|(expression, column_name)| {
+                format!("{} AS {}", expression, quote_identifier(column_name))
+            })
+            .collect();
+        // the statement is only executed to describe its result columns
+        let query = format!(
+            "SELECT {} FROM {} WHERE 1=0",
+            select_list.join(","),
+            table_name.reference()
+        );
+        debug!("Describing expressions with query: {}", query);
+
+        let rows = self.query(&query, &[])?;
+
+        Ok(rows
+            .column_info()
+            .iter()
+            .map(|info| ColumnDefinition {
+                column_name: String::from(info.name()),
+                nullable: info.nullable(),
+                data_type: map_oracle_type(info.oracle_type()),
+            })
+            .collect())
+    }
 }
 
 impl DataRowProvider for oracle::Connection {
@@ -133,73 +664,27 @@ impl DataRowProvider for oracle::Connection {
     /// queries data from database
     fn query_data<'row>(
         &self,
-        table_name: &str,
-        column_names: Rc<BTreeMap<String, ColumnDefinition>>,
+        table_name: &TableName,
+        column_names: Arc<Vec<ColumnDefinition>>,
+        options: &QueryOptions,
     ) -> Result<Vec<DataRow>> {
-        // collect column names into comma separated string
-        let column_str: String = column_names
-            .values()
-            .map(|s| s.column_name.as_str())
-            .collect::<Vec<&str>>()
-            .join(",");
-        // build query
-        let query: String = format!(r#"SELECT {} FROM {}"#, column_str, table_name);
+        let query: String = select_statement(table_name, &column_names, options);
+        info!("Executing query: {}", query);
 
         // query data from database
-        let rows = self.query(&query, &[])?;
+        let mut statement = prepare(self, &query, options)?;
+        let rows = statement.query(&bind_params(options))?;
 
         let mut result_vec: Vec<DataRow> = Vec::new();
 
-        for row_result in rows {
-            let row = row_result?;
-            let values_result: Result<Vec<Option<ColumnValue>>> = column_names
-                .values()
-                .map(|col_item| {
-                    Ok(match col_item.data_type {
-                        DataType::VarChar(_) | DataType::CLob => {
-                            let data: Option<String> = row.get(col_item.column_name.as_str())?;
-
-                            match data {
-                                Some(v) => Some(ColumnValue::Varchar(v)),
-                                None => None,
-                            }
-                        }
-                        DataType::Number(_, precision) => {
-                            if precision > 0 {
-                                let data: Option<f64> = row.get(col_item.column_name.as_str())?;
-                                match data {
-                                    Some(v) => Some(ColumnValue::Float(v)),
-                                    None => None,
-                                }
-                            } else {
-                                let data: Option<i64> = row.get(col_item.column_name.as_str())?;
-                                match data {
-                                    Some(v) => Some(ColumnValue::Number(v)),
-                                    None => None,
-                                }
-                            }
-                        }
-                        DataType::Boolean => {
-                            let data: Option<bool> = row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::Boolean)
-                        }
-                        DataType::Date => {
-                            let data: Option<DateTime<Utc>> =
-                                row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::Date)
-                        }
-                        DataType::DateTime => {
-                            let data: Option<DateTime<Utc>> =
-                                row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::DateTime)
-                        }
-                    })
-                })
-                .collect();
-            let column_values: Vec<Option<ColumnValue>> = values_result?;
+        for (read, row_result) in rows.enumerate() {
+            let column_values = read_row(
+                row_result,
+                table_name,
+                read as u64 + 1,
+                &column_names,
+                options,
+            )?;
 
             result_vec.push(DataRow {
                 column_defs: column_names.clone(),
@@ -214,97 +699,611 @@ impl DataRowProvider for oracle::Connection {
 impl ThreadedDataRowProvider for oracle::Connection {
     fn query_data_threaded(
         &self,
-        table_name: &str,
-        column_names: Rc<BTreeMap<String, ColumnDefinition>>,
-        q: Arc<RwLock<VecDeque<RowIndicator>>>,
-    ) -> Result<()> {
-        // collect column names into comma separated string
-        let column_str: String = column_names
-            .values()
-            .map(|s| s.column_name.as_str())
-            .collect::<Vec<&str>>()
-            .join(",");
-        // build query
-        let query: String = format!(r#"SELECT {} FROM {}"#, column_str, table_name);
+        table_name: &TableName,
+        column_names: Arc<Vec<ColumnDefinition>>,
+        options: &QueryOptions,
+        q: Arc<DataPipe>,
+    ) -> Result<u64> {
+        let query: String = select_statement(table_name, &column_names, options);
+        info!("Executing query: {}", query);
 
         // query data from database
-        let rows = self.query(&query, &[])?;
-
-        for row_result in rows {
-            let row = row_result?;
-            let values_result: Result<Vec<Option<ColumnValue>>> = column_names
-                .values()
-                .map(|col_item| {
-                    Ok(match col_item.data_type {
-                        DataType::VarChar(_) | DataType::CLob => {
-                            let data: Option<String> = row.get(col_item.column_name.as_str())?;
-
-                            match data {
-                                Some(v) => Some(ColumnValue::Varchar(v)),
-                                None => None,
-                            }
-                        }
-                        DataType::Number(_, precision) => {
-                            if precision > 0 {
-                                let data: Option<f64> = row.get(col_item.column_name.as_str())?;
-                                match data {
-                                    Some(v) => Some(ColumnValue::Float(v)),
-                                    None => None,
-                                }
-                            } else {
-                                let data: Option<i64> = row.get(col_item.column_name.as_str())?;
-                                match data {
-                                    Some(v) => Some(ColumnValue::Number(v)),
-                                    None => None,
-                                }
-                            }
-                        }
-                        DataType::Boolean => {
-                            let data: Option<bool> = row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::Boolean)
-                        }
-                        DataType::Date => {
-                            let data: Option<DateTime<Utc>> =
-                                row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::Date)
-                        }
-                        DataType::DateTime => {
-                            let data: Option<DateTime<Utc>> =
-                                row.get(col_item.column_name.as_str())?;
-
-                            data.map(ColumnValue::DateTime)
-                        }
-                    })
-                })
-                .collect();
-            let column_values: Vec<Option<ColumnValue>> = values_result?;
+        let mut statement = prepare(self, &query, options)?;
+        let rows = statement.query(&bind_params(options))?;
+        let mut row_count: u64 = 0;
+        let mut batch = Vec::with_capacity(options.batch_size());
 
-            match q.write() {
-                Ok(mut queue_in) => {
-                    queue_in.push_back(RowIndicator::MoreToCome(column_values));
+        for (read, row_result) in rows.enumerate() {
+            if let Some(limit) = options.memory_limit() {
+                if (read as u64).is_multiple_of(MEMORY_CHECK_INTERVAL) {
+                    wait_for_memory(&q, limit);
                 }
-                Err(e) => {
-                    error!(
-                        "Failed to push data entry because queue could not be unlocked: {}",
-                        e
-                    );
+            }
+
+            batch.push(read_row(
+                row_result,
+                table_name,
+                read as u64 + 1,
+                &column_names,
+                options,
+            )?);
+
+            if batch.len() == options.batch_size() {
+                let full = std::mem::replace(&mut batch, Vec::with_capacity(options.batch_size()));
+                if !q.push(RowIndicator::MoreToCome(full)) {
+                    return Err(Error::ChannelClosed);
                 }
-            };
+                row_count += options.batch_size() as u64;
+            }
+        }
+        let remaining = batch.len() as u64;
+        if remaining > 0 {
+            if !q.push(RowIndicator::MoreToCome(batch)) {
+                return Err(Error::ChannelClosed);
+            }
+            row_count += remaining;
+        }
+
+        if !q.push(RowIndicator::EndOfData) {
+            error!("Failed to push finalization indicator.");
+            return Err(Error::ChannelClosed);
         }
 
-        match q.write() {
-            Ok(mut queue_in) => queue_in.push_back(RowIndicator::EndOfData),
-            Err(e) => {
-                error!(
-                    "Failed to push finalization indicator. This will lead to deadlock: {}",
-                    e
-                );
-                panic!("Avoiding deadlock.");
+        Ok(row_count)
+    }
+}
+
+impl TableListProvider for oracle::Connection {
+    fn list_tables(&self, owner: Option<&str>, pattern: Option<&str>) -> Result<Vec<TableInfo>> {
+        let mut predicates: Vec<String> = Vec::new();
+        let mut params: Vec<&dyn ToSql> = Vec::new();
+        if let Some(o) = &owner {
+            params.push(o);
+            predicates.push(format!("OWNER=:{}", params.len()));
+        }
+        if let Some(p) = &pattern {
+            params.push(p);
+            predicates.push(format!("TABLE_NAME LIKE :{}", params.len()));
+        }
+
+        let query = if predicates.is_empty() {
+            String::from(
+                r#"SELECT OWNER, TABLE_NAME, NUM_ROWS FROM ALL_TABLES ORDER BY OWNER, TABLE_NAME"#,
+            )
+        } else {
+            format!(
+                r#"SELECT OWNER, TABLE_NAME, NUM_ROWS FROM ALL_TABLES WHERE {} ORDER BY OWNER, TABLE_NAME"#,
+                predicates.join(" AND ")
+            )
+        };
+        debug!("Attempting query: {}", query);
+
+        let mut result_vec: Vec<TableInfo> = Vec::new();
+        for row_result in self.query(&query, &params)? {
+            let row = row_result?;
+            result_vec.push(TableInfo {
+                owner: row.get("OWNER")?,
+                table_name: row.get("TABLE_NAME")?,
+                num_rows: row.get("NUM_ROWS")?,
+            });
+        }
+
+        Ok(result_vec)
+    }
+}
+
+impl PartitionListProvider for oracle::Connection {
+    fn list_partitions(&self, table_name: &TableName) -> Result<Vec<PartitionInfo>> {
+        let link = table_name.link_suffix();
+        let owner = table_name.owner().map(String::from);
+        let t_name = String::from(table_name.table());
+
+        let query = format!(
+            r#"SELECT TABLE_OWNER, TABLE_NAME, PARTITION_NAME, PARTITION_POSITION, HIGH_VALUE, NUM_ROWS, SUBPARTITION_COUNT FROM ALL_TAB_PARTITIONS{} WHERE TABLE_NAME=:1{} ORDER BY TABLE_OWNER, PARTITION_POSITION"#,
+            link,
+            if owner.is_some() {
+                " AND TABLE_OWNER=:2"
+            } else {
+                ""
             }
+        );
+        debug!("Attempting query: {}", query);
+
+        let rows = match &owner {
+            None => self.query(&query, &[&t_name])?,
+            Some(o) => self.query(&query, &[&t_name, o])?,
         };
 
+        let mut result_vec: Vec<PartitionInfo> = Vec::new();
+        for row_result in rows {
+            let row = row_result?;
+            result_vec.push(PartitionInfo {
+                owner: row.get("TABLE_OWNER")?,
+                table_name: row.get("TABLE_NAME")?,
+                partition_name: row.get("PARTITION_NAME")?,
+                position: row.get("PARTITION_POSITION")?,
+                high_value: row.get("HIGH_VALUE")?,
+                num_rows: row.get("NUM_ROWS")?,
+                subpartition_count: row.get("SUBPARTITION_COUNT")?,
+            });
+        }
+
+        Ok(result_vec)
+    }
+}
+
+impl SessionProvider for oracle::Connection {
+    fn initialize_session(&self, options: &SessionOptions) -> Result<()> {
+        for statement in options.statements() {
+            debug!("Initializing session: {}", statement);
+            self.execute(statement, &[])?;
+        }
+        // must be the first statement of the transaction; setup
+        // statements above are DDL and end their own transactions
+        if options.is_read_only() {
+            self.execute(r#"SET TRANSACTION READ ONLY"#, &[])?;
+        }
+        self.set_call_timeout(options.timeout())?;
+
         Ok(())
     }
 }
+
+impl DataRowSink for oracle::Connection {
+    fn insert_rows(
+        &self,
+        table_name: &TableName,
+        columns: &[ColumnDefinition],
+        rows: &[Vec<Option<ColumnValue>>],
+    ) -> Result<u64> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = insert_statement(table_name, columns);
+        debug!("Inserting {} rows: {}", rows.len(), sql);
+        let mut batch = self.batch(&sql, rows.len()).build()?;
+        for row in rows {
+            let params: Vec<&dyn ToSql> = row
+                .iter()
+                .zip(columns.iter())
+                .map(|(value, col)| insert_value(value, &col.data_type))
+                .collect();
+            batch.append_row(&params)?;
+        }
+        batch.execute()?;
+
+        Ok(rows.len() as u64)
+    }
+}
+
+impl SystemChangeNumberProvider for oracle::Connection {
+    fn current_scn(&self) -> Result<u64> {
+        Ok(self.query_row_as::<u64>(
+            r#"SELECT DBMS_FLASHBACK.GET_SYSTEM_CHANGE_NUMBER FROM DUAL"#,
+            &[],
+        )?)
+    }
+}
+
+impl TableStatisticsProvider for oracle::Connection {
+    fn estimate_rows(&self, table_name: &TableName) -> Result<Option<u64>> {
+        let link = table_name.link_suffix();
+        let owner = table_name.owner().map(String::from);
+        let t_name = String::from(table_name.table());
+
+        let rows = match &owner {
+            None => self.query(
+                &format!(
+                    r#"SELECT NUM_ROWS FROM ALL_TABLES{} WHERE TABLE_NAME=:1"#,
+                    link
+                ),
+                &[&t_name],
+            )?,
+            Some(o) => self.query(
+                &format!(
+                    r#"SELECT NUM_ROWS FROM ALL_TABLES{} WHERE TABLE_NAME=:1 AND OWNER=:2"#,
+                    link
+                ),
+                &[&t_name, o],
+            )?,
+        };
+
+        // NUM_ROWS is only set once statistics have been gathered
+        match rows.into_iter().next() {
+            Some(row_result) => Ok(row_result?.get("NUM_ROWS")?),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bind_params, insert_statement, map_data_type, map_oracle_type, select_statement};
+    use crate::definition::{
+        BindValue, ColumnDefinition, ColumnValue, DataType, QueryOptions, TableName, TablePartition,
+    };
+    use oracle::sql_type::OracleType;
+
+    ///
+    /// Maps a NUMBER column and checks whether it is read as integer
+    fn number_is_integer(precision: Option<u32>, scale: Option<i32>) -> bool {
+        map_data_type("NUMBER", Some(22), None, precision, scale).is_integer()
+    }
+
+    #[test]
+    fn test_number_with_zero_scale_is_integer() {
+        assert!(number_is_integer(Some(10), Some(0)));
+        assert!(number_is_integer(Some(18), Some(0)));
+        assert!(number_is_integer(Some(1), Some(0)));
+    }
+
+    #[test]
+    fn test_number_with_scale_is_decimal() {
+        assert!(!number_is_integer(Some(10), Some(2)));
+        assert!(!number_is_integer(None, Some(2)));
+    }
+
+    #[test]
+    fn test_number_without_precision_is_decimal() {
+        assert!(!number_is_integer(None, None));
+        // NUMBER(*,0) holds up to 38 digits
+        assert!(!number_is_integer(None, Some(0)));
+    }
+
+    #[test]
+    fn test_number_exceeding_i64_is_decimal() {
+        assert!(!number_is_integer(Some(19), Some(0)));
+        assert!(!number_is_integer(Some(38), Some(0)));
+    }
+
+    #[test]
+    fn test_number_with_negative_scale() {
+        assert!(number_is_integer(Some(5), Some(-2)));
+        assert!(!number_is_integer(Some(18), Some(-2)));
+    }
+
+    #[test]
+    fn test_select_statement_with_lower_bound() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            newer_than: Some((String::from("ID"), ColumnValue::Number(42))),
+            ..QueryOptions::default()
+        };
+
+        assert_eq!(
+            select_statement(
+                &TableName::parse("ORDERS"),
+                &columns,
+                &QueryOptions::default()
+            ),
+            r#"SELECT "ID" FROM "ORDERS""#
+        );
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" WHERE "ID" > :1"#
+        );
+
+        let options = QueryOptions {
+            changed_since_scn: Some(1000),
+            ..options
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" WHERE "ID" > :1 AND ORA_ROWSCN > :2"#
+        );
+
+        let options = QueryOptions {
+            as_of_scn: Some(2000),
+            ..options
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" AS OF SCN :1 WHERE "ID" > :2 AND ORA_ROWSCN > :3"#
+        );
+
+        let options = QueryOptions {
+            range: Some((
+                String::from("ID"),
+                BindValue::Number(100),
+                BindValue::Number(200),
+            )),
+            ..options
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" AS OF SCN :1 WHERE "ID" > :2 AND ORA_ROWSCN > :3 AND "ID" >= :4 AND "ID" < :5"#
+        );
+
+        let options = QueryOptions {
+            filter: Some(String::from("STATUS = :status")),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" WHERE (STATUS = :status)"#
+        );
+
+        let options = QueryOptions {
+            row_limit: Some(100),
+            ..options
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" WHERE (STATUS = :status) FETCH FIRST :1 ROWS ONLY"#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_db_link() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            as_of_scn: Some(2000),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(
+                &TableName::parse("SH.SALES@DWH.EXAMPLE.COM"),
+                &columns,
+                &options
+            ),
+            r#"SELECT "ID" FROM "SH"."SALES"@DWH.EXAMPLE.COM AS OF SCN :1"#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_partition() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            partition: Some(TablePartition::Partition(String::from("SALES_2024_01"))),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SH.SALES"), &columns, &options),
+            r#"SELECT "ID" FROM "SH"."SALES" PARTITION ("SALES_2024_01")"#
+        );
+
+        let options = QueryOptions {
+            partition: Some(TablePartition::Subpartition(String::from(
+                "SALES_2024_01_EU",
+            ))),
+            as_of_scn: Some(2000),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT "ID" FROM "SALES" SUBPARTITION ("SALES_2024_01_EU") AS OF SCN :1"#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_sample() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            sample: Some((10.0, None)),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT "ID" FROM "SALES" SAMPLE (10)"#
+        );
+
+        let options = QueryOptions {
+            partition: Some(TablePartition::Partition(String::from("SALES_2024_01"))),
+            sample: Some((1.5, Some(42))),
+            as_of_scn: Some(2000),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT "ID" FROM "SALES" PARTITION ("SALES_2024_01") SAMPLE (1.5) SEED (42) AS OF SCN :1"#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_type_override() {
+        let columns = vec![
+            ColumnDefinition {
+                column_name: String::from("ID"),
+                nullable: false,
+                data_type: DataType::Number(Some(10), Some(0)),
+            },
+            ColumnDefinition {
+                column_name: String::from("ZIP"),
+                nullable: true,
+                data_type: DataType::VarChar(4000),
+            },
+        ];
+
+        let options = QueryOptions {
+            type_overrides: vec![(String::from("ZIP"), DataType::VarChar(4000))],
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ADDRESSES"), &columns, &options),
+            r#"SELECT "ID",CAST("ZIP" AS VARCHAR2(4000)) AS "ZIP" FROM "ADDRESSES""#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_expression() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("KAUF_MONAT"),
+            nullable: true,
+            data_type: DataType::Date,
+        }];
+
+        let options = QueryOptions {
+            computed: vec![(
+                String::from("TRUNC(AU_KAUFDAT,'MM')"),
+                String::from("KAUF_MONAT"),
+            )],
+            type_overrides: vec![(String::from("KAUF_MONAT"), DataType::Date)],
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("AUFTRAG"), &columns, &options),
+            r#"SELECT CAST(TRUNC(AU_KAUFDAT,'MM') AS DATE) AS "KAUF_MONAT" FROM "AUFTRAG""#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_keys() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            keys: Some((
+                String::from("ID"),
+                vec![BindValue::Number(7), BindValue::Number(9)],
+            )),
+            filter: Some(String::from("STATUS = :status")),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"SELECT "ID" FROM "ORDERS" WHERE ("ID" IN (:1,:2)) AND (STATUS = :status)"#
+        );
+
+        let options = QueryOptions {
+            keys: Some((
+                String::from("ID"),
+                (0..1001).map(BindValue::Number).collect(),
+            )),
+            ..QueryOptions::default()
+        };
+        let statement = select_statement(&TableName::parse("ORDERS"), &columns, &options);
+        assert!(statement.contains(r#":1000) OR "ID" IN (:1001))"#));
+        assert_eq!(1001, bind_params(&options).len());
+    }
+
+    #[test]
+    fn test_select_statement_distinct() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("REGION"),
+            nullable: true,
+            data_type: DataType::VarChar(20),
+        }];
+
+        let options = QueryOptions {
+            distinct: true,
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT DISTINCT "REGION" FROM "SALES""#
+        );
+
+        let options = QueryOptions {
+            distinct: true,
+            row_limit: Some(10),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT DISTINCT "REGION" FROM "SALES" FETCH FIRST :1 ROWS ONLY"#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_materialized() {
+        let columns = vec![ColumnDefinition {
+            column_name: String::from("ID"),
+            nullable: false,
+            data_type: DataType::Number(Some(10), Some(0)),
+        }];
+
+        let options = QueryOptions {
+            materialize: true,
+            distinct: true,
+            filter: Some(String::from("STATUS = :status")),
+            row_limit: Some(100),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("ORDERS"), &columns, &options),
+            r#"WITH "STAGED" AS (SELECT /*+ MATERIALIZE */ DISTINCT "ID" FROM "ORDERS" WHERE (STATUS = :status) FETCH FIRST :1 ROWS ONLY) SELECT * FROM "STAGED""#
+        );
+    }
+
+    #[test]
+    fn test_select_statement_with_aggregate() {
+        let columns = vec![
+            ColumnDefinition {
+                column_name: String::from("REGION"),
+                nullable: true,
+                data_type: DataType::VarChar(20),
+            },
+            ColumnDefinition {
+                column_name: String::from("TOTAL"),
+                nullable: true,
+                data_type: DataType::Number(None, None),
+            },
+        ];
+
+        let options = QueryOptions {
+            aggregates: vec![(String::from("SUM(AMOUNT)"), String::from("TOTAL"))],
+            group_by: vec![String::from("REGION")],
+            filter: Some(String::from("AMOUNT > 0")),
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            select_statement(&TableName::parse("SALES"), &columns, &options),
+            r#"SELECT "REGION",SUM(AMOUNT) AS "TOTAL" FROM "SALES" WHERE (AMOUNT > 0) GROUP BY "REGION""#
+        );
+    }
+
+    #[test]
+    fn test_insert_statement() {
+        let column = |name: &str| ColumnDefinition {
+            column_name: String::from(name),
+            nullable: true,
+            data_type: DataType::VarChar(10),
+        };
+        let columns = vec![column("ID"), column("Name")];
+
+        assert_eq!(
+            r#"INSERT INTO "SCOTT"."EMP" ("ID","Name") VALUES (:1,:2)"#,
+            insert_statement(&TableName::parse("SCOTT.EMP"), &columns)
+        );
+    }
+
+    #[test]
+    fn test_map_oracle_type() {
+        assert!(matches!(
+            map_oracle_type(&OracleType::Number(0, -127)),
+            DataType::Number(None, None)
+        ));
+        assert!(matches!(
+            map_oracle_type(&OracleType::Number(10, 2)),
+            DataType::Number(Some(10), Some(2))
+        ));
+        assert!(matches!(
+            map_oracle_type(&OracleType::NVarchar2(30)),
+            DataType::VarChar(30)
+        ));
+    }
+}
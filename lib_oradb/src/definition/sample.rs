@@ -0,0 +1,140 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Comparison of sampled values against declared column types
+//!
+
+use super::{ColumnDefinition, ColumnValue, DataRow, DataType};
+
+/// significant digits an f64 holds without loss
+const F64_DIGITS: usize = 15;
+
+///
+/// Describes a sampled value that does not fit its declared type
+#[derive(Debug)]
+pub struct TypeDrift {
+    /// affected column
+    column_name: String,
+    /// description of the mismatch
+    description: String,
+}
+
+impl TypeDrift {
+    ///
+    /// Gets the affected column name
+    pub fn column_name(&self) -> &str {
+        &self.column_name
+    }
+
+    ///
+    /// Gets a description of the mismatch
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl std::fmt::Display for TypeDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.column_name, self.description)
+    }
+}
+
+///
+/// Counts digits in front of and after the decimal point
+fn decimal_digits(text: &str) -> (usize, usize) {
+    let digits = text.trim_start_matches('-');
+    match digits.split_once('.') {
+        Some((integer, fraction)) => (integer.trim_start_matches('0').len(), fraction.len()),
+        None => (digits.trim_start_matches('0').len(), 0),
+    }
+}
+
+///
+/// Checks a single value against its column definition
+fn check_value(column: &ColumnDefinition, value: &ColumnValue) -> Option<String> {
+    match (&column.data_type, value) {
+        (DataType::VarChar(length), ColumnValue::Varchar(text))
+            if *length > 0 && text.chars().count() > *length as usize =>
+        {
+            Some(format!(
+                "value with {} characters exceeds declared length {}",
+                text.chars().count(),
+                length
+            ))
+        }
+        (DataType::Number(_, Some(scale)), ColumnValue::Decimal(text))
+            if decimal_digits(text).1 > (*scale).max(0) as usize =>
+        {
+            Some(format!("value {} exceeds declared scale {}", text, scale))
+        }
+        (DataType::Number(_, _), ColumnValue::Float(number)) => {
+            let text = number.to_string();
+            let (integer, fraction) = decimal_digits(&text);
+            if integer + fraction >= F64_DIGITS {
+                Some(format!("value {} may have lost precision as float", text))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+///
+/// Compares sampled rows with the declared column types and
+/// reports the first mismatch of each column
+pub(crate) fn check_rows(rows: &[DataRow]) -> Vec<TypeDrift> {
+    let mut drifts: Vec<TypeDrift> = Vec::new();
+
+    for row in rows {
+        for (column, value) in row.column_defs.iter().zip(row.column_values.iter()) {
+            if drifts.iter().any(|d| d.column_name == column.column_name) {
+                continue;
+            }
+            if let Some(description) = value.as_ref().and_then(|v| check_value(column, v)) {
+                drifts.push(TypeDrift {
+                    column_name: column.column_name.clone(),
+                    description,
+                });
+            }
+        }
+    }
+
+    drifts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decimal_digits;
+
+    #[test]
+    fn test_decimal_digits() {
+        assert_eq!((3, 2), decimal_digits("123.45"));
+        assert_eq!((0, 3), decimal_digits("-0.125"));
+        assert_eq!((2, 0), decimal_digits("42"));
+    }
+}
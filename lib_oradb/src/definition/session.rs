@@ -0,0 +1,95 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Options applied to a database session before querying
+//!
+
+use std::time::Duration;
+
+///
+/// Session setup run right after connecting
+#[derive(Debug, Clone, Default)]
+pub struct SessionOptions {
+    /// statements such as `ALTER SESSION` executed in order
+    statements: Vec<String>,
+    /// whether the session runs in a read-only transaction
+    read_only: bool,
+    /// longest time a single database round trip may take
+    call_timeout: Option<Duration>,
+}
+
+impl SessionOptions {
+    ///
+    /// Constructs options that leave the session unchanged
+    pub fn new() -> SessionOptions {
+        SessionOptions::default()
+    }
+
+    ///
+    /// Adds a statement executed right after connecting
+    pub fn statement<S: AsRef<str>>(mut self, statement: S) -> Self {
+        self.statements.push(String::from(statement.as_ref()));
+
+        self
+    }
+
+    ///
+    /// Runs all queries in a read-only transaction, so the session
+    /// cannot change data even if a statement tried to
+    pub fn read_only(mut self, enabled: bool) -> Self {
+        self.read_only = enabled;
+
+        self
+    }
+
+    ///
+    /// Cancels any database round trip, such as executing a query or
+    /// fetching the next rows, taking longer than `timeout`
+    pub fn call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = Some(timeout);
+
+        self
+    }
+
+    ///
+    /// Gets the statements executed right after connecting
+    pub fn statements(&self) -> &[String] {
+        &self.statements
+    }
+
+    ///
+    /// Gets whether the session runs in a read-only transaction
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    ///
+    /// Gets the call timeout, the longest time a single round trip may take
+    pub fn timeout(&self) -> Option<Duration> {
+        self.call_timeout
+    }
+}
@@ -38,6 +38,32 @@ pub enum Error {
     UnknownDataType(String),
     /// caused by specifying an unknown column
     UnknownColumn(String),
+    /// caused by text that cannot be parsed into a column value
+    InvalidValue(String),
+    /// caused by a table name found under several owners
+    AmbiguousTable(String, Vec<String>),
+    /// caused by a table or view that does not exist or is not accessible
+    TableNotFound(String),
+    /// caused by a table or column name that cannot be quoted safely
+    InvalidIdentifier(String),
+    /// caused by a data pipe that stopped before all rows were delivered
+    ChannelClosed,
+    /// caused by rows that cannot be written out
+    SerializationError(String),
+    /// caused by a row of a table that could not be fetched;
+    /// rows are counted from 1
+    RowFetch {
+        table: String,
+        row: u64,
+        source: Box<oracle::Error>,
+    },
+    /// caused by a column value of a row that could not be read
+    ColumnRead {
+        table: String,
+        column: String,
+        row: u64,
+        source: Box<oracle::Error>,
+    },
 }
 
 impl std::error::Error for Error {
@@ -46,6 +72,14 @@ impl std::error::Error for Error {
             Error::DatabaseError(e) => Some(e),
             Error::UnknownDataType(_) => None,
             Error::UnknownColumn(_) => None,
+            Error::InvalidValue(_) => None,
+            Error::AmbiguousTable(_, _) => None,
+            Error::TableNotFound(_) => None,
+            Error::InvalidIdentifier(_) => None,
+            Error::ChannelClosed => None,
+            Error::SerializationError(_) => None,
+            Error::RowFetch { source, .. } => Some(source.as_ref()),
+            Error::ColumnRead { source, .. } => Some(source.as_ref()),
         }
     }
 }
@@ -56,6 +90,30 @@ impl std::fmt::Display for Error {
             Error::DatabaseError(e) => write!(f, "Database error: {}", e),
             Error::UnknownDataType(dt) => write!(f, "Unknown data type: {}", dt),
             Error::UnknownColumn(col) => write!(f, "Unknown column: {}", col),
+            Error::InvalidValue(v) => write!(f, "Invalid value: {}", v),
+            Error::AmbiguousTable(table, owners) => write!(
+                f,
+                "Table {} exists for several owners: {}",
+                table,
+                owners.join(", ")
+            ),
+            Error::TableNotFound(table) => write!(f, "Table not found: {}", table),
+            Error::InvalidIdentifier(name) => write!(f, "Invalid identifier: {}", name),
+            Error::ChannelClosed => write!(f, "Data pipe closed before all rows were delivered"),
+            Error::SerializationError(e) => write!(f, "Serialization error: {}", e),
+            Error::RowFetch { table, row, source } => {
+                write!(f, "Failed to fetch row {} of {}: {}", row, table, source)
+            }
+            Error::ColumnRead {
+                table,
+                column,
+                row,
+                source,
+            } => write!(
+                f,
+                "Failed to read column {} in row {} of {}: {}",
+                column, row, table, source
+            ),
         }
     }
 }
@@ -65,3 +123,9 @@ impl std::convert::From<oracle::Error> for Error {
         Error::DatabaseError(e)
     }
 }
+
+impl std::convert::From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Error {
+        Error::SerializationError(e.to_string())
+    }
+}
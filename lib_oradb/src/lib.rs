@@ -34,91 +34,19 @@
 extern crate chrono;
 extern crate oracle;
 extern crate serde;
+extern crate serde_json;
 #[macro_use]
 extern crate log;
 extern crate csv;
 extern crate simplelog;
 
+pub mod client;
 pub mod definition;
 mod error;
+pub mod logging;
 
+pub use self::client::Client;
 pub use self::error::Error;
 /// Result redefinition for crate
 pub type Result<E> = std::result::Result<E, Error>;
 
-#[cfg(test)]
-mod tests {
-    use crate::definition::TableSelectionBuilder;
-    use log::LevelFilter;
-    use oracle::Connection;
-    use simplelog::{Config, SimpleLogger};
-    use std::fs::read_to_string;
-
-    ///
-    /// Test building query on auftrag table
-    #[test]
-    fn test_builder() {
-        let _ = SimpleLogger::init(LevelFilter::Debug, Config::default());
-
-        info!("Reading password.");
-
-        let pwd: String = read_to_string("c:\\oracle\\pwd").expect("Failed to read key.");
-        info!("Creating builder.");
-        let builder = TableSelectionBuilder::new("AUFTRAG")
-            .with("AU_AKTNR")
-            .with("AU_NACHNAME")
-            .with("AU_KAUFDAT")
-            .with("AU_STORDAT")
-            .with("AU_MAND");
-        info!("Establishing db connection.");
-        let conn = Connection::connect("moerz", pwd, "//pora1/elkab.world")
-            .expect("Failed to connect to db.");
-
-        info!("Creating table definition.");
-        let table_def = builder.build(&conn).expect("Failed to build definition.");
-
-        println!("Table definition: {:?}", table_def);
-
-        let data = table_def.load(&conn).expect("Failed to load data.");
-        println!("Resulting data: {:?}", data);
-    }
-
-    ///
-    /// Test serialization
-    #[test]
-    fn test_csv_serialization() {
-        let _ = SimpleLogger::init(LevelFilter::Debug, Config::default());
-
-        info!("Reading password.");
-
-        let pwd: String = read_to_string("c:\\oracle\\pwd").expect("Failed to read key.");
-        info!("Creating builder.");
-        let builder = TableSelectionBuilder::new("AUFTRAG")
-            .with("AU_AKTNR")
-            .with("AU_NACHNAME")
-            .with("AU_KAUFDAT")
-            .with("AU_STORDAT")
-            .with("AU_MAND");
-        info!("Establishing db connection.");
-        let conn = Connection::connect("moerz", pwd, "//pora1/elkab.world")
-            .expect("Failed to connect to db.");
-
-        info!("Creating table definition.");
-        let table_def = builder.build(&conn).expect("Failed to build definition.");
-
-        println!("Table definition: {:?}", table_def);
-
-        let data = table_def.load(&conn).expect("Failed to load data.");
-
-        let mut csv_out =
-            csv::Writer::from_path("test.csv").expect("Failed to set up csv output file.");
-
-        csv_out
-            .serialize(data.header())
-            .expect("Failed to serialize header.");
-
-        for row in data.rows() {
-            csv_out.serialize(row).expect("Failed to serialize row.");
-        }
-    }
-}
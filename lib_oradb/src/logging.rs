@@ -0,0 +1,210 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Logger configuration shared by applications using this library
+//!
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+///
+/// Format of log lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// human readable lines as written by simplelog
+    Text,
+    /// one JSON object per line with timestamp, level, target and message
+    Json,
+}
+
+impl LogFormat {
+    ///
+    /// Parses a log format name as accepted on the command line
+    pub fn parse(name: &str) -> Option<LogFormat> {
+        match name.to_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Where and how log messages are written
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// most verbose level written
+    level: LevelFilter,
+    /// format of log lines
+    format: LogFormat,
+    /// file log lines are appended to instead of the terminal
+    file: Option<PathBuf>,
+}
+
+impl LogConfig {
+    ///
+    /// Constructs a configuration writing text lines up to `level`
+    /// to the terminal
+    pub fn new(level: LevelFilter) -> LogConfig {
+        LogConfig {
+            level,
+            format: LogFormat::Text,
+            file: None,
+        }
+    }
+
+    ///
+    /// Sets the format of log lines
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+
+        self
+    }
+
+    ///
+    /// Appends log lines to a file instead of writing them to the terminal,
+    /// keeping them apart from status output
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.file = Some(path.as_ref().to_path_buf());
+
+        self
+    }
+
+    ///
+    /// Installs the configured logger; fails if the log file cannot be
+    /// opened or a logger has been installed already
+    pub fn init(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let file = match &self.file {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None,
+        };
+
+        match (self.format, file) {
+            (LogFormat::Text, None) => {
+                simplelog::SimpleLogger::init(self.level, simplelog::Config::default())?
+            }
+            (LogFormat::Text, Some(f)) => {
+                simplelog::WriteLogger::init(self.level, simplelog::Config::default(), f)?
+            }
+            (LogFormat::Json, file) => {
+                log::set_boxed_logger(Box::new(JsonLogger::new(self.level, file)))?;
+                log::set_max_level(self.level);
+            }
+        };
+
+        Ok(())
+    }
+}
+
+///
+/// Logger writing one JSON object per line, to a file or to stderr
+struct JsonLogger {
+    level: LevelFilter,
+    file: Option<Mutex<File>>,
+}
+
+impl JsonLogger {
+    fn new(level: LevelFilter, file: Option<File>) -> JsonLogger {
+        JsonLogger {
+            level,
+            file: file.map(Mutex::new),
+        }
+    }
+}
+
+///
+/// Renders a log record as a single line JSON object
+fn json_line(record: &Record, timestamp: &str) -> String {
+    serde_json::json!({
+        "timestamp": timestamp,
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+    .to_string()
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = json_line(record, &chrono::Utc::now().to_rfc3339());
+        match &self.file {
+            Some(file) => {
+                if let Ok(mut f) = file.lock() {
+                    let _ = writeln!(f, "{}", line);
+                }
+            }
+            None => eprintln!("{}", line),
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(Ok(mut f)) = self.file.as_ref().map(|f| f.lock()) {
+            let _ = f.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_line, LogFormat};
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!(Some(LogFormat::Json), LogFormat::parse("JSON"));
+        assert_eq!(Some(LogFormat::Text), LogFormat::parse("text"));
+        assert_eq!(None, LogFormat::parse("xml"));
+    }
+
+    #[test]
+    fn test_json_line() {
+        let line = json_line(
+            &log::Record::builder()
+                .args(format_args!("Executing \"query\""))
+                .level(log::Level::Info)
+                .target("lib_oradb")
+                .build(),
+            "2023-01-01T00:00:00+00:00",
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!("INFO", value["level"]);
+        assert_eq!("lib_oradb", value["target"]);
+        assert_eq!("Executing \"query\"", value["message"]);
+        assert_eq!("2023-01-01T00:00:00+00:00", value["timestamp"]);
+    }
+}
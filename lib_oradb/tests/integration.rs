@@ -0,0 +1,294 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Export round trips against an Oracle database.
+//!
+//! These tests are ignored by default; run them with
+//! `cargo test -- --ignored`. See `support` for database settings.
+//!
+
+extern crate csv;
+extern crate lib_oradb;
+extern crate oracle;
+
+mod support;
+
+use lib_oradb::definition::{
+    insert_rows, ColumnValue, RowIndicator, TableName, TableSelectionBuilder,
+};
+use std::collections::BTreeMap;
+
+///
+/// Builds a table definition selecting all fixture columns
+fn fixture_builder() -> TableSelectionBuilder {
+    support::fixture_columns().into_iter().fold(
+        TableSelectionBuilder::new(support::FIXTURE_TABLE),
+        |b, c| b.with(c),
+    )
+}
+
+///
+/// Parses csv output into one map per row, keyed by header
+fn parse_csv(data: &[u8]) -> Vec<BTreeMap<String, String>> {
+    let mut reader = csv::Reader::from_reader(data);
+    let header: Vec<String> = reader
+        .headers()
+        .expect("Failed to read header.")
+        .iter()
+        .map(String::from)
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            header
+                .iter()
+                .cloned()
+                .zip(
+                    record
+                        .expect("Failed to read record.")
+                        .iter()
+                        .map(String::from),
+                )
+                .collect()
+        })
+        .collect()
+}
+
+///
+/// Checks exported rows against fixture data
+fn check_rows(rows: &[BTreeMap<String, String>]) {
+    assert_eq!(2, rows.len());
+
+    let first = rows
+        .iter()
+        .find(|r| r["C_ID"] == "1")
+        .expect("First fixture row missing.");
+    for (column, value) in support::expected_first_row() {
+        assert_eq!(value, first[column], "Unexpected value in {}", column);
+    }
+
+    let second = rows
+        .iter()
+        .find(|r| r["C_ID"] == "2")
+        .expect("Second fixture row missing.");
+    for column in support::fixture_columns().into_iter().skip(1) {
+        assert_eq!("", second[column], "Expected NULL in {}", column);
+    }
+}
+
+#[test]
+#[ignore]
+fn test_unknown_column() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let result = TableSelectionBuilder::new(support::FIXTURE_TABLE)
+        .with("C_MISSING")
+        .build(&conn);
+
+    assert!(matches!(
+        result,
+        Err(lib_oradb::Error::UnknownColumn(ref c)) if c == "C_MISSING"
+    ));
+}
+
+#[test]
+#[ignore]
+fn test_table_not_found() {
+    let conn = support::connect();
+
+    let result = TableSelectionBuilder::new("CSVDUMP_MISSING")
+        .with("C_ID")
+        .build(&conn);
+
+    assert!(matches!(
+        result,
+        Err(lib_oradb::Error::TableNotFound(ref t)) if t == "CSVDUMP_MISSING"
+    ));
+}
+
+#[test]
+#[ignore]
+fn test_primary_key() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.");
+
+    assert_eq!(Some(&[String::from("C_ID")][..]), table_def.primary_key());
+    assert!(table_def.unique_keys().is_empty());
+}
+
+#[test]
+#[ignore]
+fn test_insert_rows() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.");
+    let columns = table_def.columns().to_vec();
+    let data = table_def.load(&conn).expect("Failed to load data.");
+    let rows: Vec<Vec<Option<ColumnValue>>> =
+        data.rows().iter().map(|r| r.values().to_vec()).collect();
+
+    conn.execute(&format!("DELETE FROM {}", support::FIXTURE_TABLE), &[])
+        .expect("Failed to empty fixture table.");
+    let table_name = TableName::parse(support::FIXTURE_TABLE);
+    let inserted =
+        insert_rows(&conn, &table_name, &columns, &rows).expect("Failed to insert rows.");
+    assert_eq!(rows.len() as u64, inserted);
+
+    // rows read back equal the rows inserted, including NULL values
+    let reloaded = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.")
+        .load(&conn)
+        .expect("Failed to load data.");
+    assert_eq!(data.rows(), reloaded.rows());
+}
+
+#[test]
+#[ignore]
+fn test_all_columns_without() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = TableSelectionBuilder::new(support::FIXTURE_TABLE)
+        .all_columns()
+        .without("c_id")
+        .build(&conn)
+        .expect("Failed to build definition.");
+
+    // unnamed columns follow in table order
+    let expected: Vec<String> = support::fixture_columns()
+        .into_iter()
+        .skip(1)
+        .map(String::from)
+        .collect();
+    assert_eq!(expected, table_def.header());
+}
+
+#[test]
+#[ignore]
+fn test_export_round_trip() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.");
+    let data = table_def.load(&conn).expect("Failed to load data.");
+    for row in data.rows() {
+        assert!(matches!(row.get("C_ID"), Some(ColumnValue::Number(_))));
+        assert_eq!(row, &row.clone());
+        assert_eq!(
+            support::fixture_columns(),
+            row.iter().map(|(name, _)| name).collect::<Vec<&str>>()
+        );
+    }
+
+    let mut csv_out = csv::Writer::from_writer(Vec::new());
+    csv_out
+        .serialize(data.header())
+        .expect("Failed to serialize header.");
+    for row in data.rows() {
+        csv_out.serialize(row).expect("Failed to serialize row.");
+    }
+
+    check_rows(&parse_csv(
+        &csv_out.into_inner().expect("Failed to flush csv."),
+    ));
+}
+
+#[test]
+#[ignore]
+fn test_threaded_export_round_trip() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.");
+    let header = table_def.header();
+    let data = table_def.load_threaded().expect("Failed to load data.");
+    let row_count = data.execute(&conn).expect("Failed to execute query.");
+    assert_eq!(2, row_count);
+    assert_eq!(2, data.rows_delivered());
+    assert!(data.is_finished());
+    // both rows fit into a single batch
+    assert_eq!(2, data.queue_depth());
+
+    let mut csv_out = csv::Writer::from_writer(Vec::new());
+    csv_out
+        .serialize(header)
+        .expect("Failed to serialize header.");
+    let pipe = data.pipe();
+    let mut queue = pipe.queue().write().expect("Failed to lock pipe.");
+    while let Some(RowIndicator::MoreToCome(rows)) = queue.pop_front() {
+        for row in rows {
+            csv_out.serialize(row).expect("Failed to serialize row.");
+        }
+    }
+
+    check_rows(&parse_csv(
+        &csv_out.into_inner().expect("Failed to flush csv."),
+    ));
+}
+
+#[test]
+#[ignore]
+fn test_spawned_export_round_trip() {
+    let conn = support::connect();
+    support::create_fixture(&conn);
+
+    let table_def = fixture_builder()
+        .build(&conn)
+        .expect("Failed to build definition.");
+    let header = table_def.header();
+    let data = table_def.load_threaded().expect("Failed to load data.");
+    let handle = data.spawn(|| Ok(support::connect()));
+
+    let mut csv_out = csv::Writer::from_writer(Vec::new());
+    csv_out
+        .serialize(header)
+        .expect("Failed to serialize header.");
+    while let Some(rows) = handle.recv() {
+        for row in rows {
+            csv_out.serialize(row).expect("Failed to serialize row.");
+        }
+    }
+    assert_eq!(2, handle.join().expect("Failed to execute query."));
+
+    check_rows(&parse_csv(
+        &csv_out.into_inner().expect("Failed to flush csv."),
+    ));
+}
@@ -0,0 +1,214 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Property tests for csv serialization of column values
+//!
+
+extern crate chrono;
+extern crate csv;
+extern crate lib_oradb;
+extern crate proptest;
+
+use chrono::{TimeZone, Utc};
+use lib_oradb::definition::{ColumnValue, DataType, ValueFormat};
+use proptest::prelude::*;
+
+///
+/// Description of a generated value; column values themselves
+/// cannot be cloned, which proptest requires
+#[derive(Debug, Clone)]
+enum Sample {
+    Null,
+    Varchar(String),
+    Clob(String),
+    Integer(i64),
+    Decimal(String),
+    Float(f64),
+    Boolean(bool),
+    Date(i32, u32, u32),
+    DateTime(i32, u32, u32, u32, u32, u32),
+}
+
+impl Sample {
+    ///
+    /// Creates data type and value for the sample
+    fn typed_value(&self) -> (DataType, Option<ColumnValue>) {
+        match self {
+            Sample::Null => (DataType::VarChar(1), None),
+            Sample::Varchar(v) => (
+                DataType::VarChar(4000),
+                Some(ColumnValue::Varchar(v.clone())),
+            ),
+            Sample::Clob(v) => (DataType::CLob, Some(ColumnValue::Varchar(v.clone()))),
+            Sample::Integer(v) => (
+                DataType::Number(Some(18), Some(0)),
+                Some(ColumnValue::Number(*v)),
+            ),
+            Sample::Decimal(v) => (
+                DataType::Number(None, None),
+                Some(ColumnValue::Decimal(v.clone())),
+            ),
+            Sample::Float(v) => (DataType::BinaryDouble, Some(ColumnValue::Float(*v))),
+            Sample::Boolean(v) => (DataType::Boolean, Some(ColumnValue::Boolean(*v))),
+            Sample::Date(y, m, d) => (
+                DataType::Date,
+                Some(ColumnValue::Date(
+                    Utc.with_ymd_and_hms(*y, *m, *d, 0, 0, 0).unwrap(),
+                )),
+            ),
+            Sample::DateTime(y, m, d, h, mi, s) => (
+                DataType::DateTime,
+                Some(ColumnValue::DateTime(
+                    Utc.with_ymd_and_hms(*y, *m, *d, *h, *mi, *s).unwrap(),
+                )),
+            ),
+        }
+    }
+}
+
+///
+/// Generates samples including quotes, delimiters, newlines and extreme values
+fn sample() -> impl Strategy<Value = Sample> {
+    prop_oneof![
+        Just(Sample::Null),
+        any::<String>().prop_map(Sample::Varchar),
+        "[\"',;|\\t\\r\\n \\\\]{0,8}".prop_map(Sample::Clob),
+        any::<i64>().prop_map(Sample::Integer),
+        "-?[0-9]{1,30}(\\.[0-9]{1,8})?".prop_map(Sample::Decimal),
+        any::<f64>().prop_map(Sample::Float),
+        prop_oneof![
+            Just(f64::INFINITY),
+            Just(f64::NEG_INFINITY),
+            Just(f64::MAX),
+            Just(f64::MIN_POSITIVE),
+            Just(-0.0)
+        ]
+        .prop_map(Sample::Float),
+        any::<bool>().prop_map(Sample::Boolean),
+        (1i32..=9999, 1u32..=12, 1u32..=28).prop_map(|(y, m, d)| Sample::Date(y, m, d)),
+        (
+            1i32..=9999,
+            1u32..=12,
+            1u32..=28,
+            0u32..24,
+            0u32..60,
+            0u32..60
+        )
+            .prop_map(|(y, m, d, h, mi, s)| Sample::DateTime(y, m, d, h, mi, s)),
+    ]
+}
+
+///
+/// Generates a csv dialect and a null token
+fn dialect() -> impl Strategy<Value = (u8, csv::QuoteStyle, csv::Terminator, String)> {
+    (
+        prop_oneof![Just(b','), Just(b';'), Just(b'|'), Just(b'\t')],
+        prop_oneof![
+            Just(csv::QuoteStyle::Necessary),
+            Just(csv::QuoteStyle::Always),
+            Just(csv::QuoteStyle::NonNumeric)
+        ],
+        prop_oneof![
+            Just(csv::Terminator::CRLF),
+            Just(csv::Terminator::Any(b'\n'))
+        ],
+        prop_oneof![
+            Just(String::new()),
+            Just(String::from("\\N")),
+            Just(String::from("NULL"))
+        ],
+    )
+}
+
+///
+/// Compares values logically; NaN equals NaN
+fn same_value(expected: &Option<ColumnValue>, actual: &Option<ColumnValue>) -> bool {
+    match (expected, actual) {
+        (None, None) => true,
+        (Some(ColumnValue::Varchar(a)), Some(ColumnValue::Varchar(b))) => a == b,
+        (Some(ColumnValue::Number(a)), Some(ColumnValue::Number(b))) => a == b,
+        (Some(ColumnValue::Decimal(a)), Some(ColumnValue::Decimal(b))) => a == b,
+        (Some(ColumnValue::Float(a)), Some(ColumnValue::Float(b))) => {
+            (a.is_nan() && b.is_nan()) || a == b
+        }
+        (Some(ColumnValue::Boolean(a)), Some(ColumnValue::Boolean(b))) => a == b,
+        (Some(ColumnValue::Date(a)), Some(ColumnValue::Date(b))) => a == b,
+        (Some(ColumnValue::DateTime(a)), Some(ColumnValue::DateTime(b))) => a == b,
+        _ => false,
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_csv_round_trip(
+        samples in proptest::collection::vec(sample(), 1..8),
+        (delimiter, quote_style, terminator, null_token) in dialect(),
+    ) {
+        let row: Vec<(DataType, Option<ColumnValue>)> =
+            samples.iter().map(Sample::typed_value).collect();
+
+        // a string equal to the null token cannot be told apart from NULL
+        prop_assume!(row.iter().all(|(_, v)| match v {
+            Some(ColumnValue::Varchar(s)) => *s != null_token,
+            _ => true,
+        }));
+
+        let value_format = ValueFormat::new().null_token(&null_token);
+        let values: Vec<Option<ColumnValue>> =
+            samples.iter().map(|s| s.typed_value().1).collect();
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .quote_style(quote_style)
+            .terminator(terminator)
+            .from_writer(Vec::new());
+        writer.write_record(value_format.format_row(&values)).unwrap();
+        let data = writer.into_inner().unwrap();
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .terminator(match terminator {
+                csv::Terminator::CRLF => csv::Terminator::CRLF,
+                _ => csv::Terminator::Any(b'\n'),
+            })
+            .has_headers(false)
+            .from_reader(data.as_slice());
+        let record = reader.records().next().unwrap().unwrap();
+        prop_assert_eq!(row.len(), record.len());
+
+        for ((data_type, expected), text) in row.iter().zip(record.iter()) {
+            let actual = value_format.parse(text, data_type).unwrap();
+            prop_assert!(
+                same_value(expected, &actual),
+                "{:?} became {:?} via {:?}",
+                expected,
+                actual,
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause-FreeBSD
+ *
+ * Copyright (c) 2023 Christian Moerz. All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+//!
+//! Test support for running against an Oracle database
+//!
+//! By default a `gvenzl/oracle-xe` container is started via docker (or
+//! reused if already running). To attach to an existing database instead,
+//! set `ORADB_TEST_CONNECT`, `ORADB_TEST_USER` and `ORADB_TEST_PASSWORD`.
+//!
+
+use oracle::Connection;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Name of the container started for tests
+const CONTAINER_NAME: &str = "lib_oradb_test";
+/// Default container image
+const DEFAULT_IMAGE: &str = "gvenzl/oracle-xe:21-slim-faststart";
+/// Application user created in the container
+const CONTAINER_USER: &str = "oradb_test";
+/// Password of the application user
+const CONTAINER_PASSWORD: &str = "oradb_test";
+/// Connect string for the container
+const CONTAINER_CONNECT: &str = "//localhost:1521/XEPDB1";
+/// How long to wait for the database to come up
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Name of the fixture table
+pub const FIXTURE_TABLE: &str = "CSVDUMP_FIXTURE";
+
+///
+/// Connection settings for the test database
+struct TestDatabase {
+    connect_string: String,
+    user: String,
+    password: String,
+}
+
+impl TestDatabase {
+    ///
+    /// Reads settings from environment or falls back to container
+    fn from_env() -> TestDatabase {
+        match std::env::var("ORADB_TEST_CONNECT") {
+            Ok(connect_string) => TestDatabase {
+                connect_string,
+                user: std::env::var("ORADB_TEST_USER").expect("ORADB_TEST_USER not set."),
+                password: std::env::var("ORADB_TEST_PASSWORD")
+                    .expect("ORADB_TEST_PASSWORD not set."),
+            },
+            Err(_) => {
+                start_container();
+
+                TestDatabase {
+                    connect_string: String::from(CONTAINER_CONNECT),
+                    user: String::from(CONTAINER_USER),
+                    password: String::from(CONTAINER_PASSWORD),
+                }
+            }
+        }
+    }
+}
+
+///
+/// Starts the test container unless it is already running
+fn start_container() {
+    let running = Command::new("docker")
+        .args(["ps", "-q", "-f", &format!("name={}", CONTAINER_NAME)])
+        .output()
+        .expect("Failed to run docker.");
+    if !running.stdout.is_empty() {
+        return;
+    }
+
+    let image = std::env::var("ORADB_TEST_IMAGE").unwrap_or_else(|_| String::from(DEFAULT_IMAGE));
+    let status = Command::new("docker")
+        .args([
+            "run",
+            "-d",
+            "--rm",
+            "--name",
+            CONTAINER_NAME,
+            "-p",
+            "1521:1521",
+            "-e",
+            &format!("ORACLE_PASSWORD={}", CONTAINER_PASSWORD),
+            "-e",
+            &format!("APP_USER={}", CONTAINER_USER),
+            "-e",
+            &format!("APP_USER_PASSWORD={}", CONTAINER_PASSWORD),
+            &image,
+        ])
+        .status()
+        .expect("Failed to run docker.");
+    assert!(status.success(), "Failed to start test container.");
+}
+
+///
+/// Connects to the test database, waiting for it to come up
+pub fn connect() -> Connection {
+    let db = TestDatabase::from_env();
+    let started = Instant::now();
+
+    loop {
+        match Connection::connect(&db.user, &db.password, &db.connect_string) {
+            Ok(conn) => return conn,
+            Err(e) if started.elapsed() < STARTUP_TIMEOUT => {
+                println!("Waiting for test database: {}", e);
+                std::thread::sleep(Duration::from_secs(5));
+            }
+            Err(e) => panic!("Failed to connect to test database: {}", e),
+        }
+    }
+}
+
+///
+/// (Re-)creates the fixture table covering all supported data types.
+/// The first row has values in every column, the second only an id.
+pub fn create_fixture(conn: &Connection) {
+    let drop = format!(
+        "BEGIN EXECUTE IMMEDIATE 'DROP TABLE {}'; \
+         EXCEPTION WHEN OTHERS THEN IF SQLCODE != -942 THEN RAISE; END IF; END;",
+        FIXTURE_TABLE
+    );
+    conn.execute(&drop, &[])
+        .expect("Failed to drop fixture table.");
+
+    // BOOLEAN columns require Oracle 23 and are not covered here
+    let create = format!(
+        "CREATE TABLE {} (
+            C_ID NUMBER(10,0) NOT NULL PRIMARY KEY,
+            C_DECIMAL NUMBER(12,2),
+            C_NUMBER NUMBER,
+            C_FLOAT FLOAT,
+            C_BFLOAT BINARY_FLOAT,
+            C_BDOUBLE BINARY_DOUBLE,
+            C_VARCHAR VARCHAR2(20),
+            C_NVARCHAR NVARCHAR2(10),
+            C_NCHAR NCHAR(3),
+            C_CLOB CLOB,
+            C_NCLOB NCLOB,
+            C_DATE DATE,
+            C_TIMESTAMP TIMESTAMP(6)
+        )",
+        FIXTURE_TABLE
+    );
+    conn.execute(&create, &[])
+        .expect("Failed to create fixture table.");
+
+    let insert = format!(
+        "INSERT INTO {} VALUES (1, 1234567890.12, 0.1, 1.5, 2.5, 3.25, 'hello, \"world\"', \
+         N'gr\u{fc}\u{df}e', N'abc', 'clob text', N'nclob text', DATE '2020-02-29', \
+         TIMESTAMP '2020-02-29 13:14:15')",
+        FIXTURE_TABLE
+    );
+    conn.execute(&insert, &[])
+        .expect("Failed to insert fixture row.");
+    conn.execute(
+        &format!("INSERT INTO {} (C_ID) VALUES (2)", FIXTURE_TABLE),
+        &[],
+    )
+    .expect("Failed to insert fixture row.");
+    conn.commit().expect("Failed to commit fixture.");
+}
+
+///
+/// Names of all fixture columns
+pub fn fixture_columns() -> Vec<&'static str> {
+    vec![
+        "C_ID",
+        "C_DECIMAL",
+        "C_NUMBER",
+        "C_FLOAT",
+        "C_BFLOAT",
+        "C_BDOUBLE",
+        "C_VARCHAR",
+        "C_NVARCHAR",
+        "C_NCHAR",
+        "C_CLOB",
+        "C_NCLOB",
+        "C_DATE",
+        "C_TIMESTAMP",
+    ]
+}
+
+///
+/// Expected csv values of the first fixture row
+pub fn expected_first_row() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("C_ID", "1"),
+        ("C_DECIMAL", "1234567890.12"),
+        ("C_NUMBER", "0.1"),
+        ("C_FLOAT", "1.5"),
+        ("C_BFLOAT", "2.5"),
+        ("C_BDOUBLE", "3.25"),
+        ("C_VARCHAR", "hello, \"world\""),
+        ("C_NVARCHAR", "gr\u{fc}\u{df}e"),
+        ("C_NCHAR", "abc"),
+        ("C_CLOB", "clob text"),
+        ("C_NCLOB", "nclob text"),
+        ("C_DATE", "2020-02-29"),
+        ("C_TIMESTAMP", "2020-02-29 13:14:15"),
+    ]
+}